@@ -0,0 +1,50 @@
+CREATE TABLE flavors (
+    id TEXT PRIMARY KEY,
+    display_name TEXT NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active INTEGER NOT NULL DEFAULT 1 CHECK (is_active IN (0, 1))
+);
+
+INSERT INTO flavors (id, display_name, sort_order) VALUES
+    ('tsubuan', 'つぶあん', 1),
+    ('custard', 'カスタード', 2),
+    ('kurikinton', '栗きんとん', 3);
+
+CREATE TABLE order_items_new (
+    order_id INTEGER NOT NULL,
+    flavor TEXT NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (order_id, flavor),
+    FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (flavor) REFERENCES flavors(id)
+);
+INSERT INTO order_items_new (order_id, flavor, quantity)
+    SELECT order_id, flavor, quantity FROM order_items;
+DROP TABLE order_items;
+ALTER TABLE order_items_new RENAME TO order_items;
+
+CREATE TABLE stock_new (
+    flavor TEXT PRIMARY KEY,
+    unallocated_quantity INTEGER NOT NULL CHECK (unallocated_quantity >= 0),
+    FOREIGN KEY (flavor) REFERENCES flavors(id)
+);
+INSERT INTO stock_new (flavor, unallocated_quantity)
+    SELECT flavor, unallocated_quantity FROM stock;
+DROP TABLE stock;
+ALTER TABLE stock_new RENAME TO stock;
+
+CREATE TABLE flavor_configs_new (
+    flavor TEXT PRIMARY KEY,
+    cooking_time_minutes INTEGER NOT NULL CHECK (cooking_time_minutes > 0),
+    quantity_per_batch INTEGER NOT NULL CHECK (quantity_per_batch > 0),
+    FOREIGN KEY (flavor) REFERENCES flavors(id)
+);
+INSERT INTO flavor_configs_new (flavor, cooking_time_minutes, quantity_per_batch)
+    SELECT flavor, cooking_time_minutes, quantity_per_batch FROM flavor_configs;
+-- Previously the defaults lived in code and were only written on the first mutation.
+INSERT OR IGNORE INTO flavor_configs_new (flavor, cooking_time_minutes, quantity_per_batch) VALUES
+    ('tsubuan', 15, 9),
+    ('custard', 15, 9),
+    ('kurikinton', 15, 2);
+DROP TABLE flavor_configs;
+ALTER TABLE flavor_configs_new RENAME TO flavor_configs;
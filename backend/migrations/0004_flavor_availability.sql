@@ -0,0 +1,2 @@
+ALTER TABLE flavor_configs ADD COLUMN daily_limit INTEGER CHECK (daily_limit IS NULL OR daily_limit >= 0);
+ALTER TABLE flavor_configs ADD COLUMN is_sold_out INTEGER NOT NULL DEFAULT 0 CHECK (is_sold_out IN (0, 1));
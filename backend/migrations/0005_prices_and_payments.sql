@@ -0,0 +1,5 @@
+ALTER TABLE flavors ADD COLUMN unit_price INTEGER NOT NULL DEFAULT 0 CHECK (unit_price >= 0);
+
+ALTER TABLE orders ADD COLUMN total_price INTEGER NOT NULL DEFAULT 0 CHECK (total_price >= 0);
+ALTER TABLE orders ADD COLUMN payment_method TEXT CHECK (payment_method IS NULL OR payment_method IN ('cash', 'qr_code', 'ticket'));
+ALTER TABLE orders ADD COLUMN refunded_at TEXT;
@@ -0,0 +1,18 @@
+CREATE TABLE pricing_rules (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('bundle', 'percentage_discount', 'fixed_discount')),
+    bundle_quantity INTEGER CHECK (bundle_quantity IS NULL OR bundle_quantity >= 2),
+    bundle_price INTEGER CHECK (bundle_price IS NULL OR bundle_price >= 0),
+    discount_percent INTEGER CHECK (discount_percent IS NULL OR discount_percent BETWEEN 1 AND 100),
+    discount_amount INTEGER CHECK (discount_amount IS NULL OR discount_amount > 0),
+    is_active INTEGER NOT NULL DEFAULT 1 CHECK (is_active IN (0, 1)),
+    CHECK (
+        (kind = 'bundle' AND bundle_quantity IS NOT NULL AND bundle_price IS NOT NULL AND discount_percent IS NULL AND discount_amount IS NULL)
+        OR (kind = 'percentage_discount' AND bundle_quantity IS NULL AND bundle_price IS NULL AND discount_percent IS NOT NULL AND discount_amount IS NULL)
+        OR (kind = 'fixed_discount' AND bundle_quantity IS NULL AND bundle_price IS NULL AND discount_percent IS NULL AND discount_amount IS NOT NULL)
+    )
+);
+
+ALTER TABLE orders ADD COLUMN pricing_rule TEXT REFERENCES pricing_rules(code);
+ALTER TABLE orders ADD COLUMN discount_amount INTEGER NOT NULL DEFAULT 0 CHECK (discount_amount >= 0);
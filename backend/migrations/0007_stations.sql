@@ -0,0 +1,7 @@
+CREATE TABLE stations (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    plate_count INTEGER NOT NULL CHECK (plate_count >= 0)
+);
+
+ALTER TABLE flavor_configs ADD COLUMN station_id INTEGER REFERENCES stations(id);
@@ -0,0 +1,7 @@
+CREATE TABLE batches (
+    id INTEGER PRIMARY KEY,
+    flavor TEXT NOT NULL REFERENCES flavors(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    started_at TEXT NOT NULL,
+    finished_at TEXT
+);
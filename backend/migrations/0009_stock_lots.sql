@@ -0,0 +1,15 @@
+CREATE TABLE stock_lots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    flavor TEXT NOT NULL REFERENCES flavors(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    produced_at TEXT NOT NULL
+);
+
+CREATE TABLE stock_adjustments (
+    id INTEGER PRIMARY KEY,
+    flavor TEXT NOT NULL REFERENCES flavors(id),
+    delta INTEGER NOT NULL CHECK (delta <> 0),
+    reason TEXT NOT NULL CHECK (reason IN ('dropped', 'burnt', 'stale', 'miscount', 'other')),
+    note TEXT,
+    recorded_at TEXT NOT NULL
+);
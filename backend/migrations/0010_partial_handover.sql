@@ -0,0 +1,2 @@
+ALTER TABLE order_items ADD COLUMN handed_over_quantity INTEGER NOT NULL DEFAULT 0
+    CHECK (handed_over_quantity >= 0 AND handed_over_quantity <= quantity);
@@ -0,0 +1,3 @@
+ALTER TABLE orders ADD COLUMN display_number TEXT NOT NULL DEFAULT '';
+
+UPDATE orders SET display_number = printf('%02d', id % 100);
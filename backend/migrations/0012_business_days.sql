@@ -0,0 +1,9 @@
+CREATE TABLE business_days (
+    id INTEGER PRIMARY KEY,
+    business_date TEXT NOT NULL,
+    opened_at TEXT NOT NULL,
+    closed_at TEXT,
+    stock_disposition TEXT CHECK (stock_disposition IN ('carry_over', 'write_off'))
+);
+
+ALTER TABLE orders ADD COLUMN business_day_id INTEGER REFERENCES business_days(id);
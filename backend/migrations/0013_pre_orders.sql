@@ -0,0 +1,2 @@
+ALTER TABLE orders ADD COLUMN pickup_at TEXT;
+ALTER TABLE orders ADD COLUMN release_at TEXT;
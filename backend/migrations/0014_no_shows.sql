@@ -0,0 +1,35 @@
+-- no-transaction
+-- The status CHECK has to be widened, which in SQLite means rebuilding the table. Foreign keys are
+-- switched off so dropping the old table does not cascade to order_items and the logs, and that
+-- only works outside a transaction.
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE orders_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    status TEXT NOT NULL CHECK (status IN ('waiting', 'cooking', 'ready', 'completed', 'cancelled', 'expired')),
+    ordered_at TEXT NOT NULL,
+    ready_at TEXT,
+    completed_at TEXT,
+    is_priority INTEGER NOT NULL DEFAULT 0 CHECK (is_priority IN (0, 1)),
+    total_price INTEGER NOT NULL DEFAULT 0 CHECK (total_price >= 0),
+    payment_method TEXT CHECK (payment_method IS NULL OR payment_method IN ('cash', 'qr_code', 'ticket')),
+    refunded_at TEXT,
+    pricing_rule TEXT REFERENCES pricing_rules(code),
+    discount_amount INTEGER NOT NULL DEFAULT 0 CHECK (discount_amount >= 0),
+    display_number TEXT NOT NULL DEFAULT '',
+    business_day_id INTEGER REFERENCES business_days(id),
+    pickup_at TEXT,
+    release_at TEXT,
+    no_show_at TEXT,
+    expired_at TEXT
+);
+INSERT INTO orders_new (id, status, ordered_at, ready_at, completed_at, is_priority, total_price, payment_method, refunded_at, pricing_rule, discount_amount, display_number, business_day_id, pickup_at, release_at)
+    SELECT id, status, ordered_at, ready_at, completed_at, is_priority, total_price, payment_method, refunded_at, pricing_rule, discount_amount, display_number, business_day_id, pickup_at, release_at FROM orders;
+DROP TABLE orders;
+ALTER TABLE orders_new RENAME TO orders;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,7 @@
+ALTER TABLE orders ADD COLUMN priority TEXT NOT NULL DEFAULT 'normal'
+    CHECK (priority IN ('vip', 'staff', 'accessibility', 'normal'));
+
+-- Priority orders used to overtake everything, which the top tier still does.
+UPDATE orders SET priority = 'vip' WHERE is_priority = 1;
+
+ALTER TABLE orders DROP COLUMN is_priority;
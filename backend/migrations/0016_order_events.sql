@@ -0,0 +1,23 @@
+CREATE TABLE order_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- NULL for production and stock events, which belong to no order.
+    order_id INTEGER REFERENCES orders(id),
+    kind TEXT NOT NULL CHECK (kind IN ('status_changed', 'priority_changed', 'notification_registered', 'notification_cancelled', 'production_reported', 'stock_adjusted')),
+    actor_kind TEXT NOT NULL CHECK (actor_kind IN ('staff', 'discord', 'line', 'system')),
+    actor_id TEXT,
+    flavor TEXT,
+    before_value TEXT,
+    after_value TEXT,
+    recorded_at TEXT NOT NULL
+);
+CREATE INDEX order_events_order_id ON order_events (order_id, id);
+
+-- The trail is append-only.
+CREATE TRIGGER order_events_no_update BEFORE UPDATE ON order_events
+BEGIN
+    SELECT RAISE(ABORT, 'order_events is append-only');
+END;
+CREATE TRIGGER order_events_no_delete BEFORE DELETE ON order_events
+BEGIN
+    SELECT RAISE(ABORT, 'order_events is append-only');
+END;
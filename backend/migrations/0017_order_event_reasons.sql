@@ -0,0 +1,2 @@
+-- Why an order changed when no one asked for it; NULL for changes made on request.
+ALTER TABLE order_events ADD COLUMN reason TEXT CHECK (reason IN ('business_day_closed'));
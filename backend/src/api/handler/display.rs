@@ -3,12 +3,17 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
 };
+use chrono::Utc;
 use tracing::error;
 
 use crate::{
     api::model::{DisplayOrder, DisplayOrdersResponse, OrderDetailsResponse, WaitTimeResponse},
     app::AppRegistry,
-    domain::snapshot::OrderStatus,
+    domain::{
+        order_number::DisplayOrderNumber,
+        pre_order::PickupSlot,
+        snapshot::{MenuFlavor, OrderStatus},
+    },
 };
 
 /// GET /api/orders/display
@@ -24,25 +29,26 @@ use crate::{
 pub async fn get_display_orders(
     State(registry): State<AppRegistry>,
 ) -> Result<Json<DisplayOrdersResponse>, StatusCode> {
-    let snapshot = registry.snapshot().await.map_err(|error| {
+    let snapshot = registry.read_snapshot().await.map_err(|error| {
         error!(?error, "failed to load display orders");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
     let orders = &snapshot.orders;
+    let now = Utc::now();
     let ready = orders
         .iter()
         .filter(|o| o.status == OrderStatus::Ready)
-        .map(|o| DisplayOrder::from_id(o.id))
+        .map(DisplayOrder::from)
         .collect();
     let cooking = orders
         .iter()
         .filter(|o| o.status == OrderStatus::Cooking)
-        .map(|o| DisplayOrder::from_id(o.id))
+        .map(DisplayOrder::from)
         .collect();
     let waiting = orders
         .iter()
-        .filter(|o| o.status == OrderStatus::Waiting)
-        .map(|o| DisplayOrder::from_id(o.id))
+        .filter(|o| o.status == OrderStatus::Waiting && !o.is_held(now))
+        .map(DisplayOrder::from)
         .collect();
     Ok(Json(DisplayOrdersResponse {
         ready,
@@ -77,6 +83,37 @@ pub async fn get_order_details(
     }
 }
 
+/// GET /api/orders/by-number/{number}
+#[utoipa::path(
+    get,
+    path = "/api/orders/by-number/{number}",
+    tag = "display",
+    params(("number" = String, Path, description = "Display number, e.g. 23 or A05")),
+    responses(
+        (status = 200, description = "Details of the active order with the number, or else the latest one", body = OrderDetailsResponse),
+        (status = 404, description = "No order has the number"),
+        (status = 500, description = "Failed to load order details"),
+    )
+)]
+pub async fn get_order_details_by_number(
+    State(registry): State<AppRegistry>,
+    Path(number): Path<String>,
+) -> Result<Json<OrderDetailsResponse>, StatusCode> {
+    let number = DisplayOrderNumber::parse_input(&number).ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(details) = registry
+        .get_order_details_by_display_number(&number)
+        .await
+        .map_err(|error| {
+            error!(?error, %number, "failed to load order details");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        Ok(Json(details))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
 /// GET /api/wait-times
 #[utoipa::path(
     get,
@@ -99,3 +136,46 @@ pub async fn get_wait_times(
             StatusCode::INTERNAL_SERVER_ERROR
         })
 }
+
+/// GET /api/pickup-slots
+#[utoipa::path(
+    get,
+    path = "/api/pickup-slots",
+    tag = "display",
+    responses(
+        (status = 200, description = "Pickup slots of the current business day that can still be booked for pre-orders", body = [PickupSlot]),
+        (status = 500, description = "Failed to load pickup slots"),
+    )
+)]
+pub async fn get_pickup_slots(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<PickupSlot>>, StatusCode> {
+    registry
+        .get_pickup_slots()
+        .await
+        .map(Json)
+        .map_err(|error| {
+            error!(?error, "failed to load pickup slots");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// GET /api/menu
+#[utoipa::path(
+    get,
+    path = "/api/menu",
+    tag = "display",
+    responses(
+        (status = 200, description = "Flavors on sale, in menu order", body = [MenuFlavor]),
+        (status = 500, description = "Failed to load menu"),
+    )
+)]
+pub async fn get_menu(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<MenuFlavor>>, StatusCode> {
+    let snapshot = registry.read_snapshot().await.map_err(|error| {
+        error!(?error, "failed to load menu");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(snapshot.active_flavors().cloned().collect()))
+}
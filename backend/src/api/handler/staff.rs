@@ -1,18 +1,45 @@
+use std::convert::Infallible;
+
 use axum::{
     Json,
     extract::{Path, Query, State},
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
 };
-use tracing::{error, info};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use tracing::{error, info, warn};
 
 use crate::{
-    api::model::{
-        CreateOrderRequest, FlavorConfigsResponse, NotifyRequest, StaffOrderResponse,
-        StaffOrdersQuery, StockResponse, UpdateOrderPriorityRequest, UpdateProductionRequest,
-        UpdateProductionResponse,
+    api::{
+        model::{
+            AdjustStockRequest, AuditTrailQuery, BusinessDaySummaryQuery, CloseBusinessDayRequest,
+            CreateFlavorRequest, CreateOrderRequest, CreatePricingRuleRequest,
+            CreateStationRequest, DiscardStockRequest, FinishBatchResponse, FlavorConfigsResponse,
+            HandOverItemsRequest, NotifyRequest, RevenueQuery, StaffEvent, StaffFlavorResponse,
+            StaffOrderResponse, StaffOrdersQuery, StartBatchRequest, StockLotResponse,
+            StockResponse, UndoRequest, UpdateFlavorActiveRequest, UpdateFlavorDailyLimitRequest,
+            UpdateFlavorPriceRequest, UpdateFlavorSoldOutRequest, UpdateOrderItemsRequest,
+            UpdateOrderPriorityRequest, UpdatePricingRuleActiveRequest, UpdateProductionRequest,
+            UpdateProductionResponse, UpdateStationPlatesRequest, WasteQuery,
+        },
+        problem::ProblemResponse,
     },
     app::AppRegistry,
-    domain::snapshot::{Flavor, FlavorConfig},
+    domain::{
+        business_day::{BusinessDay, BusinessDaySummary},
+        order_event::{OrderEvent, OrderEventKind},
+        production_plan::ProductionPlan,
+        revenue::RevenueSummary,
+        snapshot::{
+            Batch, Flavor, FlavorConfig, MenuFlavor, PricingRule, Station, StockAdjustment,
+        },
+        stock::WasteReport,
+        undo::UndoEntry,
+    },
+    usecase::{
+        business_day::BusinessDayError, menu::AddFlavorError, order::NewOrder,
+        pricing::AddPricingRuleError, production::BatchError, stock::StockError,
+    },
 };
 /// GET /api/staff/orders
 #[utoipa::path(
@@ -62,22 +89,33 @@ pub async fn get_staff_orders(
     security(("staffBearerAuth" = [])),
     responses(
         (status = 201, description = "Created order", body = StaffOrderResponse),
+        (status = 400, description = "Flavor is not on sale or the pricing rule is unknown", body = ProblemResponse, content_type = "application/problem+json"),
         (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Failed to save order"),
+        (status = 409, description = "Flavor is sold out or exceeds today's remaining quantity, or the pickup slot is full", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Items are empty, have zero or too large quantities or repeat a flavor, the pricing rule does not apply, the total is too large, or the pickup slot is too soon or not today", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save order", body = ProblemResponse, content_type = "application/problem+json"),
     )
 )]
 pub async fn create_order(
     State(registry): State<AppRegistry>,
     Json(payload): Json<CreateOrderRequest>,
-) -> Result<(StatusCode, Json<StaffOrderResponse>), StatusCode> {
+) -> Result<(StatusCode, Json<StaffOrderResponse>), ProblemResponse> {
     info!(items = ?payload.items, "creating order");
+    let priority = payload.priority_tier();
     let new_order = registry
-        .create_order(payload.items, payload.is_priority.unwrap_or(false))
+        .create_order(NewOrder {
+            items: payload.items,
+            priority,
+            payment_method: payload.payment_method,
+            pricing_rule: payload.pricing_rule,
+            pickup_at: payload.pickup_at,
+            ..Default::default()
+        })
         .await
         .map_err(|error| {
             error!(?error, "failed to save order");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+            ProblemResponse::internal_server_error()
+        })??;
     Ok((StatusCode::CREATED, Json(new_order.into())))
 }
 
@@ -103,6 +141,135 @@ pub async fn get_stock(
     Ok(Json(snapshot.unallocated_stock.into()))
 }
 
+/// GET /api/staff/stock/lots
+#[utoipa::path(
+    get,
+    path = "/api/staff/stock/lots",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Unallocated stock by production time, oldest first per flavor", body = [StockLotResponse]),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load stock"),
+    )
+)]
+pub async fn get_stock_lots(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<StockLotResponse>>, StatusCode> {
+    let lots = registry.get_stock_lots().await.map_err(|error| {
+        error!(?error, "failed to load stock");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(lots))
+}
+
+/// POST /api/staff/stock/discard
+#[utoipa::path(
+    post,
+    path = "/api/staff/stock/discard",
+    tag = "staff",
+    request_body = DiscardStockRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 201, description = "Recorded discard", body = StockAdjustment),
+        (status = 400, description = "Unknown flavor, zero quantity or a reason that is not waste"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Not enough unallocated stock"),
+        (status = 500, description = "Failed to save stock"),
+    )
+)]
+pub async fn discard_stock(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<DiscardStockRequest>,
+) -> Result<(StatusCode, Json<StockAdjustment>), StatusCode> {
+    info!(flavor = %payload.flavor, quantity = payload.quantity, reason = ?payload.reason, "discarding stock");
+    match registry
+        .discard_stock(
+            payload.flavor,
+            payload.quantity,
+            payload.reason,
+            payload.note,
+        )
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to save stock");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })? {
+        Ok(adjustment) => Ok((StatusCode::CREATED, Json(adjustment))),
+        Err(error) => Err(stock_error_status(error)),
+    }
+}
+
+/// PUT /api/staff/stock/{flavor}
+#[utoipa::path(
+    put,
+    path = "/api/staff/stock/{flavor}",
+    tag = "staff",
+    params(("flavor" = String, Path, description = "Flavor id")),
+    request_body = AdjustStockRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Recorded recount", body = StockAdjustment),
+        (status = 400, description = "Unknown flavor"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Recount matches the recorded stock"),
+        (status = 500, description = "Failed to save stock"),
+    )
+)]
+pub async fn adjust_stock(
+    State(registry): State<AppRegistry>,
+    Path(flavor): Path<Flavor>,
+    Json(payload): Json<AdjustStockRequest>,
+) -> Result<Json<StockAdjustment>, StatusCode> {
+    info!(flavor = %flavor, quantity = payload.quantity, "recounting stock");
+    match registry
+        .adjust_stock(flavor, payload.quantity, payload.note)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to save stock");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })? {
+        Ok(adjustment) => Ok(Json(adjustment)),
+        Err(error) => Err(stock_error_status(error)),
+    }
+}
+
+fn stock_error_status(error: StockError) -> StatusCode {
+    match error {
+        StockError::UnknownFlavor | StockError::ZeroQuantity | StockError::NotWasteReason => {
+            StatusCode::BAD_REQUEST
+        }
+        StockError::InsufficientStock { .. } | StockError::Unchanged => StatusCode::CONFLICT,
+    }
+}
+
+/// GET /api/staff/stock/waste
+#[utoipa::path(
+    get,
+    path = "/api/staff/stock/waste",
+    tag = "staff",
+    params(("date" = Option<String>, Query, description = "Store day (YYYY-MM-DD, JST); defaults to today")),
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Discarded pieces of the day by flavor and reason", body = WasteReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load stock"),
+    )
+)]
+pub async fn get_waste_report(
+    State(registry): State<AppRegistry>,
+    Query(query): Query<WasteQuery>,
+) -> Result<Json<WasteReport>, StatusCode> {
+    let report = registry
+        .get_waste_report(query.date)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to load stock");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(report))
+}
+
 /// POST /api/staff/production
 #[utoipa::path(
     post,
@@ -112,6 +279,7 @@ pub async fn get_stock(
     security(("staffBearerAuth" = [])),
     responses(
         (status = 200, description = "Production update result", body = UpdateProductionResponse),
+        (status = 400, description = "Item is not in the menu catalog"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Failed to save production update"),
     )
@@ -120,193 +288,1112 @@ pub async fn update_production(
     State(registry): State<AppRegistry>,
     Json(payload): Json<UpdateProductionRequest>,
 ) -> Result<Json<UpdateProductionResponse>, StatusCode> {
-    let (newly_ready_orders, unallocated_items) = registry
+    let Some((newly_ready_orders, unallocated_items)) = registry
         .update_production(payload.items)
         .await
         .map_err(|error| {
-        error!(?error, "failed to save production update");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+            error!(?error, "failed to save production update");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
     Ok(Json(UpdateProductionResponse {
         newly_ready_orders,
         unallocated_items,
     }))
 }
 
-/// POST /api/staff/orders/{id}/complete
+/// GET /api/staff/batches
+#[utoipa::path(
+    get,
+    path = "/api/staff/batches",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Batches on the grill", body = [Batch]),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load batches"),
+    )
+)]
+pub async fn get_active_batches(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<Batch>>, StatusCode> {
+    let snapshot = registry.snapshot().await.map_err(|error| {
+        error!(?error, "failed to load batches");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(snapshot.active_batches().cloned().collect()))
+}
+
+/// POST /api/staff/batches
 #[utoipa::path(
     post,
-    path = "/api/staff/orders/{id}/complete",
+    path = "/api/staff/batches",
     tag = "staff",
-    params(("id" = u32, Path, description = "Order id")),
+    request_body = StartBatchRequest,
     security(("staffBearerAuth" = [])),
     responses(
-        (status = 200, description = "Completed order", body = StaffOrderResponse),
+        (status = 201, description = "Started batch", body = Batch),
+        (status = 400, description = "Flavor is not in the menu catalog or quantity is zero"),
         (status = 401, description = "Unauthorized"),
-        (status = 404, description = "Order not found"),
-        (status = 500, description = "Failed to save completed order"),
+        (status = 500, description = "Failed to save batch"),
     )
 )]
-pub async fn complete_order(
+pub async fn start_batch(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<StartBatchRequest>,
+) -> Result<(StatusCode, Json<Batch>), StatusCode> {
+    info!(flavor = %payload.flavor, quantity = payload.quantity, "starting batch");
+    match registry
+        .start_batch(payload.flavor, payload.quantity)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to save batch");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })? {
+        Ok(batch) => Ok((StatusCode::CREATED, Json(batch))),
+        Err(BatchError::UnknownFlavor | BatchError::ZeroQuantity) => Err(StatusCode::BAD_REQUEST),
+        Err(BatchError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(BatchError::AlreadyFinished) => Err(StatusCode::CONFLICT),
+    }
+}
+
+/// GET /api/staff/events
+#[utoipa::path(
+    get,
+    path = "/api/staff/events",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Server-sent stream of staff events", content_type = "text/event-stream", body = StaffEvent),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+pub async fn get_staff_events(
+    State(registry): State<AppRegistry>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = BroadcastStream::new(registry.subscribe_staff_events()).filter_map(|event| {
+        let event = match event {
+            Ok(event) => event,
+            Err(error) => {
+                warn!(?error, "staff event stream lagged");
+                return None;
+            }
+        };
+        match Event::default().json_data(&event) {
+            Ok(event) => Some(Ok(event)),
+            Err(error) => {
+                error!(?error, "failed to serialize staff event");
+                None
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// POST /api/staff/batches/{id}/finish
+#[utoipa::path(
+    post,
+    path = "/api/staff/batches/{id}/finish",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Batch id")),
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Finished batch and the orders it made ready", body = FinishBatchResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Batch not found"),
+        (status = 409, description = "Batch is already finished"),
+        (status = 500, description = "Failed to save batch"),
+    )
+)]
+pub async fn finish_batch(
     State(registry): State<AppRegistry>,
     Path(id): Path<u32>,
-) -> Result<Json<StaffOrderResponse>, StatusCode> {
-    if let Some(order) = registry.complete_order(id).await.map_err(|error| {
-        error!(?error, order_id = id, "failed to save completed order");
+) -> Result<Json<FinishBatchResponse>, StatusCode> {
+    match registry.finish_batch(id).await.map_err(|error| {
+        error!(?error, batch_id = id, "failed to save batch");
         StatusCode::INTERNAL_SERVER_ERROR
     })? {
-        Ok(Json(order.into()))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+        Ok((batch, newly_ready_orders)) => Ok(Json(FinishBatchResponse {
+            batch,
+            newly_ready_orders,
+        })),
+        Err(BatchError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(BatchError::AlreadyFinished) => Err(StatusCode::CONFLICT),
+        Err(BatchError::UnknownFlavor | BatchError::ZeroQuantity) => Err(StatusCode::BAD_REQUEST),
     }
 }
 
-/// POST /api/staff/orders/{id}/cancel
+/// GET /api/staff/undo
+#[utoipa::path(
+    get,
+    path = "/api/staff/undo",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Staff actions that can be undone, newest first", body = [UndoEntry]),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+pub async fn get_undoable_actions(State(registry): State<AppRegistry>) -> Json<Vec<UndoEntry>> {
+    Json(registry.undoable_actions().await)
+}
+
+/// POST /api/staff/undo
 #[utoipa::path(
     post,
-    path = "/api/staff/orders/{id}/cancel",
+    path = "/api/staff/undo",
+    tag = "staff",
+    request_body = UndoRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "The action that was undone", body = UndoEntry),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No such action to undo", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "The action can no longer be undone, or is a hand-over, which never can", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save undo", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn undo(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<UndoRequest>,
+) -> Result<Json<UndoEntry>, ProblemResponse> {
+    info!(entry_id = ?payload.id, "undoing staff action");
+    let entry = registry.undo(payload.id).await.map_err(|error| {
+        error!(?error, "failed to save undo");
+        ProblemResponse::internal_server_error()
+    })??;
+    Ok(Json(entry))
+}
+
+/// GET /api/staff/audit-trail
+#[utoipa::path(
+    get,
+    path = "/api/staff/audit-trail",
+    tag = "staff",
+    params(
+        ("from" = Option<String>, Query, description = "Recorded at or after (RFC 3339)"),
+        ("to" = Option<String>, Query, description = "Recorded before (RFC 3339)"),
+        ("kind" = Option<OrderEventKind>, Query, description = "Only events of this kind"),
+    ),
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Recorded events of all orders, production and stock, oldest first", body = [OrderEvent]),
+        (status = 400, description = "Invalid query"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load audit trail"),
+    )
+)]
+pub async fn get_audit_trail(
+    State(registry): State<AppRegistry>,
+    Query(query): Query<AuditTrailQuery>,
+) -> Result<Json<Vec<OrderEvent>>, StatusCode> {
+    let events = registry
+        .get_audit_trail(query.into())
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to load audit trail");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(events))
+}
+
+/// GET /api/staff/orders/{id}/history
+#[utoipa::path(
+    get,
+    path = "/api/staff/orders/{id}/history",
     tag = "staff",
     params(("id" = u32, Path, description = "Order id")),
     security(("staffBearerAuth" = [])),
     responses(
-        (status = 200, description = "Cancelled order", body = StaffOrderResponse),
+        (status = 200, description = "Recorded changes of the order, oldest first", body = [OrderEvent]),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Order not found"),
-        (status = 500, description = "Failed to save cancelled order"),
+        (status = 500, description = "Failed to load order history"),
     )
 )]
-pub async fn cancel_order(
+pub async fn get_order_history(
     State(registry): State<AppRegistry>,
     Path(id): Path<u32>,
-) -> Result<Json<StaffOrderResponse>, StatusCode> {
-    if let Some(order) = registry.cancel_order(id).await.map_err(|error| {
-        error!(?error, order_id = id, "failed to save cancelled order");
+) -> Result<Json<Vec<OrderEvent>>, StatusCode> {
+    let history = registry.get_order_history(id).await.map_err(|error| {
+        error!(?error, order_id = id, "failed to load order history");
         StatusCode::INTERNAL_SERVER_ERROR
-    })? {
-        Ok(Json(order.into()))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+    })?;
+    history.map(Json).ok_or(StatusCode::NOT_FOUND)
 }
 
-/// PUT /api/staff/orders/{id}/priority
+/// POST /api/staff/orders/{id}/complete
 #[utoipa::path(
-    put,
-    path = "/api/staff/orders/{id}/priority",
+    post,
+    path = "/api/staff/orders/{id}/complete",
     tag = "staff",
     params(("id" = u32, Path, description = "Order id")),
-    request_body = UpdateOrderPriorityRequest,
     security(("staffBearerAuth" = [])),
     responses(
-        (status = 200, description = "Updated order", body = StaffOrderResponse),
+        (status = 200, description = "Completed order", body = StaffOrderResponse),
         (status = 401, description = "Unauthorized"),
-        (status = 404, description = "Order not found"),
-        (status = 500, description = "Failed to save order priority update"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order is already completed or cancelled", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save completed order", body = ProblemResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn update_order_priority(
+pub async fn complete_order(
     State(registry): State<AppRegistry>,
     Path(id): Path<u32>,
-    Json(payload): Json<UpdateOrderPriorityRequest>,
-) -> Result<Json<StaffOrderResponse>, StatusCode> {
-    if let Some(order) = registry
-        .update_order_priority(id, payload.is_priority)
-        .await
-        .map_err(|error| {
-            error!(
-                ?error,
-                order_id = id,
-                "failed to save order priority update"
-            );
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-    {
-        Ok(Json(order.into()))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    let order = registry.complete_order(id).await.map_err(|error| {
+        error!(?error, order_id = id, "failed to save completed order");
+        ProblemResponse::internal_server_error()
+    })??;
+    Ok(Json(order.into()))
 }
 
-/// PUT /api/orders/{id}/notification
+/// POST /api/staff/orders/{id}/cancel
 #[utoipa::path(
-    put,
-    path = "/api/staff/orders/{id}/notification",
+    post,
+    path = "/api/staff/orders/{id}/cancel",
     tag = "staff",
     params(("id" = u32, Path, description = "Order id")),
-    request_body = NotifyRequest,
     security(("staffBearerAuth" = [])),
     responses(
-        (status = 200, description = "Updated order notification", body = StaffOrderResponse),
+        (status = 200, description = "Cancelled order", body = StaffOrderResponse),
         (status = 401, description = "Unauthorized"),
-        (status = 404, description = "Order not found"),
-        (status = 500, description = "Failed to save notification update"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order is already completed or cancelled", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save cancelled order", body = ProblemResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn add_notification(
+pub async fn cancel_order(
     State(registry): State<AppRegistry>,
     Path(id): Path<u32>,
-    Json(payload): Json<NotifyRequest>,
-) -> Result<Json<StaffOrderResponse>, StatusCode> {
-    if let Some(order) = registry
-        .add_notification(id, payload.into())
-        .await
-        .map_err(|error| {
-            error!(?error, order_id = id, "failed to save notification update");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-    {
-        Ok(Json(order.into()))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    let order = registry.cancel_order(id).await.map_err(|error| {
+        error!(?error, order_id = id, "failed to save cancelled order");
+        ProblemResponse::internal_server_error()
+    })??;
+    Ok(Json(order.into()))
 }
-/// GET /api/staff/flavors/config
+
+/// POST /api/staff/orders/{id}/reopen
 #[utoipa::path(
-    get,
-    path = "/api/staff/flavors/config",
+    post,
+    path = "/api/staff/orders/{id}/reopen",
     tag = "staff",
+    params(("id" = u32, Path, description = "Order id")),
     security(("staffBearerAuth" = [])),
     responses(
-        (status = 200, description = "Flavor configs", body = FlavorConfigsResponse),
+        (status = 200, description = "Reopened order, queued again at its original place", body = StaffOrderResponse),
         (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Failed to load flavor configs"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order is not completed, already refunded, or every piece was handed over", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save reopened order", body = ProblemResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn get_flavor_configs(
+pub async fn reopen_order(
     State(registry): State<AppRegistry>,
-) -> Result<Json<FlavorConfigsResponse>, StatusCode> {
-    let snapshot = registry.snapshot().await.map_err(|error| {
-        error!(?error, "failed to load flavor configs");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-    Ok(Json(snapshot.flavor_configs.into()))
+    Path(id): Path<u32>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    info!(order_id = id, "reopening order");
+    let order = registry.reopen_order(id).await.map_err(|error| {
+        error!(?error, order_id = id, "failed to save reopened order");
+        ProblemResponse::internal_server_error()
+    })??;
+    Ok(Json(order.into()))
 }
 
-/// PUT /api/staff/flavors/{flavor}
+/// POST /api/staff/orders/{id}/restore
 #[utoipa::path(
-    put,
-    path = "/api/staff/flavors/{flavor}",
+    post,
+    path = "/api/staff/orders/{id}/restore",
     tag = "staff",
-    params(("flavor" = Flavor, Path, description = "Flavor")),
-    request_body = FlavorConfig,
+    params(("id" = u32, Path, description = "Order id")),
     security(("staffBearerAuth" = [])),
     responses(
-        (status = 200, description = "Flavor config updated"),
+        (status = 200, description = "Restored order, queued again at its original place", body = StaffOrderResponse),
         (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Failed to save flavor config"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order is not cancelled or expired", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save restored order", body = ProblemResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn set_flavor_config(
+pub async fn restore_order(
     State(registry): State<AppRegistry>,
-    Path(flavor): Path<Flavor>,
-    Json(config): Json<FlavorConfig>,
-) -> Result<StatusCode, StatusCode> {
-    registry
-        .set_flavor_config(flavor, config)
-        .await
-        .map_err(|error| {
-            error!(?error, ?flavor, "failed to save flavor config");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    Path(id): Path<u32>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    info!(order_id = id, "restoring order");
+    let order = registry.restore_order(id).await.map_err(|error| {
+        error!(?error, order_id = id, "failed to save restored order");
+        ProblemResponse::internal_server_error()
+    })??;
+    Ok(Json(order.into()))
+}
+
+/// POST /api/staff/orders/{id}/refund
+#[utoipa::path(
+    post,
+    path = "/api/staff/orders/{id}/refund",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Order id")),
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Refunded order", body = StaffOrderResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order is not completed or already refunded", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save refund", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn refund_order(
+    State(registry): State<AppRegistry>,
+    Path(id): Path<u32>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    info!(order_id = id, "refunding order");
+    let order = registry.refund_order(id).await.map_err(|error| {
+        error!(?error, order_id = id, "failed to save refund");
+        ProblemResponse::internal_server_error()
+    })??;
+    Ok(Json(order.into()))
+}
+
+/// GET /api/staff/production/plan
+#[utoipa::path(
+    get,
+    path = "/api/staff/production/plan",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Batches recommended to grill next", body = ProductionPlan),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load orders"),
+    )
+)]
+pub async fn get_production_plan(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<ProductionPlan>, StatusCode> {
+    let plan = registry.get_production_plan().await.map_err(|error| {
+        error!(?error, "failed to load orders");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(plan))
+}
+
+/// GET /api/staff/revenue
+#[utoipa::path(
+    get,
+    path = "/api/staff/revenue",
+    tag = "staff",
+    params(("date" = Option<String>, Query, description = "Store day (YYYY-MM-DD, JST); defaults to today")),
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Revenue summary of the day", body = RevenueSummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load orders"),
+    )
+)]
+pub async fn get_revenue_summary(
+    State(registry): State<AppRegistry>,
+    Query(query): Query<RevenueQuery>,
+) -> Result<Json<RevenueSummary>, StatusCode> {
+    let summary = registry
+        .get_revenue_summary(query.date)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to load orders");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(summary))
+}
+
+/// POST /api/staff/business-days/open
+#[utoipa::path(
+    post,
+    path = "/api/staff/business-days/open",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 201, description = "Opened business day", body = BusinessDay),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "A business day is already open"),
+        (status = 500, description = "Failed to save business day"),
+    )
+)]
+pub async fn open_business_day(
+    State(registry): State<AppRegistry>,
+) -> Result<(StatusCode, Json<BusinessDay>), StatusCode> {
+    info!("opening business day");
+    match registry.open_business_day().await.map_err(|error| {
+        error!(?error, "failed to save business day");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })? {
+        Ok(day) => Ok((StatusCode::CREATED, Json(day))),
+        Err(BusinessDayError::AlreadyOpen(_) | BusinessDayError::NotOpen) => {
+            Err(StatusCode::CONFLICT)
+        }
+    }
+}
+
+/// POST /api/staff/business-days/close
+#[utoipa::path(
+    post,
+    path = "/api/staff/business-days/close",
+    tag = "staff",
+    request_body = CloseBusinessDayRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Summary of the closed day; orders still in progress were cancelled and their customers told", body = BusinessDaySummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "No business day is open"),
+        (status = 500, description = "Failed to save business day"),
+    )
+)]
+pub async fn close_business_day(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<CloseBusinessDayRequest>,
+) -> Result<Json<BusinessDaySummary>, StatusCode> {
+    info!(stock_disposition = ?payload.stock_disposition, "closing business day");
+    match registry
+        .close_business_day(payload.stock_disposition)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to save business day");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })? {
+        Ok(summary) => Ok(Json(summary)),
+        Err(BusinessDayError::AlreadyOpen(_) | BusinessDayError::NotOpen) => {
+            Err(StatusCode::CONFLICT)
+        }
+    }
+}
+
+/// GET /api/staff/business-days/current
+#[utoipa::path(
+    get,
+    path = "/api/staff/business-days/current",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "The open business day", body = BusinessDay),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No business day is open"),
+        (status = 500, description = "Failed to load business days"),
+    )
+)]
+pub async fn get_current_business_day(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<BusinessDay>, StatusCode> {
+    let day = registry.get_current_business_day().await.map_err(|error| {
+        error!(?error, "failed to load business days");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    day.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// GET /api/staff/business-days/summary
+#[utoipa::path(
+    get,
+    path = "/api/staff/business-days/summary",
+    tag = "staff",
+    params(("date" = Option<String>, Query, description = "Business day (YYYY-MM-DD, JST); defaults to the current one")),
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Sales, waste and stock handling of the day", body = BusinessDaySummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load orders"),
+    )
+)]
+pub async fn get_business_day_summary(
+    State(registry): State<AppRegistry>,
+    Query(query): Query<BusinessDaySummaryQuery>,
+) -> Result<Json<BusinessDaySummary>, StatusCode> {
+    let summary = registry
+        .get_business_day_summary(query.date)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to load orders");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(summary))
+}
+
+/// PUT /api/staff/orders/{id}/priority
+#[utoipa::path(
+    put,
+    path = "/api/staff/orders/{id}/priority",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Order id")),
+    request_body = UpdateOrderPriorityRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated order", body = StaffOrderResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Neither priority nor isPriority is given", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save order priority update", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_order_priority(
+    State(registry): State<AppRegistry>,
+    Path(id): Path<u32>,
+    Json(payload): Json<UpdateOrderPriorityRequest>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    let priority = payload.priority_tier().ok_or_else(|| {
+        ProblemResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "missing-priority",
+            "priority is required",
+        )
+    })?;
+    let order = registry
+        .update_order_priority(id, priority)
+        .await
+        .map_err(|error| {
+            error!(
+                ?error,
+                order_id = id,
+                "failed to save order priority update"
+            );
+            ProblemResponse::internal_server_error()
+        })??;
+    Ok(Json(order.into()))
+}
+
+/// PATCH /api/staff/orders/{id}/items
+#[utoipa::path(
+    patch,
+    path = "/api/staff/orders/{id}/items",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Order id")),
+    request_body = UpdateOrderItemsRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Order with the new items, repriced and re-allocated", body = StaffOrderResponse),
+        (status = 400, description = "Flavor is not on sale", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order was already handed over or cancelled, exceeds today's remaining quantity, or no longer fits its pickup slot", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Items are empty, have zero or too large quantities or repeat a flavor, the order's pricing rule no longer applies, or the total is too large", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save order items", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_order_items(
+    State(registry): State<AppRegistry>,
+    Path(id): Path<u32>,
+    Json(payload): Json<UpdateOrderItemsRequest>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    info!(order_id = id, items = ?payload.items, "updating order items");
+    let order = registry
+        .update_order_items(id, payload.items)
+        .await
+        .map_err(|error| {
+            error!(?error, order_id = id, "failed to save order items");
+            ProblemResponse::internal_server_error()
+        })??;
+    Ok(Json(order.into()))
+}
+
+/// POST /api/staff/orders/{id}/handover
+#[utoipa::path(
+    post,
+    path = "/api/staff/orders/{id}/handover",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Order id")),
+    request_body = HandOverItemsRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Order with the handed-over part split off; completed once nothing is outstanding", body = StaffOrderResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Order was already completed or cancelled, or the pieces are not outstanding or not in stock after the orders queued ahead", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Items have zero quantities or repeat a flavor", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save handover", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn hand_over_items(
+    State(registry): State<AppRegistry>,
+    Path(id): Path<u32>,
+    Json(payload): Json<HandOverItemsRequest>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    info!(order_id = id, items = ?payload.items, "handing over order items");
+    let order = registry
+        .hand_over_items(id, payload.items)
+        .await
+        .map_err(|error| {
+            error!(?error, order_id = id, "failed to save handover");
+            ProblemResponse::internal_server_error()
+        })??;
+    Ok(Json(order.into()))
+}
+
+/// PUT /api/orders/{id}/notification
+#[utoipa::path(
+    put,
+    path = "/api/staff/orders/{id}/notification",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Order id")),
+    request_body = NotifyRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated order notification", body = StaffOrderResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Order not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Failed to save notification update", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn add_notification(
+    State(registry): State<AppRegistry>,
+    Path(id): Path<u32>,
+    Json(payload): Json<NotifyRequest>,
+) -> Result<Json<StaffOrderResponse>, ProblemResponse> {
+    let order = registry
+        .add_notification(id, payload.into())
+        .await
+        .map_err(|error| {
+            error!(?error, order_id = id, "failed to save notification update");
+            ProblemResponse::internal_server_error()
+        })??;
+    Ok(Json(order.into()))
+}
+/// GET /api/staff/flavors/config
+#[utoipa::path(
+    get,
+    path = "/api/staff/flavors/config",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Flavor configs", body = FlavorConfigsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load flavor configs"),
+    )
+)]
+pub async fn get_flavor_configs(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<FlavorConfigsResponse>, StatusCode> {
+    let snapshot = registry.snapshot().await.map_err(|error| {
+        error!(?error, "failed to load flavor configs");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(snapshot.flavor_configs.into()))
+}
+
+/// PUT /api/staff/flavors/{flavor}
+#[utoipa::path(
+    put,
+    path = "/api/staff/flavors/{flavor}",
+    tag = "staff",
+    params(("flavor" = Flavor, Path, description = "Flavor")),
+    request_body = FlavorConfig,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Flavor config updated"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Flavor or station not found"),
+        (status = 500, description = "Failed to save flavor config"),
+    )
+)]
+pub async fn set_flavor_config(
+    State(registry): State<AppRegistry>,
+    Path(flavor): Path<Flavor>,
+    Json(config): Json<FlavorConfig>,
+) -> Result<StatusCode, StatusCode> {
+    registry
+        .set_flavor_config(flavor.clone(), config)
+        .await
+        .map_err(|error| {
+            error!(?error, ?flavor, "failed to save flavor config");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
     Ok(StatusCode::OK)
 }
+
+/// GET /api/staff/flavors
+#[utoipa::path(
+    get,
+    path = "/api/staff/flavors",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Menu catalog including retired flavors", body = [StaffFlavorResponse]),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load menu"),
+    )
+)]
+pub async fn get_flavors(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<StaffFlavorResponse>>, StatusCode> {
+    let snapshot = registry.snapshot().await.map_err(|error| {
+        error!(?error, "failed to load menu");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(
+        snapshot
+            .menu
+            .iter()
+            .cloned()
+            .map(|flavor| {
+                let config = snapshot.flavor_configs.get(&flavor.id).copied();
+                StaffFlavorResponse::new(flavor, config)
+            })
+            .collect(),
+    ))
+}
+
+/// POST /api/staff/flavors
+#[utoipa::path(
+    post,
+    path = "/api/staff/flavors",
+    tag = "staff",
+    request_body = CreateFlavorRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 201, description = "Added flavor", body = StaffFlavorResponse),
+        (status = 400, description = "Invalid flavor id"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Flavor already exists"),
+        (status = 500, description = "Failed to save flavor"),
+    )
+)]
+pub async fn create_flavor(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<CreateFlavorRequest>,
+) -> Result<(StatusCode, Json<StaffFlavorResponse>), StatusCode> {
+    info!(flavor = %payload.id, "adding flavor");
+    let config = FlavorConfig {
+        cooking_time_minutes: payload.cooking_time_minutes,
+        quantity_per_batch: payload.quantity_per_batch,
+        daily_limit: None,
+        is_sold_out: false,
+        station_id: None,
+    };
+    let flavor = MenuFlavor {
+        id: payload.id,
+        display_name: payload.display_name,
+        sort_order: payload.sort_order,
+        is_active: true,
+        unit_price: payload.unit_price,
+    };
+    match registry.add_flavor(flavor, config).await.map_err(|error| {
+        error!(?error, "failed to save flavor");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })? {
+        Ok(flavor) => Ok((
+            StatusCode::CREATED,
+            Json(StaffFlavorResponse::new(flavor, Some(config))),
+        )),
+        Err(AddFlavorError::InvalidId) => Err(StatusCode::BAD_REQUEST),
+        Err(AddFlavorError::AlreadyExists) => Err(StatusCode::CONFLICT),
+    }
+}
+
+/// PUT /api/staff/flavors/{flavor}/active
+#[utoipa::path(
+    put,
+    path = "/api/staff/flavors/{flavor}/active",
+    tag = "staff",
+    params(("flavor" = Flavor, Path, description = "Flavor")),
+    request_body = UpdateFlavorActiveRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated flavor", body = StaffFlavorResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Flavor not found"),
+        (status = 500, description = "Failed to save flavor"),
+    )
+)]
+pub async fn update_flavor_active(
+    State(registry): State<AppRegistry>,
+    Path(flavor): Path<Flavor>,
+    Json(payload): Json<UpdateFlavorActiveRequest>,
+) -> Result<Json<StaffFlavorResponse>, StatusCode> {
+    if let Some(updated) = registry
+        .set_flavor_active(&flavor, payload.is_active)
+        .await
+        .map_err(|error| {
+            error!(?error, ?flavor, "failed to save flavor");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        Ok(Json(updated))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// PUT /api/staff/flavors/{flavor}/price
+#[utoipa::path(
+    put,
+    path = "/api/staff/flavors/{flavor}/price",
+    tag = "staff",
+    params(("flavor" = Flavor, Path, description = "Flavor")),
+    request_body = UpdateFlavorPriceRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated flavor", body = StaffFlavorResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Flavor not found"),
+        (status = 500, description = "Failed to save flavor"),
+    )
+)]
+pub async fn update_flavor_price(
+    State(registry): State<AppRegistry>,
+    Path(flavor): Path<Flavor>,
+    Json(payload): Json<UpdateFlavorPriceRequest>,
+) -> Result<Json<StaffFlavorResponse>, StatusCode> {
+    if let Some(updated) = registry
+        .set_flavor_price(&flavor, payload.unit_price)
+        .await
+        .map_err(|error| {
+            error!(?error, ?flavor, "failed to save flavor");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        Ok(Json(updated))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// PUT /api/staff/flavors/{flavor}/sold-out
+#[utoipa::path(
+    put,
+    path = "/api/staff/flavors/{flavor}/sold-out",
+    tag = "staff",
+    params(("flavor" = Flavor, Path, description = "Flavor")),
+    request_body = UpdateFlavorSoldOutRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated flavor config", body = FlavorConfig),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Flavor not found"),
+        (status = 500, description = "Failed to save flavor config"),
+    )
+)]
+pub async fn update_flavor_sold_out(
+    State(registry): State<AppRegistry>,
+    Path(flavor): Path<Flavor>,
+    Json(payload): Json<UpdateFlavorSoldOutRequest>,
+) -> Result<Json<FlavorConfig>, StatusCode> {
+    if let Some(config) = registry
+        .set_flavor_sold_out(&flavor, payload.is_sold_out)
+        .await
+        .map_err(|error| {
+            error!(?error, ?flavor, "failed to save flavor config");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        Ok(Json(config))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// PUT /api/staff/flavors/{flavor}/daily-limit
+#[utoipa::path(
+    put,
+    path = "/api/staff/flavors/{flavor}/daily-limit",
+    tag = "staff",
+    params(("flavor" = Flavor, Path, description = "Flavor")),
+    request_body = UpdateFlavorDailyLimitRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated flavor config", body = FlavorConfig),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Flavor not found"),
+        (status = 500, description = "Failed to save flavor config"),
+    )
+)]
+pub async fn update_flavor_daily_limit(
+    State(registry): State<AppRegistry>,
+    Path(flavor): Path<Flavor>,
+    Json(payload): Json<UpdateFlavorDailyLimitRequest>,
+) -> Result<Json<FlavorConfig>, StatusCode> {
+    if let Some(config) = registry
+        .set_flavor_daily_limit(&flavor, payload.daily_limit)
+        .await
+        .map_err(|error| {
+            error!(?error, ?flavor, "failed to save flavor config");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        Ok(Json(config))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// GET /api/staff/stations
+#[utoipa::path(
+    get,
+    path = "/api/staff/stations",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Grill stations", body = [Station]),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load stations"),
+    )
+)]
+pub async fn get_stations(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<Station>>, StatusCode> {
+    let snapshot = registry.snapshot().await.map_err(|error| {
+        error!(?error, "failed to load stations");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(snapshot.stations))
+}
+
+/// POST /api/staff/stations
+#[utoipa::path(
+    post,
+    path = "/api/staff/stations",
+    tag = "staff",
+    request_body = CreateStationRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 201, description = "Added station", body = Station),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to save station"),
+    )
+)]
+pub async fn create_station(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<CreateStationRequest>,
+) -> Result<(StatusCode, Json<Station>), StatusCode> {
+    info!(name = payload.name, "adding station");
+    let station = registry
+        .add_station(payload.name, payload.plate_count)
+        .await
+        .map_err(|error| {
+            error!(?error, "failed to save station");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok((StatusCode::CREATED, Json(station)))
+}
+
+/// PUT /api/staff/stations/{id}/plates
+#[utoipa::path(
+    put,
+    path = "/api/staff/stations/{id}/plates",
+    tag = "staff",
+    params(("id" = u32, Path, description = "Station id")),
+    request_body = UpdateStationPlatesRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated station", body = Station),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Station not found"),
+        (status = 500, description = "Failed to save station"),
+    )
+)]
+pub async fn update_station_plates(
+    State(registry): State<AppRegistry>,
+    Path(id): Path<u32>,
+    Json(payload): Json<UpdateStationPlatesRequest>,
+) -> Result<Json<Station>, StatusCode> {
+    registry
+        .set_station_plate_count(id, payload.plate_count)
+        .await
+        .map_err(|error| {
+            error!(?error, station_id = id, "failed to save station");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// GET /api/staff/pricing-rules
+#[utoipa::path(
+    get,
+    path = "/api/staff/pricing-rules",
+    tag = "staff",
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Bundles and discounts including inactive ones", body = [PricingRule]),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Failed to load pricing rules"),
+    )
+)]
+pub async fn get_pricing_rules(
+    State(registry): State<AppRegistry>,
+) -> Result<Json<Vec<PricingRule>>, StatusCode> {
+    let snapshot = registry.snapshot().await.map_err(|error| {
+        error!(?error, "failed to load pricing rules");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(snapshot.pricing_rules))
+}
+
+/// POST /api/staff/pricing-rules
+#[utoipa::path(
+    post,
+    path = "/api/staff/pricing-rules",
+    tag = "staff",
+    request_body = CreatePricingRuleRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 201, description = "Added pricing rule", body = PricingRule),
+        (status = 400, description = "Invalid code or rule"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Pricing rule already exists"),
+        (status = 500, description = "Failed to save pricing rule"),
+    )
+)]
+pub async fn create_pricing_rule(
+    State(registry): State<AppRegistry>,
+    Json(payload): Json<CreatePricingRuleRequest>,
+) -> Result<(StatusCode, Json<PricingRule>), StatusCode> {
+    info!(code = %payload.code, "adding pricing rule");
+    let rule = PricingRule {
+        code: payload.code,
+        name: payload.name,
+        kind: payload.kind,
+        is_active: true,
+    };
+    match registry.add_pricing_rule(rule).await.map_err(|error| {
+        error!(?error, "failed to save pricing rule");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })? {
+        Ok(rule) => Ok((StatusCode::CREATED, Json(rule))),
+        Err(AddPricingRuleError::InvalidCode | AddPricingRuleError::InvalidRule) => {
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Err(AddPricingRuleError::AlreadyExists) => Err(StatusCode::CONFLICT),
+    }
+}
+
+/// PUT /api/staff/pricing-rules/{code}/active
+#[utoipa::path(
+    put,
+    path = "/api/staff/pricing-rules/{code}/active",
+    tag = "staff",
+    params(("code" = String, Path, description = "Pricing rule code")),
+    request_body = UpdatePricingRuleActiveRequest,
+    security(("staffBearerAuth" = [])),
+    responses(
+        (status = 200, description = "Updated pricing rule", body = PricingRule),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Pricing rule not found"),
+        (status = 500, description = "Failed to save pricing rule"),
+    )
+)]
+pub async fn update_pricing_rule_active(
+    State(registry): State<AppRegistry>,
+    Path(code): Path<String>,
+    Json(payload): Json<UpdatePricingRuleActiveRequest>,
+) -> Result<Json<PricingRule>, StatusCode> {
+    if let Some(rule) = registry
+        .set_pricing_rule_active(&code, payload.is_active)
+        .await
+        .map_err(|error| {
+            error!(?error, code, "failed to save pricing rule");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        Ok(Json(rule))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
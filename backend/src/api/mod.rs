@@ -2,4 +2,5 @@ pub mod auth;
 pub mod handler;
 pub mod model;
 pub mod openapi;
+pub mod problem;
 pub mod route;
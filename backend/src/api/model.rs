@@ -1,10 +1,18 @@
-use chrono::{DateTime, Utc};
-use enum_map::EnumMap;
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::domain::order_number::DisplayOrderNumber;
-use crate::domain::snapshot::{Flavor, FlavorConfig, Item, Notify, Order, OrderStatus};
+use crate::domain::batch_timer::BatchAlert;
+use crate::domain::business_day::StockDisposition;
+use crate::domain::no_show::NoShowNotice;
+use crate::domain::order_event::{EventFilter, OrderEventKind};
+use crate::domain::snapshot::{
+    Batch, Flavor, FlavorConfig, Item, MenuFlavor, Notify, Order, OrderStatus, PaymentMethod,
+    PricingRuleKind, PriorityTier, StockAdjustment, StockReason,
+};
+use crate::domain::wait_history::{WaitEstimate, WaitTimeEstimator};
 
 //==// Request Bodies //==//
 
@@ -12,7 +20,16 @@ use crate::domain::snapshot::{Flavor, FlavorConfig, Item, Notify, Order, OrderSt
 #[serde(rename_all = "camelCase")]
 pub struct CreateOrderRequest {
     pub items: Vec<Item>,
+    /// Defaults to `normal`.
+    pub priority: Option<PriorityTier>,
+    /// Older form of `priority`; `true` stands for the top tier.
+    #[schema(deprecated)]
     pub is_priority: Option<bool>,
+    pub payment_method: Option<PaymentMethod>,
+    /// Code of a bundle or coupon to apply.
+    pub pricing_rule: Option<String>,
+    /// Requested pickup time, which makes the order a pre-order for the slot it falls in.
+    pub pickup_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -20,10 +37,146 @@ pub struct UpdateProductionRequest {
     pub items: Vec<Item>,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardStockRequest {
+    pub flavor: Flavor,
+    pub quantity: usize,
+    /// Any reason except `miscount`.
+    pub reason: StockReason,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdjustStockRequest {
+    /// Pieces actually counted.
+    pub quantity: usize,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StartBatchRequest {
+    pub flavor: Flavor,
+    /// Defaults to the flavor's quantity per batch.
+    pub quantity: Option<usize>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateOrderItemsRequest {
+    pub items: Vec<Item>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct HandOverItemsRequest {
+    /// Defaults to every outstanding piece in stock that orders queued ahead leave for it.
+    pub items: Option<Vec<Item>>,
+}
+
 #[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateOrderPriorityRequest {
-    pub is_priority: bool,
+    pub priority: Option<PriorityTier>,
+    /// Older form of `priority`; `true` stands for the top tier.
+    #[schema(deprecated)]
+    pub is_priority: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoRequest {
+    /// Entry to undo; defaults to the latest staff action.
+    pub id: Option<u32>,
+}
+
+impl CreateOrderRequest {
+    pub fn priority_tier(&self) -> PriorityTier {
+        requested_priority(self.priority, self.is_priority).unwrap_or_default()
+    }
+}
+
+impl UpdateOrderPriorityRequest {
+    pub fn priority_tier(&self) -> Option<PriorityTier> {
+        requested_priority(self.priority, self.is_priority)
+    }
+}
+
+fn requested_priority(
+    priority: Option<PriorityTier>,
+    is_priority: Option<bool>,
+) -> Option<PriorityTier> {
+    priority.or_else(|| {
+        is_priority.map(|is_priority| {
+            if is_priority {
+                PriorityTier::Vip
+            } else {
+                PriorityTier::Normal
+            }
+        })
+    })
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFlavorRequest {
+    pub id: Flavor,
+    pub display_name: String,
+    pub sort_order: i64,
+    pub cooking_time_minutes: u32,
+    pub quantity_per_batch: u32,
+    pub unit_price: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePricingRuleRequest {
+    pub code: String,
+    pub name: String,
+    pub kind: PricingRuleKind,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePricingRuleActiveRequest {
+    pub is_active: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateStationRequest {
+    pub name: String,
+    pub plate_count: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStationPlatesRequest {
+    pub plate_count: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFlavorActiveRequest {
+    pub is_active: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFlavorPriceRequest {
+    pub unit_price: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFlavorSoldOutRequest {
+    pub is_sold_out: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFlavorDailyLimitRequest {
+    pub daily_limit: Option<u32>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -53,6 +206,45 @@ pub struct StaffOrdersQuery {
     pub status: Vec<OrderStatus>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct RevenueQuery {
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseBusinessDayRequest {
+    /// Whether unsold pieces are kept for the next day or thrown away.
+    pub stock_disposition: StockDisposition,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BusinessDaySummaryQuery {
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WasteQuery {
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AuditTrailQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub kind: Option<OrderEventKind>,
+}
+
+impl From<AuditTrailQuery> for EventFilter {
+    fn from(query: AuditTrailQuery) -> Self {
+        EventFilter {
+            from: query.from,
+            to: query.to,
+            kind: query.kind,
+        }
+    }
+}
+
 // Custom deserializer for comma-separated order status strings
 fn csv_to_order_status<'de, D>(deserializer: D) -> Result<Vec<OrderStatus>, D::Error>
 where
@@ -83,13 +275,16 @@ pub struct DisplayOrdersResponse {
 pub struct DisplayOrder {
     pub id: u32,
     pub display_number: String,
+    /// Part of the order was already handed over.
+    pub is_partially_handed_over: bool,
 }
 
-impl DisplayOrder {
-    pub fn from_id(id: u32) -> Self {
+impl From<&Order> for DisplayOrder {
+    fn from(order: &Order) -> Self {
         Self {
-            id,
-            display_number: DisplayOrderNumber::from_order_id(id).as_str(),
+            id: order.id,
+            display_number: order.display_number.to_string(),
+            is_partially_handed_over: order.is_partially_handed_over(),
         }
     }
 }
@@ -99,79 +294,112 @@ impl DisplayOrder {
 pub struct OrderDetailsResponse {
     pub id: u32,
     pub display_number: String,
-    pub items: Vec<Item>,
+    pub items: Vec<OrderItemResponse>,
+    /// Some pieces were handed over while the rest is still being prepared.
+    pub is_partially_handed_over: bool,
     pub status: OrderStatus,
     pub ordered_at: DateTime<Utc>,
+    /// Start of the pickup slot of a pre-order.
+    pub pickup_at: Option<DateTime<Utc>>,
+    /// Estimate of the configured estimator, for waiting and cooking orders.
     pub estimated_wait_minutes: Option<i64>,
+    pub estimate: Option<WaitEstimate>,
+    pub batch_model_wait_minutes: Option<i64>,
+    /// `None` without enough recent history for the ordered flavors.
+    pub history_wait_minutes: Option<i64>,
 }
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct WaitTimeResponse {
-    pub wait_times: WaitTimes,
+pub struct OrderItemResponse {
+    pub flavor: Flavor,
+    pub display_name: String,
+    pub quantity: usize,
+    pub handed_over_quantity: usize,
 }
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct WaitTimes {
-    pub tsubuan: Option<i64>,
-    pub custard: Option<i64>,
-    pub kurikinton: Option<i64>,
+pub struct WaitTimeResponse {
+    pub wait_times: WaitTimes,
+    /// Flavors on sale, in menu order, with what their wait times are made of.
+    pub flavors: Vec<FlavorWaitTime>,
+    /// Estimator behind `waitMinutes`.
+    pub estimator: WaitTimeEstimator,
 }
 
-impl From<EnumMap<Flavor, Option<i64>>> for WaitTimes {
-    fn from(wait_times: EnumMap<Flavor, Option<i64>>) -> Self {
-        Self {
-            tsubuan: wait_times[Flavor::Tsubuan],
-            custard: wait_times[Flavor::Custard],
-            kurikinton: wait_times[Flavor::Kurikinton],
-        }
-    }
-}
+/// Wait minutes of the flavors on sale keyed by flavor id.
+#[derive(Serialize, ToSchema)]
+#[serde(transparent)]
+pub struct WaitTimes(BTreeMap<Flavor, Option<i64>>);
 
-impl WaitTimes {
-    pub fn iter(&self) -> impl Iterator<Item = (Flavor, Option<i64>)> {
-        [
-            (Flavor::Tsubuan, self.tsubuan),
-            (Flavor::Custard, self.custard),
-            (Flavor::Kurikinton, self.kurikinton),
-        ]
-        .into_iter()
+impl From<&[FlavorWaitTime]> for WaitTimes {
+    fn from(flavors: &[FlavorWaitTime]) -> Self {
+        Self(
+            flavors
+                .iter()
+                .map(|wait_time| (wait_time.flavor.clone(), wait_time.wait_minutes))
+                .collect(),
+        )
     }
 }
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct StockResponse {
-    pub tsubuan: usize,
-    pub custard: usize,
-    pub kurikinton: usize,
+pub struct FlavorWaitTime {
+    pub flavor: Flavor,
+    pub display_name: String,
+    pub wait_minutes: Option<i64>,
+    pub estimate: Option<WaitEstimate>,
+    pub batch_model_wait_minutes: Option<i64>,
+    pub history_wait_minutes: Option<i64>,
+    /// Pieces left for today; `None` when the flavor has no daily cap.
+    pub remaining_quantity: Option<usize>,
+    pub is_sold_out: bool,
 }
 
-impl From<EnumMap<Flavor, usize>> for StockResponse {
-    fn from(stock: EnumMap<Flavor, usize>) -> Self {
-        Self {
-            tsubuan: stock[Flavor::Tsubuan],
-            custard: stock[Flavor::Custard],
-            kurikinton: stock[Flavor::Kurikinton],
-        }
+/// Unallocated stock keyed by flavor id.
+#[derive(Serialize, ToSchema)]
+#[serde(transparent)]
+pub struct StockResponse(BTreeMap<Flavor, usize>);
+
+impl From<HashMap<Flavor, usize>> for StockResponse {
+    fn from(stock: HashMap<Flavor, usize>) -> Self {
+        Self(stock.into_iter().collect())
+    }
+}
+
+/// Flavor configs keyed by flavor id.
+#[derive(Serialize, ToSchema)]
+#[serde(transparent)]
+pub struct FlavorConfigsResponse(BTreeMap<Flavor, FlavorConfig>);
+
+impl From<HashMap<Flavor, FlavorConfig>> for FlavorConfigsResponse {
+    fn from(configs: HashMap<Flavor, FlavorConfig>) -> Self {
+        Self(configs.into_iter().collect())
     }
 }
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct FlavorConfigsResponse {
-    pub tsubuan: FlavorConfig,
-    pub custard: FlavorConfig,
-    pub kurikinton: FlavorConfig,
+pub struct StaffFlavorResponse {
+    pub id: Flavor,
+    pub display_name: String,
+    pub sort_order: i64,
+    pub is_active: bool,
+    pub unit_price: u32,
+    pub config: Option<FlavorConfig>,
 }
 
-impl From<EnumMap<Flavor, FlavorConfig>> for FlavorConfigsResponse {
-    fn from(configs: EnumMap<Flavor, FlavorConfig>) -> Self {
+impl StaffFlavorResponse {
+    pub fn new(flavor: MenuFlavor, config: Option<FlavorConfig>) -> Self {
         Self {
-            tsubuan: configs[Flavor::Tsubuan],
-            custard: configs[Flavor::Custard],
-            kurikinton: configs[Flavor::Kurikinton],
+            id: flavor.id,
+            display_name: flavor.display_name,
+            sort_order: flavor.sort_order,
+            is_active: flavor.is_active,
+            unit_price: flavor.unit_price,
+            config,
         }
     }
 }
@@ -183,6 +411,43 @@ pub struct UpdateProductionResponse {
     pub unallocated_items: Vec<Item>,
 }
 
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishBatchResponse {
+    pub batch: Batch,
+    pub newly_ready_orders: Vec<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StockLotResponse {
+    pub flavor: Flavor,
+    pub display_name: String,
+    pub quantity: usize,
+    pub produced_at: DateTime<Utc>,
+    pub age_minutes: i64,
+    /// Older than the freshness limit.
+    pub is_expired: bool,
+}
+
+/// Event pushed to staff screens over `GET /api/staff/events`.
+#[derive(Serialize, ToSchema, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum StaffEvent {
+    /// A batch is due to be turned over or taken out, or is still on the grill past its time.
+    BatchDue { alert: BatchAlert, message: String },
+    /// Stale pieces were discarded automatically.
+    StockDiscarded {
+        adjustment: StockAdjustment,
+        message: String,
+    },
+    /// A ready order has not been collected: its customer was reminded, or it expired.
+    OrderNoShow {
+        notice: NoShowNotice,
+        message: String,
+    },
+}
+
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StaffOrderResponse {
@@ -193,20 +458,45 @@ pub struct StaffOrderResponse {
     pub ordered_at: DateTime<Utc>,
     pub ready_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
+    pub priority: PriorityTier,
+    /// Whether the order is in any tier above `normal`.
     pub is_priority: bool,
+    /// Amount charged in yen.
+    pub total_price: u32,
+    pub pricing_rule: Option<String>,
+    pub discount_amount: u32,
+    pub payment_method: Option<PaymentMethod>,
+    pub refunded_at: Option<DateTime<Utc>>,
+    /// Pieces given to the customer ahead of the rest of the order.
+    pub handed_over: Vec<Item>,
+    /// Start of the pickup slot of a pre-order.
+    pub pickup_at: Option<DateTime<Utc>>,
+    /// When the order was flagged as not collected and the customer reminded.
+    pub no_show_at: Option<DateTime<Utc>>,
+    pub expired_at: Option<DateTime<Utc>>,
 }
 
 impl From<Order> for StaffOrderResponse {
     fn from(order: Order) -> Self {
         Self {
-            display_number: DisplayOrderNumber::from_order_id(order.id).as_str(),
+            display_number: order.display_number.to_string(),
             id: order.id,
             items: order.items,
             status: order.status,
             ordered_at: order.ordered_at,
             ready_at: order.ready_at,
             completed_at: order.completed_at,
-            is_priority: order.is_priority,
+            priority: order.priority,
+            is_priority: order.priority.is_priority(),
+            total_price: order.total_price,
+            pricing_rule: order.pricing_rule,
+            discount_amount: order.discount_amount,
+            payment_method: order.payment_method,
+            refunded_at: order.refunded_at,
+            handed_over: order.handed_over,
+            pickup_at: order.pickup_at,
+            no_show_at: order.no_show_at,
+            expired_at: order.expired_at,
         }
     }
 }
@@ -7,20 +7,57 @@ use utoipa::{
 use crate::{
     api::{
         handler::{
-            __path_add_notification, __path_cancel_order, __path_complete_order,
-            __path_create_order, __path_get_display_orders, __path_get_flavor_configs,
-            __path_get_order_details, __path_get_staff_orders, __path_get_stock,
-            __path_get_wait_times, __path_line_callback, __path_set_flavor_config,
-            __path_update_order_priority, __path_update_production,
+            __path_add_notification, __path_adjust_stock, __path_cancel_order,
+            __path_close_business_day, __path_complete_order, __path_create_flavor,
+            __path_create_order, __path_create_pricing_rule, __path_create_station,
+            __path_discard_stock, __path_finish_batch, __path_get_active_batches,
+            __path_get_audit_trail, __path_get_business_day_summary,
+            __path_get_current_business_day, __path_get_display_orders, __path_get_flavor_configs,
+            __path_get_flavors, __path_get_menu, __path_get_order_details,
+            __path_get_order_details_by_number, __path_get_order_history, __path_get_pickup_slots,
+            __path_get_pricing_rules, __path_get_production_plan, __path_get_revenue_summary,
+            __path_get_staff_events, __path_get_staff_orders, __path_get_stations,
+            __path_get_stock, __path_get_stock_lots, __path_get_undoable_actions,
+            __path_get_wait_times, __path_get_waste_report, __path_hand_over_items,
+            __path_line_callback, __path_open_business_day, __path_refund_order,
+            __path_reopen_order, __path_restore_order, __path_set_flavor_config,
+            __path_start_batch, __path_undo, __path_update_flavor_active,
+            __path_update_flavor_daily_limit, __path_update_flavor_price,
+            __path_update_flavor_sold_out, __path_update_order_items, __path_update_order_priority,
+            __path_update_pricing_rule_active, __path_update_production,
+            __path_update_station_plates,
         },
         model::{
-            CreateOrderRequest, DisplayOrder, DisplayOrdersResponse, FlavorConfigsResponse,
-            NotifyRequest, OrderDetailsResponse, StaffOrderResponse, StockResponse,
-            UpdateOrderPriorityRequest, UpdateProductionRequest, UpdateProductionResponse,
+            AdjustStockRequest, CloseBusinessDayRequest, CreateFlavorRequest, CreateOrderRequest,
+            CreatePricingRuleRequest, CreateStationRequest, DiscardStockRequest, DisplayOrder,
+            DisplayOrdersResponse, FinishBatchResponse, FlavorConfigsResponse, FlavorWaitTime,
+            HandOverItemsRequest, NotifyRequest, OrderDetailsResponse, OrderItemResponse,
+            StaffEvent, StaffFlavorResponse, StaffOrderResponse, StartBatchRequest,
+            StockLotResponse, StockResponse, UndoRequest, UpdateFlavorActiveRequest,
+            UpdateFlavorDailyLimitRequest, UpdateFlavorPriceRequest, UpdateFlavorSoldOutRequest,
+            UpdateOrderItemsRequest, UpdateOrderPriorityRequest, UpdatePricingRuleActiveRequest,
+            UpdateProductionRequest, UpdateProductionResponse, UpdateStationPlatesRequest,
             WaitTimeResponse, WaitTimes,
         },
+        problem::ProblemResponse,
+    },
+    domain::{
+        batch_timer::BatchAlert,
+        business_day::{BusinessDay, BusinessDaySummary, StockDisposition},
+        no_show::{NoShowNotice, NoShowStep},
+        order_event::{Actor, EventReason, OrderEvent, OrderEventKind},
+        pre_order::PickupSlot,
+        production_plan::{PlannedBatch, ProductionPlan},
+        revenue::{PaymentMethodRevenue, RevenueSummary},
+        snapshot::{
+            Batch, Flavor, FlavorConfig, Item, MenuFlavor, Notify, OrderStatus, PaymentMethod,
+            PricingRule, PricingRuleKind, PriorityTier, Station, StockAdjustment, StockLot,
+            StockReason,
+        },
+        stock::{FlavorWaste, ReasonWaste, WasteReport},
+        undo::{UndoAction, UndoEntry},
+        wait_history::{WaitEstimate, WaitTimeEstimator},
     },
-    domain::snapshot::{Flavor, FlavorConfig, Item, Notify, OrderStatus},
 };
 
 #[derive(OpenApi)]
@@ -28,38 +65,131 @@ use crate::{
     paths(
         get_display_orders,
         get_order_details,
+        get_order_details_by_number,
         get_wait_times,
+        get_pickup_slots,
+        get_menu,
         get_staff_orders,
         create_order,
         get_stock,
+        get_stock_lots,
+        discard_stock,
+        adjust_stock,
+        get_waste_report,
+        get_revenue_summary,
+        open_business_day,
+        close_business_day,
+        get_current_business_day,
+        get_business_day_summary,
         update_production,
+        get_production_plan,
+        get_active_batches,
+        start_batch,
+        finish_batch,
+        get_staff_events,
+        get_undoable_actions,
+        undo,
+        get_audit_trail,
+        get_order_history,
         complete_order,
         cancel_order,
+        reopen_order,
+        restore_order,
+        refund_order,
+        update_order_items,
+        hand_over_items,
         update_order_priority,
         add_notification,
         get_flavor_configs,
         set_flavor_config,
+        get_flavors,
+        create_flavor,
+        update_flavor_active,
+        update_flavor_price,
+        update_flavor_sold_out,
+        update_flavor_daily_limit,
+        get_pricing_rules,
+        create_pricing_rule,
+        update_pricing_rule_active,
+        get_stations,
+        create_station,
+        update_station_plates,
         line_callback,
     ),
     components(schemas(
+        Actor,
+        AdjustStockRequest,
+        Batch,
+        BatchAlert,
+        BusinessDay,
+        BusinessDaySummary,
+        CloseBusinessDayRequest,
+        CreateFlavorRequest,
         CreateOrderRequest,
+        CreatePricingRuleRequest,
+        CreateStationRequest,
+        DiscardStockRequest,
         DisplayOrder,
         DisplayOrdersResponse,
+        EventReason,
+        FinishBatchResponse,
         Flavor,
         FlavorConfig,
         FlavorConfigsResponse,
+        FlavorWaitTime,
+        FlavorWaste,
+        HandOverItemsRequest,
         Item,
+        MenuFlavor,
+        NoShowNotice,
+        NoShowStep,
         Notify,
         NotifyRequest,
         OrderDetailsResponse,
+        OrderEvent,
+        OrderEventKind,
+        OrderItemResponse,
         OrderStatus,
+        PaymentMethod,
+        PaymentMethodRevenue,
+        PickupSlot,
+        PlannedBatch,
+        PricingRule,
+        PricingRuleKind,
+        PriorityTier,
+        ProblemResponse,
+        ProductionPlan,
+        ReasonWaste,
+        RevenueSummary,
+        StaffEvent,
+        StaffFlavorResponse,
         StaffOrderResponse,
+        StartBatchRequest,
+        Station,
+        StockAdjustment,
+        StockDisposition,
+        StockLot,
+        StockLotResponse,
+        StockReason,
         StockResponse,
+        UndoAction,
+        UndoEntry,
+        UndoRequest,
+        UpdateFlavorActiveRequest,
+        UpdateFlavorDailyLimitRequest,
+        UpdateFlavorPriceRequest,
+        UpdateFlavorSoldOutRequest,
+        UpdateOrderItemsRequest,
         UpdateOrderPriorityRequest,
+        UpdatePricingRuleActiveRequest,
         UpdateProductionRequest,
         UpdateProductionResponse,
+        UpdateStationPlatesRequest,
+        WaitEstimate,
+        WaitTimeEstimator,
         WaitTimeResponse,
         WaitTimes,
+        WasteReport,
     )),
     modifiers(&SecurityAddon),
     tags(
@@ -0,0 +1,115 @@
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::usecase::order::OrderError;
+use crate::usecase::undo::UndoError;
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Problem details (RFC 9457) returned when a staff order request is rejected.
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemResponse {
+    /// Stable error code, e.g. `duplicate-flavor`.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+}
+
+impl ProblemResponse {
+    pub fn new(status: StatusCode, problem_type: &str, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: problem_type.to_owned(),
+            title: status.canonical_reason().unwrap_or_default().to_owned(),
+            status: status.as_u16(),
+            detail: detail.into(),
+        }
+    }
+
+    pub fn internal_server_error() -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal-error",
+            "the request could not be processed",
+        )
+    }
+}
+
+impl IntoResponse for ProblemResponse {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (
+            status,
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+impl From<OrderError> for ProblemResponse {
+    fn from(error: OrderError) -> Self {
+        let (status, problem_type) = match &error {
+            OrderError::EmptyItems => (StatusCode::UNPROCESSABLE_ENTITY, "empty-items"),
+            OrderError::ZeroQuantity(_) => (StatusCode::UNPROCESSABLE_ENTITY, "zero-quantity"),
+            OrderError::DuplicateFlavor(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "duplicate-flavor")
+            }
+            OrderError::TooManyPieces { .. } => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "too-many-pieces")
+            }
+            OrderError::TotalTooLarge => (StatusCode::UNPROCESSABLE_ENTITY, "total-too-large"),
+            OrderError::PricingRuleNotApplicable(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "pricing-rule-not-applicable",
+            ),
+            OrderError::NotOnSale(_) => (StatusCode::BAD_REQUEST, "not-on-sale"),
+            OrderError::UnknownPricingRule(_) => (StatusCode::BAD_REQUEST, "unknown-pricing-rule"),
+            OrderError::NotFound(_) => (StatusCode::NOT_FOUND, "order-not-found"),
+            OrderError::ExceedsRemaining { .. } => (StatusCode::CONFLICT, "exceeds-remaining"),
+            OrderError::NotCompleted(_) => (StatusCode::CONFLICT, "order-not-completed"),
+            OrderError::AlreadyRefunded(_) => (StatusCode::CONFLICT, "already-refunded"),
+            OrderError::NotEditable { .. } => (StatusCode::CONFLICT, "order-not-editable"),
+            OrderError::BelowHandedOver { .. } => (StatusCode::CONFLICT, "below-handed-over"),
+            OrderError::ExceedsOutstanding { .. } => (StatusCode::CONFLICT, "exceeds-outstanding"),
+            OrderError::NotInStock { .. } => (StatusCode::CONFLICT, "not-in-stock"),
+            OrderError::PickupTooSoon { .. } => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "pickup-too-soon")
+            }
+            OrderError::PickupOutsideBusinessDay => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "pickup-outside-business-day",
+            ),
+            OrderError::PickupSlotFull { .. } => (StatusCode::CONFLICT, "pickup-slot-full"),
+            OrderError::BusinessDayNotOpen => (StatusCode::CONFLICT, "business-day-not-open"),
+            OrderError::BusinessDayClosed(_) => (StatusCode::CONFLICT, "business-day-closed"),
+            OrderError::DisplayNumbersExhausted => {
+                (StatusCode::SERVICE_UNAVAILABLE, "display-numbers-exhausted")
+            }
+            OrderError::NothingToHandOver(_) => (StatusCode::CONFLICT, "nothing-to-hand-over"),
+            OrderError::FullyHandedOver(_) => (StatusCode::CONFLICT, "fully-handed-over"),
+            OrderError::InvalidTransition { .. } => (StatusCode::CONFLICT, "invalid-transition"),
+        };
+        Self::new(status, problem_type, error.to_string())
+    }
+}
+
+impl From<UndoError> for ProblemResponse {
+    fn from(error: UndoError) -> Self {
+        let (status, problem_type) = match &error {
+            UndoError::Order(error) => return error.clone().into(),
+            UndoError::NothingToUndo => (StatusCode::NOT_FOUND, "nothing-to-undo"),
+            UndoError::PiecesHandedOver(_) => (StatusCode::CONFLICT, "pieces-handed-over"),
+            UndoError::FlavorGone(_) => (StatusCode::CONFLICT, "flavor-gone"),
+            UndoError::NotUndoable => (StatusCode::CONFLICT, "not-undoable"),
+        };
+        Self::new(status, problem_type, error.to_string())
+    }
+}
@@ -2,17 +2,27 @@ use crate::{
     api::{
         auth::staff_api_auth,
         handler::{
-            add_notification, cancel_order, complete_order, create_order, get_display_orders,
-            get_flavor_configs, get_order_details, get_staff_orders, get_stock, get_wait_times,
-            line_callback, set_flavor_config, update_order_priority, update_production,
+            add_notification, adjust_stock, cancel_order, close_business_day, complete_order,
+            create_flavor, create_order, create_pricing_rule, create_station, discard_stock,
+            finish_batch, get_active_batches, get_audit_trail, get_business_day_summary,
+            get_current_business_day, get_display_orders, get_flavor_configs, get_flavors,
+            get_menu, get_order_details, get_order_details_by_number, get_order_history,
+            get_pickup_slots, get_pricing_rules, get_production_plan, get_revenue_summary,
+            get_staff_events, get_staff_orders, get_stations, get_stock, get_stock_lots,
+            get_undoable_actions, get_wait_times, get_waste_report, hand_over_items, line_callback,
+            open_business_day, refund_order, reopen_order, restore_order, set_flavor_config,
+            start_batch, undo, update_flavor_active, update_flavor_daily_limit,
+            update_flavor_price, update_flavor_sold_out, update_order_items, update_order_priority,
+            update_pricing_rule_active, update_production, update_station_plates,
         },
         openapi::build_openapi,
     },
     app::AppRegistry,
+    domain::order_event::Actor,
 };
 use axum::{
     Router, middleware,
-    routing::{get, post, put},
+    routing::{get, patch, post, put},
 };
 use utoipa_swagger_ui::SwaggerUi;
 
@@ -20,25 +30,84 @@ pub fn routes(registry: AppRegistry) -> Router {
     let user_routes = Router::new()
         .route("/orders/display", get(get_display_orders))
         .route("/orders/{id}", get(get_order_details))
-        .route("/wait-times", get(get_wait_times));
+        .route(
+            "/orders/by-number/{number}",
+            get(get_order_details_by_number),
+        )
+        .route("/wait-times", get(get_wait_times))
+        .route("/pickup-slots", get(get_pickup_slots))
+        .route("/menu", get(get_menu));
+    // Staff share one token, so their changes are recorded as made by staff.
+    let staff_registry = registry.as_staff(Actor::Staff);
     let staff_routes = Router::new()
         .route("/staff/orders", get(get_staff_orders).post(create_order))
         .route("/staff/stock", get(get_stock))
+        .route("/staff/stock/lots", get(get_stock_lots))
+        .route("/staff/stock/discard", post(discard_stock))
+        .route("/staff/stock/waste", get(get_waste_report))
+        .route("/staff/stock/{flavor}", put(adjust_stock))
+        .route("/staff/revenue", get(get_revenue_summary))
+        .route("/staff/business-days/open", post(open_business_day))
+        .route("/staff/business-days/close", post(close_business_day))
+        .route(
+            "/staff/business-days/current",
+            get(get_current_business_day),
+        )
+        .route(
+            "/staff/business-days/summary",
+            get(get_business_day_summary),
+        )
         .route("/staff/production", post(update_production))
+        .route("/staff/production/plan", get(get_production_plan))
+        .route("/staff/batches", get(get_active_batches).post(start_batch))
+        .route("/staff/batches/{id}/finish", post(finish_batch))
+        .route("/staff/events", get(get_staff_events))
+        .route("/staff/undo", get(get_undoable_actions).post(undo))
+        .route("/staff/audit-trail", get(get_audit_trail))
+        .route("/staff/orders/{id}/history", get(get_order_history))
         .route("/staff/orders/{id}/complete", post(complete_order))
         .route("/staff/orders/{id}/cancel", post(cancel_order))
+        .route("/staff/orders/{id}/reopen", post(reopen_order))
+        .route("/staff/orders/{id}/restore", post(restore_order))
+        .route("/staff/orders/{id}/refund", post(refund_order))
+        .route("/staff/orders/{id}/items", patch(update_order_items))
+        .route("/staff/orders/{id}/handover", post(hand_over_items))
         .route("/staff/orders/{id}/priority", put(update_order_priority))
         .route("/staff/orders/{id}/notification", put(add_notification))
+        .route(
+            "/staff/pricing-rules",
+            get(get_pricing_rules).post(create_pricing_rule),
+        )
+        .route(
+            "/staff/pricing-rules/{code}/active",
+            put(update_pricing_rule_active),
+        )
+        .route("/staff/stations", get(get_stations).post(create_station))
+        .route("/staff/stations/{id}/plates", put(update_station_plates))
+        .route("/staff/flavors", get(get_flavors).post(create_flavor))
         .route("/staff/flavors/config", get(get_flavor_configs))
         .route("/staff/flavors/{flavor}", put(set_flavor_config))
+        .route("/staff/flavors/{flavor}/active", put(update_flavor_active))
+        .route("/staff/flavors/{flavor}/price", put(update_flavor_price))
+        .route(
+            "/staff/flavors/{flavor}/sold-out",
+            put(update_flavor_sold_out),
+        )
+        .route(
+            "/staff/flavors/{flavor}/daily-limit",
+            put(update_flavor_daily_limit),
+        )
         .layer(middleware::from_fn_with_state(
-            registry.clone(),
+            staff_registry.clone(),
             staff_api_auth,
-        ));
+        ))
+        .with_state(staff_registry);
     let line_router = Router::new().route("/line_callback", post(line_callback));
     Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", build_openapi()))
-        .nest("/api", user_routes.merge(staff_routes))
-        .merge(line_router)
-        .with_state(registry)
+        .nest(
+            "/api",
+            user_routes.with_state(registry.clone()).merge(staff_routes),
+        )
+        .merge(line_router.with_state(registry))
 }
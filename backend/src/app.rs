@@ -1,25 +1,56 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bot_sdk_line::client::LINE;
 use bot_sdk_line::messaging_api_line::apis::MessagingApiApi;
 use bot_sdk_line::messaging_api_line::models::{Message, ReplyMessageRequest};
+use chrono::{NaiveDate, Utc};
 use poise::serenity_prelude::Context;
-use tokio::sync::Mutex;
-use tracing::error;
+use tokio::sync::{Mutex, OnceCell, RwLock, RwLockReadGuard, broadcast};
+use tracing::{error, warn};
 
-use crate::api::model::{OrderDetailsResponse, WaitTimeResponse};
+use crate::api::model::{
+    FlavorWaitTime, OrderDetailsResponse, OrderItemResponse, StaffEvent, StaffFlavorResponse,
+    StockLotResponse, WaitTimeResponse,
+};
+use crate::domain::allocation::AllocationPolicyKind;
+use crate::domain::availability::{self, OrderLimits};
+use crate::domain::batch_timer::{self, BatchAlert};
+use crate::domain::business_day::{
+    self as domain_business_day, BusinessDay, BusinessDaySummary, StockDisposition,
+};
+use crate::domain::no_show::{self, NoShowNotice, NoShowSettings};
 use crate::domain::notification::{NotificationDeliveryLog, NotificationDeliveryStatus};
-use crate::domain::order_number::DisplayOrderNumber;
+use crate::domain::order_event::{self, Actor, EventFilter, OrderEvent, OrderEventKind};
+use crate::domain::order_number::{self, DisplayNumberSettings, DisplayOrderNumber};
 use crate::domain::order_status;
-use crate::domain::snapshot::{Flavor, FlavorConfig, Item, Notify, Order, OrderSystemSnapshot};
+use crate::domain::pre_order::{self, PickupSlot, PreOrderSettings};
+use crate::domain::production_plan::{self, ProductionPlan};
+use crate::domain::revenue::{self, RevenueSummary};
+use crate::domain::snapshot::{
+    Batch, Flavor, FlavorConfig, Item, MenuFlavor, Notify, Order, OrderSystemSnapshot, PricingRule,
+    PriorityAging, PriorityTier, Station, StockAdjustment, StockReason,
+};
+use crate::domain::snapshot_change;
+use crate::domain::stock::{self as domain_stock, StockSettings, WasteReport};
+use crate::domain::undo::{UndoAction, UndoEntry, UndoStack};
+use crate::domain::wait_history::{self, ThroughputHistory, WaitEstimate, WaitTimeSettings};
 use crate::domain::wait_time;
 use crate::port::line_reply::LineReplySender;
 use crate::port::notification_log::AppRepository;
 use crate::port::notifier::Notifier;
+use crate::port::staff_alert::StaffAlertSender;
 use crate::storage::SqliteRepository;
-use crate::usecase::order;
-use crate::usecase::production;
+use crate::usecase::business_day::{self, BusinessDayError};
+use crate::usecase::menu::{self, AddFlavorError};
+use crate::usecase::order::{self, NewOrder, OrderError};
+use crate::usecase::pricing::{self, AddPricingRuleError};
+use crate::usecase::production::{self, BatchError};
+use crate::usecase::station;
+use crate::usecase::stock::{self, StockError};
+use crate::usecase::undo::{self, UndoError};
 use crate::{discord, line};
 
 // AppRegistry is the main application state.
@@ -28,15 +59,51 @@ pub struct AppRegistry {
     repository: Arc<dyn AppRepository>,
     notifier: Arc<dyn Notifier>,
     line_reply_sender: Arc<dyn LineReplySender>,
+    staff_alert_sender: Arc<dyn StaffAlertSender>,
     staff_api_token: Arc<str>,
     line_channel_secret: Arc<str>,
-    mutation_lock: Arc<Mutex<()>>,
+    state: Arc<OnceCell<Arc<RwLock<OrderSystemSnapshot>>>>,
     dispatch_lock: Arc<Mutex<()>>,
+    staff_events: broadcast::Sender<StaffEvent>,
+    // Highest escalation level already alerted per unfinished batch.
+    batch_alert_levels: Arc<Mutex<HashMap<u32, u32>>>,
+    wait_time_settings: WaitTimeSettings,
+    stock_settings: StockSettings,
+    display_number_settings: Arc<DisplayNumberSettings>,
+    pre_order_settings: PreOrderSettings,
+    order_limits: OrderLimits,
+    no_show_settings: NoShowSettings,
+    priority_aging: PriorityAging,
+    allocation_policy: AllocationPolicyKind,
+    // Who mutations made through this registry are recorded as.
+    actor: Actor,
+    // Staff actions are kept for undo; customers' are not.
+    records_undo: bool,
+    undo_stack: Arc<Mutex<UndoStack>>,
 }
 
 pub struct LineDiscordNotifier {
     line: Arc<Mutex<LINE>>,
     discord_ctx: Arc<Mutex<Context>>,
+    staff_channel_id: Option<u64>,
+}
+
+const STAFF_EVENT_CAPACITY: usize = 64;
+const BATCH_TIMER_INTERVAL: Duration = Duration::from_secs(20);
+const STOCK_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+const PRE_ORDER_RELEASE_INTERVAL: Duration = Duration::from_secs(30);
+const NO_SHOW_INTERVAL: Duration = Duration::from_secs(30);
+const UNDO_STACK_CAPACITY: usize = 20;
+
+// Orders in progress and those of the current business date, which daily limits and the closing
+// summary count, plus `order_id`. Orders of earlier days are locked, so mutations leave them out.
+fn working_copy(state: &OrderSystemSnapshot, order_id: Option<u32>) -> OrderSystemSnapshot {
+    let date = domain_business_day::business_date_at(state, Utc::now());
+    state.working_copy(|order| {
+        Some(order.id) == order_id
+            || order_number::is_holding_number(order)
+            || domain_business_day::business_date_of(state, order) == date
+    })
 }
 
 impl AppRegistry {
@@ -44,17 +111,20 @@ impl AppRegistry {
         line_token: String,
         line_channel_secret: String,
         staff_api_token: String,
+        staff_channel_id: Option<u64>,
         ctx: Context,
         repository: SqliteRepository,
     ) -> Self {
         let notifier = LineDiscordNotifier {
             line: Arc::new(Mutex::new(LINE::new(line_token))),
             discord_ctx: Arc::new(Mutex::new(ctx)),
+            staff_channel_id,
         };
         let notifier = Arc::new(notifier);
         Self::new_with_ports(
             Arc::new(repository),
             notifier.clone(),
+            notifier.clone(),
             notifier,
             staff_api_token,
             line_channel_secret,
@@ -65,20 +135,94 @@ impl AppRegistry {
         repository: Arc<dyn AppRepository>,
         notifier: Arc<dyn Notifier>,
         line_reply_sender: Arc<dyn LineReplySender>,
+        staff_alert_sender: Arc<dyn StaffAlertSender>,
         staff_api_token: String,
         line_channel_secret: String,
     ) -> Self {
+        let (staff_events, _) = broadcast::channel(STAFF_EVENT_CAPACITY);
         Self {
             repository,
             notifier,
             line_reply_sender,
+            staff_alert_sender,
             staff_api_token: Arc::from(staff_api_token),
             line_channel_secret: Arc::from(line_channel_secret),
-            mutation_lock: Arc::new(Mutex::new(())),
+            state: Arc::new(OnceCell::new()),
             dispatch_lock: Arc::new(Mutex::new(())),
+            staff_events,
+            batch_alert_levels: Arc::new(Mutex::new(HashMap::new())),
+            wait_time_settings: WaitTimeSettings::default(),
+            stock_settings: StockSettings::default(),
+            display_number_settings: Arc::new(DisplayNumberSettings::default()),
+            pre_order_settings: PreOrderSettings::default(),
+            order_limits: OrderLimits::default(),
+            no_show_settings: NoShowSettings::default(),
+            priority_aging: PriorityAging::default(),
+            allocation_policy: AllocationPolicyKind::default(),
+            actor: Actor::default(),
+            records_undo: false,
+            undo_stack: Arc::new(Mutex::new(UndoStack::new(UNDO_STACK_CAPACITY))),
+        }
+    }
+
+    /// The same registry with its mutations recorded as made by `actor`.
+    pub fn as_actor(&self, actor: Actor) -> Self {
+        Self {
+            actor,
+            records_undo: false,
+            ..self.clone()
+        }
+    }
+
+    /// Like `as_actor`, for staff, whose actions can be undone.
+    pub fn as_staff(&self, actor: Actor) -> Self {
+        Self {
+            actor,
+            records_undo: true,
+            ..self.clone()
         }
     }
 
+    pub fn with_display_number_settings(mut self, settings: DisplayNumberSettings) -> Self {
+        self.display_number_settings = Arc::new(settings);
+        self
+    }
+
+    pub fn with_pre_order_settings(mut self, settings: PreOrderSettings) -> Self {
+        self.pre_order_settings = settings;
+        self
+    }
+
+    pub fn with_order_limits(mut self, limits: OrderLimits) -> Self {
+        self.order_limits = limits;
+        self
+    }
+
+    pub fn with_no_show_settings(mut self, settings: NoShowSettings) -> Self {
+        self.no_show_settings = settings;
+        self
+    }
+
+    pub fn with_allocation_policy(mut self, policy: AllocationPolicyKind) -> Self {
+        self.allocation_policy = policy;
+        self
+    }
+
+    pub fn with_priority_aging(mut self, aging: PriorityAging) -> Self {
+        self.priority_aging = aging;
+        self
+    }
+
+    pub fn with_stock_settings(mut self, settings: StockSettings) -> Self {
+        self.stock_settings = settings;
+        self
+    }
+
+    pub fn with_wait_time_settings(mut self, settings: WaitTimeSettings) -> Self {
+        self.wait_time_settings = settings;
+        self
+    }
+
     pub fn staff_api_token(&self) -> &str {
         &self.staff_api_token
     }
@@ -88,22 +232,101 @@ impl AppRegistry {
     }
 
     pub async fn initialize(&self) -> anyhow::Result<()> {
-        self.repository.load_snapshot().await?;
+        self.state().await?;
         Ok(())
     }
 
+    // Loaded from storage on first use; from then on memory is authoritative and storage only
+    // follows it.
+    async fn state(&self) -> anyhow::Result<&Arc<RwLock<OrderSystemSnapshot>>> {
+        self.state
+            .get_or_try_init(|| async {
+                let mut snapshot = self.repository.load_snapshot().await?;
+                snapshot.priority_aging = self.priority_aging;
+                snapshot.allocation_policy = self.allocation_policy;
+                // Working copies leave old orders out, so they number new ones from here.
+                snapshot.last_order_id = snapshot.next_order_id() - 1;
+                Ok(Arc::new(RwLock::new(snapshot)))
+            })
+            .await
+    }
+
+    /// Copy of the live state, for callers that keep it across awaits.
     pub async fn snapshot(&self) -> anyhow::Result<OrderSystemSnapshot> {
-        self.repository.load_snapshot().await
+        Ok(self.read_snapshot().await?.clone())
+    }
+
+    /// The live state without copying it. Mutations wait while the guard is held, so keep it
+    /// for synchronous work only.
+    pub async fn read_snapshot(&self) -> anyhow::Result<RwLockReadGuard<'_, OrderSystemSnapshot>> {
+        Ok(self.state().await?.read().await)
     }
 
+    // Mutations work on the active orders only and write back just the rows they changed;
+    // memory takes them only once storage has, so a failed write leaves both as they were.
     async fn mutate_snapshot<T>(
         &self,
         mutate: impl FnOnce(&mut OrderSystemSnapshot) -> T,
     ) -> anyhow::Result<T> {
-        let _guard = self.mutation_lock.lock().await;
-        let mut snapshot = self.repository.load_snapshot().await?;
-        let result = mutate(&mut snapshot);
-        self.repository.replace_snapshot(&snapshot).await?;
+        self.mutate_working_copy(None, |snapshot| (mutate(snapshot), Vec::new()))
+            .await
+    }
+
+    // Brings the order in even when it is no longer active, so changes to orders of a closed
+    // day are still refused as such rather than as unknown orders.
+    async fn mutate_order<T>(
+        &self,
+        id: u32,
+        mutate: impl FnOnce(&mut OrderSystemSnapshot) -> T,
+    ) -> anyhow::Result<T> {
+        self.mutate_working_copy(Some(id), |snapshot| (mutate(snapshot), Vec::new()))
+            .await
+    }
+
+    // Like `mutate_snapshot`, for mutations that report events the state does not show or say
+    // more than it does. The events read off the state are recorded after them, except those of
+    // an order and kind already reported.
+    async fn mutate_snapshot_recording<T>(
+        &self,
+        mutate: impl FnOnce(&mut OrderSystemSnapshot) -> (T, Vec<OrderEvent>),
+    ) -> anyhow::Result<T> {
+        self.mutate_working_copy(None, mutate).await
+    }
+
+    async fn mutate_working_copy<T>(
+        &self,
+        order_id: Option<u32>,
+        mutate: impl FnOnce(&mut OrderSystemSnapshot) -> (T, Vec<OrderEvent>),
+    ) -> anyhow::Result<T> {
+        let mut state = self.state().await?.clone().write_owned().await;
+        let before = working_copy(&state, order_id);
+        let mut snapshot = before.clone();
+        let (result, mut events) = mutate(&mut snapshot);
+        let reported: HashSet<_> = events
+            .iter()
+            .filter_map(|event| Some((event.order_id?, event.kind)))
+            .collect();
+        events.extend(
+            order_event::events_between(&before, &snapshot, &self.actor, Utc::now())
+                .into_iter()
+                .filter(|event| {
+                    event
+                        .order_id
+                        .is_none_or(|id| !reported.contains(&(id, event.kind)))
+                }),
+        );
+        let changes = snapshot_change::changes_between(&before, &snapshot);
+        if !changes.is_empty() || !events.is_empty() {
+            // Runs to the end even if the caller is dropped, so a commit that landed is always
+            // merged.
+            let repository = self.repository.clone();
+            tokio::spawn(async move {
+                repository.apply_changes(&changes, &events).await?;
+                state.merge(snapshot);
+                anyhow::Ok(())
+            })
+            .await??;
+        }
         Ok(result)
     }
 
@@ -111,92 +334,391 @@ impl AppRegistry {
         self.dispatch_notifications(notifications).await;
     }
 
-    pub async fn create_order(&self, items: Vec<Item>, is_priority: bool) -> anyhow::Result<Order> {
-        let mutation = self
-            .mutate_snapshot(|snapshot| order::create_order(snapshot, items, is_priority))
-            .await?;
+    pub async fn create_order(
+        &self,
+        new_order: NewOrder,
+    ) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_snapshot(|snapshot| {
+                order::create_order(
+                    snapshot,
+                    new_order,
+                    &self.display_number_settings,
+                    self.pre_order_settings,
+                    self.order_limits,
+                )
+            })
+            .await?
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.remember(UndoAction::CreateOrder {
+            order_id: mutation.result.id,
+        })
+        .await;
         self.send_notifications(mutation.status_update.notifications)
             .await;
-        Ok(mutation.result)
+        Ok(Ok(mutation.result))
     }
 
     // Updates stock and fulfills waiting orders.
+    // Returns None when an item is not in the menu catalog.
     pub async fn update_production(
         &self,
         production: Vec<Item>,
-    ) -> anyhow::Result<(Vec<u32>, Vec<Item>)> {
-        let (status_update, unallocated_items) = self
-            .mutate_snapshot(|snapshot| {
-                let status_update = production::register_completed_production(snapshot, production);
+    ) -> anyhow::Result<Option<(Vec<u32>, Vec<Item>)>> {
+        let reported = production.clone();
+        let Some((status_update, unallocated_items)) = self
+            .mutate_snapshot_recording(|snapshot| {
+                let now = Utc::now();
+                let reports = production
+                    .iter()
+                    .map(|item| {
+                        let stock = snapshot.stock_of(&item.flavor);
+                        OrderEvent::stock_change(
+                            OrderEventKind::ProductionReported,
+                            &item.flavor,
+                            stock,
+                            stock + item.quantity,
+                            &self.actor,
+                            now,
+                        )
+                    })
+                    .collect();
+                let Some(status_update) =
+                    production::register_completed_production(snapshot, production)
+                else {
+                    return (None, Vec::new());
+                };
                 let unallocated_items = snapshot
-                    .unallocated_stock
+                    .menu
                     .iter()
-                    .filter(|&(_, &quantity)| quantity > 0)
-                    .map(|(flavor, &quantity)| Item { flavor, quantity })
+                    .map(|entry| Item {
+                        flavor: entry.id.clone(),
+                        quantity: snapshot.stock_of(&entry.id),
+                    })
+                    .filter(|item| item.quantity > 0)
                     .collect();
-                (status_update, unallocated_items)
+                (Some((status_update, unallocated_items)), reports)
             })
-            .await?;
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.remember(UndoAction::ReportProduction { items: reported })
+            .await;
         self.send_notifications(status_update.notifications).await;
-        Ok((status_update.newly_ready_orders, unallocated_items))
+        Ok(Some((status_update.newly_ready_orders, unallocated_items)))
     }
 
-    pub async fn complete_order(&self, id: u32) -> anyhow::Result<Option<Order>> {
-        let Some(mutation) = self
-            .mutate_snapshot(|snapshot| order::complete_order(snapshot, id))
+    pub async fn start_batch(
+        &self,
+        flavor: Flavor,
+        quantity: Option<usize>,
+    ) -> anyhow::Result<Result<Batch, BatchError>> {
+        let (batch, status_update) = match self
+            .mutate_snapshot(|snapshot| production::start_batch(snapshot, flavor, quantity))
             .await?
-        else {
-            return Ok(None);
+        {
+            Ok(result) => result,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.send_notifications(status_update.notifications).await;
+        Ok(Ok(batch))
+    }
+
+    // Puts the batch into stock and returns the orders it made ready.
+    pub async fn finish_batch(
+        &self,
+        id: u32,
+    ) -> anyhow::Result<Result<(Batch, Vec<u32>), BatchError>> {
+        let (batch, status_update) = match self
+            .mutate_snapshot(|snapshot| production::finish_batch(snapshot, id))
+            .await?
+        {
+            Ok(result) => result,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.remember(UndoAction::FinishBatch { batch_id: id })
+            .await;
+        self.send_notifications(status_update.notifications).await;
+        Ok(Ok((batch, status_update.newly_ready_orders)))
+    }
+
+    pub async fn discard_stock(
+        &self,
+        flavor: Flavor,
+        quantity: usize,
+        reason: StockReason,
+        note: Option<String>,
+    ) -> anyhow::Result<Result<StockAdjustment, StockError>> {
+        let (adjustment, status_update) = match self
+            .mutate_snapshot(|snapshot| {
+                stock::discard_stock(snapshot, flavor, quantity, reason, note, Utc::now())
+            })
+            .await?
+        {
+            Ok(result) => result,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.send_notifications(status_update.notifications).await;
+        Ok(Ok(adjustment))
+    }
+
+    // A recount above the recorded stock can make waiting orders ready.
+    pub async fn adjust_stock(
+        &self,
+        flavor: Flavor,
+        counted: usize,
+        note: Option<String>,
+    ) -> anyhow::Result<Result<StockAdjustment, StockError>> {
+        let (adjustment, status_update) = match self
+            .mutate_snapshot(|snapshot| {
+                stock::adjust_stock(snapshot, flavor, counted, note, Utc::now())
+            })
+            .await?
+        {
+            Ok(result) => result,
+            Err(error) => return Ok(Err(error)),
         };
+        self.send_notifications(status_update.notifications).await;
+        Ok(Ok(adjustment))
+    }
+
+    pub async fn open_business_day(&self) -> anyhow::Result<Result<BusinessDay, BusinessDayError>> {
+        self.mutate_snapshot(|snapshot| business_day::open_business_day(snapshot, Utc::now()))
+            .await
+    }
+
+    pub async fn close_business_day(
+        &self,
+        stock_disposition: StockDisposition,
+    ) -> anyhow::Result<Result<BusinessDaySummary, BusinessDayError>> {
+        let closed = match self
+            .mutate_snapshot_recording(|snapshot| {
+                let now = Utc::now();
+                match business_day::close_business_day(snapshot, stock_disposition, now) {
+                    Ok(closed) => {
+                        let events = closed
+                            .cancelled
+                            .iter()
+                            .map(|&(id, from)| {
+                                OrderEvent::cancelled_at_closing(id, from, &self.actor, now)
+                            })
+                            .collect();
+                        (Ok(closed), events)
+                    }
+                    Err(error) => (Err(error), Vec::new()),
+                }
+            })
+            .await?
+        {
+            Ok(closed) => closed,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.send_notifications(closed.notifications).await;
+        Ok(Ok(closed.summary))
+    }
+
+    pub async fn complete_order(&self, id: u32) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_order(id, |snapshot| order::complete_order(snapshot, id))
+            .await?
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.remember(UndoAction::CompleteOrder { order_id: id })
+            .await;
         self.send_notifications(mutation.status_update.notifications)
             .await;
-        Ok(Some(mutation.result))
+        Ok(Ok(mutation.result))
     }
 
-    pub async fn cancel_order(&self, id: u32) -> anyhow::Result<Option<Order>> {
-        let Some(mutation) = self
-            .mutate_snapshot(|snapshot| order::cancel_order(snapshot, id))
+    pub async fn cancel_order(&self, id: u32) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_order(id, |snapshot| order::cancel_order(snapshot, id))
             .await?
-        else {
-            return Ok(None);
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.remember(UndoAction::CancelOrder { order_id: id })
+            .await;
+        self.send_notifications(mutation.status_update.notifications)
+            .await;
+        Ok(Ok(mutation.result))
+    }
+
+    pub async fn reopen_order(&self, id: u32) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_order(id, |snapshot| {
+                order::reopen_order(snapshot, id, &self.display_number_settings)
+            })
+            .await?
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.send_notifications(mutation.status_update.notifications)
+            .await;
+        Ok(Ok(mutation.result))
+    }
+
+    pub async fn restore_order(&self, id: u32) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_order(id, |snapshot| {
+                order::restore_order(snapshot, id, &self.display_number_settings)
+            })
+            .await?
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.send_notifications(mutation.status_update.notifications)
+            .await;
+        Ok(Ok(mutation.result))
+    }
+
+    pub async fn refund_order(&self, id: u32) -> anyhow::Result<Result<Order, OrderError>> {
+        self.mutate_order(id, |snapshot| order::refund_order(snapshot, id))
+            .await
+    }
+
+    pub async fn update_order_items(
+        &self,
+        id: u32,
+        items: Vec<Item>,
+    ) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_order(id, |snapshot| {
+                order::update_order_items(
+                    snapshot,
+                    id,
+                    items,
+                    self.pre_order_settings,
+                    self.order_limits,
+                )
+            })
+            .await?
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
         };
         self.send_notifications(mutation.status_update.notifications)
             .await;
-        Ok(Some(mutation.result))
+        Ok(Ok(mutation.result))
+    }
+
+    pub async fn hand_over_items(
+        &self,
+        id: u32,
+        items: Option<Vec<Item>>,
+    ) -> anyhow::Result<Result<Order, OrderError>> {
+        let mutation = match self
+            .mutate_order(id, |snapshot| order::hand_over_items(snapshot, id, items))
+            .await?
+        {
+            Ok(mutation) => mutation,
+            Err(error) => return Ok(Err(error)),
+        };
+        self.remember(UndoAction::HandOverItems { order_id: id })
+            .await;
+        self.send_notifications(mutation.status_update.notifications)
+            .await;
+        Ok(Ok(mutation.result))
     }
 
     pub async fn update_order_priority(
         &self,
         id: u32,
-        is_priority: bool,
-    ) -> anyhow::Result<Option<Order>> {
-        let Some(mutation) = self
-            .mutate_snapshot(|snapshot| order::update_order_priority(snapshot, id, is_priority))
+        priority: PriorityTier,
+    ) -> anyhow::Result<Result<Order, OrderError>> {
+        let (mutation, previous) = match self
+            .mutate_order(id, |snapshot| {
+                let previous = snapshot
+                    .orders
+                    .iter()
+                    .find(|order| order.id == id)
+                    .map(|order| order.priority);
+                order::update_order_priority(snapshot, id, priority)
+                    .map(|mutation| (mutation, previous))
+            })
             .await?
-        else {
-            return Ok(None);
+        {
+            Ok(result) => result,
+            Err(error) => return Ok(Err(error)),
         };
+        if let Some(previous) = previous.filter(|previous| *previous != priority) {
+            self.remember(UndoAction::UpdatePriority {
+                order_id: id,
+                previous,
+            })
+            .await;
+        }
         self.send_notifications(mutation.status_update.notifications)
             .await;
-        Ok(Some(mutation.result))
+        Ok(Ok(mutation.result))
     }
 
     pub async fn add_notification(
         &self,
         id: u32,
         payload: Notify,
-    ) -> anyhow::Result<Option<Order>> {
-        self.mutate_snapshot(|snapshot| order::add_notification(snapshot, id, payload))
-            .await
+    ) -> anyhow::Result<Result<Order, OrderError>> {
+        self.mutate_order(id, |snapshot| {
+            order::add_notification(snapshot, id, payload)
+        })
+        .await
     }
 
     pub async fn cancel_notification(
         &self,
         id: u32,
         payload: &Notify,
-    ) -> anyhow::Result<Option<Order>> {
-        self.mutate_snapshot(|snapshot| order::cancel_notification(snapshot, id, payload))
-            .await
+    ) -> anyhow::Result<Result<Order, OrderError>> {
+        self.mutate_order(id, |snapshot| {
+            order::cancel_notification(snapshot, id, payload)
+        })
+        .await
+    }
+
+    async fn remember(&self, action: UndoAction) {
+        if self.records_undo {
+            self.undo_stack
+                .lock()
+                .await
+                .push(action, self.actor.clone(), Utc::now());
+        }
+    }
+
+    /// Staff actions that can be undone, newest first.
+    pub async fn undoable_actions(&self) -> Vec<UndoEntry> {
+        self.undo_stack.lock().await.entries().cloned().collect()
+    }
+
+    /// Reverts the staff action `id`, or the latest one, and takes it off the stack.
+    pub async fn undo(&self, id: Option<u32>) -> anyhow::Result<Result<UndoEntry, UndoError>> {
+        // Held throughout so that one action is not undone twice.
+        let mut undo_stack = self.undo_stack.lock().await;
+        let Some(entry) = undo_stack.get(id).cloned() else {
+            return Ok(Err(UndoError::NothingToUndo));
+        };
+        let status_update = match self
+            .mutate_working_copy(entry.action.order_id(), |snapshot| {
+                let result = undo::undo(snapshot, &entry.action, &self.display_number_settings);
+                (result, Vec::new())
+            })
+            .await?
+        {
+            Ok(status_update) => status_update,
+            Err(error) => return Ok(Err(error)),
+        };
+        undo_stack.remove(entry.id);
+        drop(undo_stack);
+        self.send_notifications(status_update.notifications).await;
+        Ok(Ok(entry))
     }
 
     pub async fn send_notification(&self, order_id: u32, notify: &Notify, message: String) {
@@ -285,6 +807,17 @@ impl Notifier for LineDiscordNotifier {
     }
 }
 
+#[async_trait]
+impl StaffAlertSender for LineDiscordNotifier {
+    async fn alert_staff(&self, message: String) -> anyhow::Result<()> {
+        let Some(channel_id) = self.staff_channel_id else {
+            return Ok(());
+        };
+        let ctx = self.discord_ctx.lock().await;
+        discord::send_channel_message(&ctx, channel_id, &message).await
+    }
+}
+
 #[async_trait]
 impl LineReplySender for LineDiscordNotifier {
     async fn reply(&self, reply_token: String, messages: Vec<Message>) -> anyhow::Result<()> {
@@ -307,38 +840,575 @@ impl LineReplySender for LineDiscordNotifier {
 
 impl AppRegistry {
     pub async fn get_order_details(&self, id: u32) -> anyhow::Result<Option<OrderDetailsResponse>> {
-        let snapshot = self.repository.load_snapshot().await?;
-        let Some(order) = snapshot.orders.iter().find(|o| o.id == id) else {
+        let snapshot = self.read_snapshot().await?;
+        Ok(snapshot
+            .orders
+            .iter()
+            .find(|o| o.id == id)
+            .map(|order| self.order_details(&snapshot, order)))
+    }
+
+    /// Recorded events the filter picks, oldest first.
+    pub async fn get_audit_trail(&self, filter: EventFilter) -> anyhow::Result<Vec<OrderEvent>> {
+        self.repository.events(filter).await
+    }
+
+    /// Recorded events of the order, oldest first; `None` for an unknown order.
+    pub async fn get_order_history(&self, id: u32) -> anyhow::Result<Option<Vec<OrderEvent>>> {
+        if !self
+            .read_snapshot()
+            .await?
+            .orders
+            .iter()
+            .any(|o| o.id == id)
+        {
             return Ok(None);
-        };
-        let estimated_wait_minutes = wait_time::estimate_order_wait_minutes(&snapshot, order);
+        }
+        Ok(Some(self.repository.order_history(id).await?))
+    }
 
-        Ok(Some(OrderDetailsResponse {
+    // Numbers are reused, so the order currently holding the number wins over older ones.
+    pub async fn get_order_details_by_display_number(
+        &self,
+        number: &DisplayOrderNumber,
+    ) -> anyhow::Result<Option<OrderDetailsResponse>> {
+        let snapshot = self.read_snapshot().await?;
+        let mut orders = snapshot
+            .orders
+            .iter()
+            .rev()
+            .filter(|order| &order.display_number == number);
+        let order = orders
+            .clone()
+            .find(|order| order_number::is_holding_number(order))
+            .or_else(|| orders.next());
+        Ok(order.map(|order| self.order_details(&snapshot, order)))
+    }
+
+    fn order_details(&self, snapshot: &OrderSystemSnapshot, order: &Order) -> OrderDetailsResponse {
+        let now = Utc::now();
+        let estimator = self.wait_time_settings.estimator;
+        let batch_model_wait_minutes = wait_time::estimate_order_wait_minutes(snapshot, order, now);
+        let history_wait_minutes = wait_history::estimate_order_wait_minutes(
+            snapshot,
+            order,
+            &self.throughput_history(snapshot, now),
+            now,
+        );
+
+        OrderDetailsResponse {
             id: order.id,
-            display_number: DisplayOrderNumber::from_order_id(order.id).as_str(),
-            items: order.items.clone(),
+            display_number: order.display_number.to_string(),
+            items: order
+                .items
+                .iter()
+                .map(|item| OrderItemResponse {
+                    flavor: item.flavor.clone(),
+                    display_name: snapshot.flavor_name(&item.flavor).to_owned(),
+                    quantity: item.quantity,
+                    handed_over_quantity: order.handed_over_quantity(&item.flavor),
+                })
+                .collect(),
+            is_partially_handed_over: order.is_partially_handed_over(),
             status: order.status,
             ordered_at: order.ordered_at,
-            estimated_wait_minutes,
-        }))
+            pickup_at: order.pickup_at,
+            estimated_wait_minutes: estimator
+                .select(batch_model_wait_minutes, history_wait_minutes),
+            estimate: WaitEstimate::new(
+                estimator,
+                batch_model_wait_minutes,
+                history_wait_minutes,
+                now,
+            ),
+            batch_model_wait_minutes,
+            history_wait_minutes,
+        }
     }
 
     pub async fn get_current_wait_times(&self) -> anyhow::Result<WaitTimeResponse> {
-        let snapshot = self.repository.load_snapshot().await?;
+        let snapshot = self.read_snapshot().await?;
+        let now = Utc::now();
+        let estimator = self.wait_time_settings.estimator;
+        let from_history = wait_history::estimate_current_wait_times(
+            &snapshot,
+            &self.throughput_history(&snapshot, now),
+            now,
+        );
+        let flavors: Vec<_> = wait_time::estimate_current_wait_times(&snapshot, now)
+            .into_iter()
+            .zip(from_history)
+            .map(
+                |((flavor, batch_model_wait_minutes), (_, history_wait_minutes))| FlavorWaitTime {
+                    display_name: snapshot.flavor_name(&flavor).to_owned(),
+                    remaining_quantity: availability::remaining_quantity(&snapshot, &flavor, now),
+                    is_sold_out: availability::is_sold_out(&snapshot, &flavor, now),
+                    flavor,
+                    wait_minutes: estimator.select(batch_model_wait_minutes, history_wait_minutes),
+                    estimate: WaitEstimate::new(
+                        estimator,
+                        batch_model_wait_minutes,
+                        history_wait_minutes,
+                        now,
+                    ),
+                    batch_model_wait_minutes,
+                    history_wait_minutes,
+                },
+            )
+            .collect();
         Ok(WaitTimeResponse {
-            wait_times: wait_time::estimate_current_wait_times(&snapshot).into(),
+            wait_times: flavors.as_slice().into(),
+            flavors,
+            estimator,
         })
     }
 
+    /// Pickup slots of the current business day that can still be booked; none while closed.
+    pub async fn get_pickup_slots(&self) -> anyhow::Result<Vec<PickupSlot>> {
+        let snapshot = self.read_snapshot().await?;
+        let Some(day) = domain_business_day::open_day(&snapshot) else {
+            return Ok(Vec::new());
+        };
+        Ok(pre_order::open_slots(
+            &snapshot,
+            self.pre_order_settings,
+            day.date,
+            Utc::now(),
+        ))
+    }
+
+    fn throughput_history(
+        &self,
+        snapshot: &OrderSystemSnapshot,
+        now: chrono::DateTime<Utc>,
+    ) -> ThroughputHistory {
+        let window = chrono::Duration::minutes(self.wait_time_settings.history_window_minutes);
+        ThroughputHistory::observe(snapshot, now, window)
+    }
+
+    pub async fn get_production_plan(&self) -> anyhow::Result<ProductionPlan> {
+        let snapshot = self.read_snapshot().await?;
+        Ok(production_plan::plan_production(&snapshot, Utc::now()))
+    }
+
+    pub async fn get_stock_lots(&self) -> anyhow::Result<Vec<StockLotResponse>> {
+        let snapshot = self.read_snapshot().await?;
+        let now = Utc::now();
+        Ok(snapshot
+            .stock_lots
+            .iter()
+            .map(|lot| StockLotResponse {
+                flavor: lot.flavor.clone(),
+                display_name: snapshot.flavor_name(&lot.flavor).to_owned(),
+                quantity: lot.quantity,
+                produced_at: lot.produced_at,
+                age_minutes: (now - lot.produced_at).num_minutes(),
+                is_expired: domain_stock::is_expired(lot, now, self.stock_settings),
+            })
+            .collect())
+    }
+
+    /// Waste of the given business day, defaulting to the current one.
+    pub async fn get_waste_report(&self, date: Option<NaiveDate>) -> anyhow::Result<WasteReport> {
+        let snapshot = self.read_snapshot().await?;
+        let date =
+            date.unwrap_or_else(|| domain_business_day::business_date_at(&snapshot, Utc::now()));
+        Ok(domain_stock::waste_report(&snapshot, date))
+    }
+
+    /// Revenue of the given business day, defaulting to the current one.
+    pub async fn get_revenue_summary(
+        &self,
+        date: Option<NaiveDate>,
+    ) -> anyhow::Result<RevenueSummary> {
+        let snapshot = self.read_snapshot().await?;
+        let date =
+            date.unwrap_or_else(|| domain_business_day::business_date_at(&snapshot, Utc::now()));
+        Ok(revenue::summarize_day(&snapshot, date))
+    }
+
+    pub async fn get_current_business_day(&self) -> anyhow::Result<Option<BusinessDay>> {
+        let snapshot = self.read_snapshot().await?;
+        Ok(domain_business_day::open_day(&snapshot).cloned())
+    }
+
+    /// Summary of the given business day, defaulting to the current one.
+    pub async fn get_business_day_summary(
+        &self,
+        date: Option<NaiveDate>,
+    ) -> anyhow::Result<BusinessDaySummary> {
+        let snapshot = self.read_snapshot().await?;
+        let date =
+            date.unwrap_or_else(|| domain_business_day::business_date_at(&snapshot, Utc::now()));
+        Ok(domain_business_day::summarize(&snapshot, date))
+    }
+
+    // Returns None when the flavor is not in the menu catalog or the station does not exist.
     pub async fn set_flavor_config(
         &self,
         flavor: Flavor,
         config: FlavorConfig,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<FlavorConfig>> {
+        let Some((status_update, previous)) = self
+            .mutate_snapshot(|snapshot| {
+                let previous = snapshot.flavor_configs.get(&flavor).copied();
+                order::set_flavor_config(snapshot, flavor.clone(), config)
+                    .map(|status_update| (status_update, previous))
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+        if let Some(previous) = previous.filter(|previous| *previous != config) {
+            self.remember(UndoAction::SetFlavorConfig {
+                flavor,
+                previous,
+                applied: config,
+            })
+            .await;
+        }
+        self.send_notifications(status_update.notifications).await;
+        Ok(Some(config))
+    }
+
+    // Returns None when the flavor is not in the menu catalog.
+    pub async fn set_flavor_price(
+        &self,
+        flavor: &Flavor,
+        unit_price: u32,
+    ) -> anyhow::Result<Option<StaffFlavorResponse>> {
+        self.mutate_snapshot(|snapshot| {
+            let flavor = menu::set_flavor_price(snapshot, flavor, unit_price)?;
+            let config = snapshot.flavor_configs.get(&flavor.id).copied();
+            Some(StaffFlavorResponse::new(flavor, config))
+        })
+        .await
+    }
+
+    // Returns None when the flavor is not in the menu catalog.
+    pub async fn set_flavor_sold_out(
+        &self,
+        flavor: &Flavor,
+        is_sold_out: bool,
+    ) -> anyhow::Result<Option<FlavorConfig>> {
+        self.set_flavor_config_field(flavor, |snapshot| {
+            menu::set_flavor_sold_out(snapshot, flavor, is_sold_out)
+        })
+        .await
+    }
+
+    // Returns None when the flavor is not in the menu catalog.
+    pub async fn set_flavor_daily_limit(
+        &self,
+        flavor: &Flavor,
+        daily_limit: Option<u32>,
+    ) -> anyhow::Result<Option<FlavorConfig>> {
+        self.set_flavor_config_field(flavor, |snapshot| {
+            menu::set_flavor_daily_limit(snapshot, flavor, daily_limit)
+        })
+        .await
+    }
+
+    // Remembers the change so that undoing it restores just that field.
+    async fn set_flavor_config_field(
+        &self,
+        flavor: &Flavor,
+        set: impl FnOnce(&mut OrderSystemSnapshot) -> Option<FlavorConfig>,
+    ) -> anyhow::Result<Option<FlavorConfig>> {
+        let Some((previous, applied)) = self
+            .mutate_snapshot(|snapshot| {
+                let previous = snapshot.flavor_configs.get(flavor).copied()?;
+                set(snapshot).map(|applied| (previous, applied))
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+        if previous != applied {
+            self.remember(UndoAction::SetFlavorConfig {
+                flavor: flavor.clone(),
+                previous,
+                applied,
+            })
+            .await;
+        }
+        Ok(Some(applied))
+    }
+
+    pub async fn add_pricing_rule(
+        &self,
+        rule: PricingRule,
+    ) -> anyhow::Result<Result<PricingRule, AddPricingRuleError>> {
+        self.mutate_snapshot(|snapshot| pricing::add_pricing_rule(snapshot, rule))
+            .await
+    }
+
+    // Returns None when no rule has the code.
+    pub async fn set_pricing_rule_active(
+        &self,
+        code: &str,
+        is_active: bool,
+    ) -> anyhow::Result<Option<PricingRule>> {
+        self.mutate_snapshot(|snapshot| pricing::set_pricing_rule_active(snapshot, code, is_active))
+            .await
+    }
+
+    pub async fn add_flavor(
+        &self,
+        flavor: MenuFlavor,
+        config: FlavorConfig,
+    ) -> anyhow::Result<Result<MenuFlavor, AddFlavorError>> {
+        self.mutate_snapshot(|snapshot| menu::add_flavor(snapshot, flavor, config))
+            .await
+    }
+
+    pub async fn set_flavor_active(
+        &self,
+        flavor: &Flavor,
+        is_active: bool,
+    ) -> anyhow::Result<Option<StaffFlavorResponse>> {
+        let Some((result, status_update)) = self
+            .mutate_snapshot(|snapshot| {
+                let (flavor, status_update) = menu::set_flavor_active(snapshot, flavor, is_active)?;
+                let config = snapshot.flavor_configs.get(&flavor.id).copied();
+                Some((StaffFlavorResponse::new(flavor, config), status_update))
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.send_notifications(status_update.notifications).await;
+        Ok(Some(result))
+    }
+
+    pub async fn add_station(&self, name: String, plate_count: u32) -> anyhow::Result<Station> {
+        self.mutate_snapshot(|snapshot| station::add_station(snapshot, name, plate_count))
+            .await
+    }
+
+    // Returns None when the station does not exist.
+    pub async fn set_station_plate_count(
+        &self,
+        id: u32,
+        plate_count: u32,
+    ) -> anyhow::Result<Option<Station>> {
+        let Some((station, status_update)) = self
+            .mutate_snapshot(|snapshot| station::set_station_plate_count(snapshot, id, plate_count))
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.send_notifications(status_update.notifications).await;
+        Ok(Some(station))
+    }
+}
+
+impl AppRegistry {
+    pub fn subscribe_staff_events(&self) -> broadcast::Receiver<StaffEvent> {
+        self.staff_events.subscribe()
+    }
+
+    // Polls the grill until the process exits.
+    pub async fn run_batch_timers(self, escalation_minutes: i64) {
+        let mut interval = tokio::time::interval(BATCH_TIMER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(error) = self
+                .check_batch_timers(Utc::now(), escalation_minutes)
+                .await
+            {
+                error!(?error, "failed to check batch timers");
+            }
+        }
+    }
+
+    // Alerts staff about batches that became due or reached a new escalation level since the
+    // last check. Returns the alerts that were raised.
+    pub async fn check_batch_timers(
+        &self,
+        now: chrono::DateTime<Utc>,
+        escalation_minutes: i64,
+    ) -> anyhow::Result<Vec<BatchAlert>> {
+        let (active_batch_ids, due_alerts) = {
+            let snapshot = self.read_snapshot().await?;
+            (
+                snapshot
+                    .active_batches()
+                    .map(|batch| batch.id)
+                    .collect::<HashSet<_>>(),
+                batch_timer::due_batch_alerts(&snapshot, now, escalation_minutes),
+            )
+        };
+        let alerts = {
+            let mut alerted = self.batch_alert_levels.lock().await;
+            alerted.retain(|id, _| active_batch_ids.contains(id));
+            due_alerts
+                .into_iter()
+                .filter(|alert| {
+                    let is_new = alerted
+                        .get(&alert.batch.id)
+                        .is_none_or(|level| *level < alert.escalation_level);
+                    if is_new {
+                        alerted.insert(alert.batch.id, alert.escalation_level);
+                    }
+                    is_new
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for alert in &alerts {
+            let message = alert.message();
+            if let Err(error) = self.staff_alert_sender.alert_staff(message.clone()).await {
+                warn!(
+                    ?error,
+                    batch_id = alert.batch.id,
+                    "failed to post batch alert"
+                );
+            }
+            // Nobody may be watching the stream; that is not an error.
+            let _ = self.staff_events.send(StaffEvent::BatchDue {
+                alert: alert.clone(),
+                message,
+            });
+        }
+        Ok(alerts)
+    }
+}
+
+impl AppRegistry {
+    // Discards stale stock every minute until the process exits.
+    pub async fn run_stock_expiry(self) {
+        let mut interval = tokio::time::interval(STOCK_EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(error) = self.discard_expired_stock(Utc::now()).await {
+                error!(?error, "failed to discard expired stock");
+            }
+        }
+    }
+
+    pub async fn discard_expired_stock(
+        &self,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<Vec<StockAdjustment>> {
+        let settings = self.stock_settings;
+        let (adjustments, menu) = self
+            .mutate_snapshot(|snapshot| {
+                let adjustments = stock::discard_expired_stock(snapshot, now, settings);
+                (adjustments, snapshot.menu.clone())
+            })
+            .await?;
+
+        for adjustment in &adjustments {
+            let display_name = menu
+                .iter()
+                .find(|entry| entry.id == adjustment.flavor)
+                .map_or(adjustment.flavor.as_str(), |entry| &entry.display_name);
+            let message = format!(
+                "{}分以上たった{}を{}個廃棄しました。",
+                settings.freshness_minutes,
+                display_name,
+                adjustment.delta.unsigned_abs()
+            );
+            if let Err(error) = self.staff_alert_sender.alert_staff(message.clone()).await {
+                warn!(?error, "failed to post stock expiry alert");
+            }
+            let _ = self.staff_events.send(StaffEvent::StockDiscarded {
+                adjustment: adjustment.clone(),
+                message,
+            });
+        }
+        Ok(adjustments)
+    }
+}
+
+impl AppRegistry {
+    // Queues pre-orders as their lead time starts, until the process exits.
+    pub async fn run_pre_order_release(self) {
+        let mut interval = tokio::time::interval(PRE_ORDER_RELEASE_INTERVAL);
+        let mut checked_at = Utc::now();
+        loop {
+            interval.tick().await;
+            let now = Utc::now();
+            match self.release_pre_orders(checked_at, now).await {
+                Ok(_) => checked_at = now,
+                Err(error) => error!(?error, "failed to release pre-orders"),
+            }
+        }
+    }
+
+    // Allocation skips pre-orders until their release time, so once one passes nothing else
+    // would trigger it. Returns the pre-orders released after `since`.
+    pub async fn release_pre_orders(
+        &self,
+        since: chrono::DateTime<Utc>,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<Vec<u32>> {
+        let released = self
+            .read_snapshot()
+            .await?
+            .orders
+            .iter()
+            .filter(|order| {
+                order
+                    .release_at
+                    .is_some_and(|release_at| since < release_at && release_at <= now)
+            })
+            .map(|order| order.id)
+            .collect::<Vec<_>>();
+        if released.is_empty() {
+            return Ok(released);
+        }
         let status_update = self
-            .mutate_snapshot(|snapshot| order::set_flavor_config(snapshot, flavor, config))
+            .mutate_snapshot(order_status::update_order_statuses)
             .await?;
         self.send_notifications(status_update.notifications).await;
-        Ok(())
+        Ok(released)
+    }
+}
+
+impl AppRegistry {
+    // Chases uncollected orders until the process exits.
+    pub async fn run_no_show_policy(self) {
+        let mut interval = tokio::time::interval(NO_SHOW_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(error) = self.chase_no_shows(Utc::now()).await {
+                error!(?error, "failed to chase uncollected orders");
+            }
+        }
+    }
+
+    // Reminds customers, alerts staff and expires orders as they come due. Returns the steps
+    // taken.
+    pub async fn chase_no_shows(
+        &self,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<Vec<NoShowNotice>> {
+        let settings = self.no_show_settings;
+        if no_show::due_no_show_steps(&*self.read_snapshot().await?, settings, now).is_empty() {
+            return Ok(Vec::new());
+        }
+        let mutation = self
+            .mutate_snapshot(|snapshot| order::chase_no_shows(snapshot, settings, now))
+            .await?;
+        self.send_notifications(mutation.status_update.notifications)
+            .await;
+
+        for notice in &mutation.result {
+            let message = notice.message();
+            if let Err(error) = self.staff_alert_sender.alert_staff(message.clone()).await {
+                warn!(
+                    ?error,
+                    order_id = notice.order_id,
+                    "failed to post no-show alert"
+                );
+            }
+            let _ = self.staff_events.send(StaffEvent::OrderNoShow {
+                notice: notice.clone(),
+                message,
+            });
+        }
+        Ok(mutation.result)
     }
 }
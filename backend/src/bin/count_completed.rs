@@ -1,9 +1,9 @@
-use enum_map::EnumMap;
-use strum::IntoEnumIterator;
-use taiyaq_backend::domain::snapshot::{Flavor, OrderStatus};
+use chrono::{NaiveDate, Utc};
+use taiyaq_backend::domain::business_day;
 use taiyaq_backend::port::order_repository::OrderRepository;
 use taiyaq_backend::storage::{self, SqliteRepository};
 
+// Usage: count_completed [YYYY-MM-DD]; defaults to the current business day.
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let database_url =
@@ -11,25 +11,25 @@ async fn main() -> anyhow::Result<()> {
     let repository = SqliteRepository::new(storage::connect(&database_url).await?);
     let snapshot = repository.load_snapshot().await?;
 
-    let mut flavor_counts = EnumMap::from_fn(|_| 0);
+    let date = match std::env::args().nth(1) {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")?,
+        None => business_day::business_date_at(&snapshot, Utc::now()),
+    };
+    let summary = business_day::summarize(&snapshot, date);
 
-    snapshot
-        .orders
-        .iter()
-        .filter(|o| o.status == OrderStatus::Completed)
-        .flat_map(|o| &o.items)
-        .for_each(|item| {
-            flavor_counts[item.flavor] += item.quantity;
-        });
-
-    let sum: usize = flavor_counts.iter().map(|(_, n)| n).sum();
-
-    println!("Total completed taiyaki sold by flavor:");
-    for flavor in Flavor::iter() {
-        println!("- {}: {}", flavor, flavor_counts[flavor]);
+    println!("Completed taiyaki sold by flavor on {date}:");
+    for flavor in &snapshot.menu {
+        let quantity = summary
+            .sold
+            .iter()
+            .find(|item| item.flavor == flavor.id)
+            .map_or(0, |item| item.quantity);
+        println!("- {}: {}", flavor.display_name, quantity);
     }
 
+    let sum: usize = summary.sold.iter().map(|item| item.quantity).sum();
     println!("sum: {sum}");
+    println!("revenue: {} yen", summary.revenue.net_sales);
 
     Ok(())
 }
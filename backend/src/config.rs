@@ -1,5 +1,14 @@
 use std::net::SocketAddr;
 
+use crate::domain::allocation::AllocationPolicyKind;
+use crate::domain::availability::OrderLimits;
+use crate::domain::no_show::NoShowSettings;
+use crate::domain::order_number::DisplayNumberSettings;
+use crate::domain::pre_order::PreOrderSettings;
+use crate::domain::snapshot::PriorityAging;
+use crate::domain::stock::StockSettings;
+use crate::domain::wait_history::WaitTimeSettings;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
@@ -8,7 +17,19 @@ pub struct Config {
     pub staff_api_token: String,
     pub discord_token: String,
     pub discord_guild_id: u64,
+    /// Channel where batch timer alerts are posted; alerts only go to the event stream when unset.
+    pub discord_staff_channel_id: Option<u64>,
+    /// Minutes an overdue batch may stay unfinished before its alert is repeated more urgently.
+    pub batch_alert_escalation_minutes: i64,
     pub bind_addr: SocketAddr,
+    pub wait_time: WaitTimeSettings,
+    pub stock: StockSettings,
+    pub display_numbers: DisplayNumberSettings,
+    pub pre_orders: PreOrderSettings,
+    pub order_limits: OrderLimits,
+    pub no_shows: NoShowSettings,
+    pub priority_aging: PriorityAging,
+    pub allocation_policy: AllocationPolicyKind,
 }
 
 impl Config {
@@ -23,12 +44,124 @@ impl Config {
             discord_guild_id: required_var("DISCORD_GUILD_ID")?.parse().map_err(|error| {
                 anyhow::anyhow!("DISCORD_GUILD_ID must be a valid u64: {error}")
             })?,
+            discord_staff_channel_id: std::env::var("DISCORD_STAFF_CHANNEL_ID")
+                .ok()
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(|error| {
+                    anyhow::anyhow!("DISCORD_STAFF_CHANNEL_ID must be a valid u64: {error}")
+                })?,
+            batch_alert_escalation_minutes: std::env::var("BATCH_ALERT_ESCALATION_MINUTES")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .map_err(|error| {
+                    anyhow::anyhow!("BATCH_ALERT_ESCALATION_MINUTES must be a number: {error}")
+                })?,
             bind_addr: std::env::var("BIND_ADDR")
                 .unwrap_or_else(|_| "0.0.0.0:38000".to_string())
                 .parse()
                 .map_err(|error| {
                     anyhow::anyhow!("BIND_ADDR must be a valid socket address: {error}")
                 })?,
+            wait_time: WaitTimeSettings {
+                estimator: std::env::var("WAIT_TIME_ESTIMATOR")
+                    .unwrap_or_else(|_| "batch".to_string())
+                    .parse()
+                    .map_err(|error| anyhow::anyhow!("WAIT_TIME_ESTIMATOR: {error}"))?,
+                history_window_minutes: std::env::var("WAIT_TIME_HISTORY_WINDOW_MINUTES")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .map_err(|error| {
+                        anyhow::anyhow!(
+                            "WAIT_TIME_HISTORY_WINDOW_MINUTES must be a number: {error}"
+                        )
+                    })?,
+            },
+            stock: StockSettings {
+                freshness_minutes: std::env::var("STOCK_FRESHNESS_MINUTES")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .map_err(|error| {
+                        anyhow::anyhow!("STOCK_FRESHNESS_MINUTES must be a number: {error}")
+                    })?,
+                auto_discard: std::env::var("STOCK_AUTO_DISCARD")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .map_err(|error| {
+                        anyhow::anyhow!("STOCK_AUTO_DISCARD must be true or false: {error}")
+                    })?,
+            },
+            display_numbers: DisplayNumberSettings {
+                regular: std::env::var("DISPLAY_NUMBER_POOL")
+                    .unwrap_or_else(|_| "01-99".to_string())
+                    .parse()
+                    .map_err(|error| anyhow::anyhow!("DISPLAY_NUMBER_POOL: {error}"))?,
+                priority: std::env::var("DISPLAY_NUMBER_PRIORITY_POOL")
+                    .ok()
+                    .map(|value| value.parse())
+                    .transpose()
+                    .map_err(|error| anyhow::anyhow!("DISPLAY_NUMBER_PRIORITY_POOL: {error}"))?,
+            },
+            pre_orders: PreOrderSettings {
+                slot_minutes: std::env::var("PRE_ORDER_SLOT_MINUTES")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .ok()
+                    .filter(|minutes| *minutes > 0)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("PRE_ORDER_SLOT_MINUTES must be a positive number")
+                    })?,
+                slot_capacity: std::env::var("PRE_ORDER_SLOT_CAPACITY")
+                    .unwrap_or_else(|_| "36".to_string())
+                    .parse()
+                    .map_err(|error| {
+                        anyhow::anyhow!("PRE_ORDER_SLOT_CAPACITY must be a number: {error}")
+                    })?,
+                lead_minutes: std::env::var("PRE_ORDER_LEAD_MINUTES")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .map_err(|error| {
+                        anyhow::anyhow!("PRE_ORDER_LEAD_MINUTES must be a number: {error}")
+                    })?,
+            },
+            order_limits: OrderLimits {
+                max_item_quantity: std::env::var("MAX_ITEM_QUANTITY")
+                    .unwrap_or_else(|_| "50".to_string())
+                    .parse()
+                    .ok()
+                    .filter(|quantity| *quantity > 0)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("MAX_ITEM_QUANTITY must be a positive number")
+                    })?,
+            },
+            no_shows: NoShowSettings {
+                reminder_minutes: std::env::var("NO_SHOW_REMINDER_MINUTES")
+                    .unwrap_or_else(|_| "15".to_string())
+                    .parse()
+                    .map_err(|error| {
+                        anyhow::anyhow!("NO_SHOW_REMINDER_MINUTES must be a number: {error}")
+                    })?,
+                expire_minutes: std::env::var("NO_SHOW_EXPIRE_MINUTES")
+                    .ok()
+                    .map(|value| value.parse())
+                    .transpose()
+                    .map_err(|error| {
+                        anyhow::anyhow!("NO_SHOW_EXPIRE_MINUTES must be a number: {error}")
+                    })?,
+            },
+            priority_aging: PriorityAging {
+                promote_every_minutes: std::env::var("PRIORITY_AGING_MINUTES")
+                    .ok()
+                    .map(|value| value.parse())
+                    .transpose()
+                    .map_err(|error| {
+                        anyhow::anyhow!("PRIORITY_AGING_MINUTES must be a number: {error}")
+                    })?,
+            },
+            allocation_policy: std::env::var("ALLOCATION_POLICY")
+                .unwrap_or_else(|_| "greedy".to_string())
+                .parse()
+                .map_err(|error| anyhow::anyhow!("ALLOCATION_POLICY: {error}"))?,
         })
     }
 }
@@ -1,14 +1,50 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use poise::serenity_prelude::*;
 
 use crate::{
+    app::AppRegistry,
     discord::CREATE_CHANNEL,
-    domain::snapshot::{Flavor, FlavorConfig, Item, Notify, OrderStatus},
+    domain::availability::{store_date, store_time},
+    domain::business_day::{self, BusinessDaySummary, StockDisposition},
+    domain::order_event::Actor,
+    domain::order_status::OrderTransition,
+    domain::pre_order,
+    domain::snapshot::{
+        Flavor, FlavorConfig, Item, MenuFlavor, Notify, OrderStatus, OrderSystemSnapshot,
+        PaymentMethod, PricingRuleKind, PriorityTier, StockReason,
+    },
+    domain::station,
+    usecase::{
+        business_day::BusinessDayError,
+        menu::AddFlavorError,
+        order::{NewOrder, OrderError},
+        production::BatchError,
+        stock::StockError,
+        undo::UndoError,
+    },
 };
 
 use super::PoiseContext;
 
+// Mutations are recorded as made by the Discord user running the command.
+fn acting_registry(ctx: PoiseContext<'_>) -> AppRegistry {
+    ctx.data().as_actor(Actor::Discord {
+        user_id: ctx.author().id.get(),
+    })
+}
+
+// Like `acting_registry`, for `/staff` commands, whose actions can be undone.
+fn staff_registry(ctx: PoiseContext<'_>) -> AppRegistry {
+    ctx.data().as_staff(Actor::Discord {
+        user_id: ctx.author().id.get(),
+    })
+}
+
 /// ユーザー向け注文コマンド
-#[poise::command(slash_command, subcommands("display", "details", "notify"))]
+#[poise::command(
+    slash_command,
+    subcommands("display", "details", "notify", "pickup_slots", "preorder")
+)]
 pub async fn orders(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
     ctx.say("サブコマンドを使用してください。例: `/orders display`")
         .await?;
@@ -85,8 +121,8 @@ async fn details(
             details.id,
             details.status,
             details
-                .estimated_wait_minutes
-                .map_or("N/A".to_string(), |m| format!("{} 分", m))
+                .estimate
+                .map_or("N/A".to_string(), |estimate| estimate.describe())
         );
         ctx.say(response).await?;
     } else {
@@ -101,7 +137,7 @@ async fn notify(
     ctx: PoiseContext<'_>,
     #[description = "注文ID"] id: u32,
 ) -> Result<(), anyhow::Error> {
-    let registry = ctx.data();
+    let registry = acting_registry(ctx);
     let snapshot = registry.snapshot().await?;
     let order = snapshot.orders.iter().find(|o| o.id == id);
 
@@ -113,7 +149,10 @@ async fn notify(
     }
 
     let order = order.unwrap().clone();
-    if order.status == OrderStatus::Completed || order.status == OrderStatus::Cancelled {
+    if matches!(
+        order.status,
+        OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Expired
+    ) {
         let builder = poise::CreateReply::default().content(format!(
             "注文 `{}` はすでに完了/キャンセルされています。",
             id
@@ -125,7 +164,13 @@ async fn notify(
     let items_str = order
         .items
         .iter()
-        .map(|item| format!("- {} x{}", item.flavor, item.quantity))
+        .map(|item| {
+            format!(
+                "- {} x{}",
+                snapshot.flavor_name(&item.flavor),
+                item.quantity
+            )
+        })
         .collect::<Vec<_>>()
         .join("\n");
 
@@ -180,7 +225,7 @@ async fn notify(
                     channel_id: ctx.channel_id().into(),
                     user_id: ctx.author().id.into(),
                 };
-                if registry.add_notification(id, payload).await?.is_some() {
+                if registry.add_notification(id, payload).await?.is_ok() {
                     (
                         "通知を登録しました。準備ができたらメンションでお知らせします。",
                         Colour::DARK_GREEN,
@@ -208,21 +253,99 @@ async fn notify(
     Ok(())
 }
 
+/// 予約できる受け取り時間帯を表示します
+#[poise::command(slash_command)]
+async fn pickup_slots(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let slots = ctx.data().get_pickup_slots().await?;
+    let lines = slots
+        .iter()
+        .filter(|slot| slot.remaining > 0)
+        .map(|slot| {
+            format!(
+                "- {}〜{} (残り{}個)",
+                store_time(slot.starts_at).format("%H:%M"),
+                store_time(slot.ends_at).format("%H:%M"),
+                slot.remaining
+            )
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        ctx.say("現在予約できる時間帯はありません。").await?;
+    } else {
+        ctx.say(format!("予約できる受け取り時間帯:\n{}", lines.join("\n")))
+            .await?;
+    }
+    Ok(())
+}
+
+/// 受け取り時刻を指定して予約注文します
+#[poise::command(slash_command)]
+async fn preorder(
+    ctx: PoiseContext<'_>,
+    #[description = "注文アイテム (例: tsubuan:20,custard:10)"] items: String,
+    #[description = "受け取り時刻 (例: 14:00)"] pickup: String,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let (items, pickup_at) = match parse_items(&snapshot, &items)
+        .and_then(|items| Ok((items, parse_pickup(&snapshot, &pickup)?)))
+    {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            ctx.say(message).await?;
+            return Ok(());
+        }
+    };
+    let notify = Notify::Discord {
+        channel_id: ctx.channel_id().into(),
+        user_id: ctx.author().id.into(),
+    };
+
+    let order = match acting_registry(ctx)
+        .create_order(NewOrder {
+            pickup_at: Some(pickup_at),
+            notify: [notify].into(),
+            ..NewOrder::new(items)
+        })
+        .await?
+    {
+        Ok(order) => order,
+        Err(error) => {
+            ctx.say(format_order_error(Some(&snapshot), &error)).await?;
+            return Ok(());
+        }
+    };
+
+    let pickup_at = order.pickup_at.unwrap_or(pickup_at);
+    ctx.say(format!(
+        "予約を受け付けました。呼び出し番号: #{} (合計: {}円)\n{}から受け取れます。準備ができたらメンションでお知らせします。",
+        order.display_number,
+        order.total_price,
+        store_time(pickup_at).format("%H:%M")
+    ))
+    .await?;
+    Ok(())
+}
+
 /// 現在の待ち時間を表示します
 #[poise::command(slash_command)]
 pub async fn waittime(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
     let wait_times = ctx.data().get_current_wait_times().await?;
     let mut fields = Vec::new();
 
-    for (flavor, time) in wait_times.wait_times.iter() {
-        let time_str = time.map_or("提供なし".into(), |t| {
-            if t == 0 {
-                "すぐに提供できます".into()
-            } else {
-                format!("約{}分", t)
-            }
-        });
-        fields.push((flavor.to_string(), time_str, false));
+    for wait_time in &wait_times.flavors {
+        let mut time_str = if wait_time.is_sold_out {
+            "売り切れ".into()
+        } else {
+            wait_time
+                .estimate
+                .map_or("提供なし".into(), |estimate| estimate.describe())
+        };
+        if !wait_time.is_sold_out
+            && let Some(remaining) = wait_time.remaining_quantity
+        {
+            time_str.push_str(&format!(" (残り{}個)", remaining));
+        }
+        fields.push((wait_time.display_name.clone(), time_str, false));
     }
 
     let embed = CreateEmbed::default()
@@ -236,34 +359,344 @@ pub async fn waittime(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
-// Helper function for parsing flavor from string
-fn parse_flavor(s: &str) -> Result<Flavor, &'static str> {
-    match s.to_lowercase().as_str() {
-        "tsubuan" => Ok(Flavor::Tsubuan),
-        "custard" => Ok(Flavor::Custard),
-        "kurikinton" => Ok(Flavor::Kurikinton),
-        _ => Err("不正なフレーバーです"),
+// Helper function for parsing flavor from string (id or display name in the menu catalog)
+fn parse_flavor(snapshot: &OrderSystemSnapshot, s: &str) -> Result<Flavor, &'static str> {
+    let s = s.trim();
+    snapshot
+        .menu
+        .iter()
+        .find(|entry| entry.id.as_str().eq_ignore_ascii_case(s) || entry.display_name == s)
+        .map(|entry| entry.id.clone())
+        .ok_or("不正なフレーバーです")
+}
+
+// Helper function for parsing items such as `tsubuan:2,custard:1`
+fn parse_items(snapshot: &OrderSystemSnapshot, s: &str) -> Result<Vec<Item>, String> {
+    s.split(',')
+        .map(|item_str| {
+            let Some((flavor, quantity)) = item_str.trim().split_once(':') else {
+                return Err(
+                    "アイテムのフォーマットが不正です。例: `tsubuan:2,custard:1,kurikinton:1`"
+                        .to_string(),
+                );
+            };
+            let flavor =
+                parse_flavor(snapshot, flavor).map_err(|e| format!("{}: `{}`", e, flavor))?;
+            let quantity = quantity
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("不正な数量です: `{}`", quantity))?;
+            Ok(Item { flavor, quantity })
+        })
+        .collect()
+}
+
+// Helper function for parsing a pickup time on the current business day
+fn parse_pickup(snapshot: &OrderSystemSnapshot, s: &str) -> Result<DateTime<Utc>, String> {
+    let date =
+        business_day::open_day(snapshot).map_or_else(|| store_date(Utc::now()), |day| day.date);
+    pre_order::parse_pickup_time(s, date)
+        .ok_or_else(|| format!("受け取り時刻が不正です: `{}` (例: `14:00`)", s.trim()))
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum PaymentMethodChoice {
+    #[name = "現金"]
+    Cash,
+    #[name = "QR決済"]
+    QrCode,
+    #[name = "チケット"]
+    Ticket,
+}
+
+impl From<PaymentMethodChoice> for PaymentMethod {
+    fn from(choice: PaymentMethodChoice) -> Self {
+        match choice {
+            PaymentMethodChoice::Cash => PaymentMethod::Cash,
+            PaymentMethodChoice::QrCode => PaymentMethod::QrCode,
+            PaymentMethodChoice::Ticket => PaymentMethod::Ticket,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum PriorityChoice {
+    #[name = "VIP"]
+    Vip,
+    #[name = "スタッフ"]
+    Staff,
+    #[name = "配慮が必要なお客様"]
+    Accessibility,
+    #[name = "通常"]
+    Normal,
+}
+
+impl From<PriorityChoice> for PriorityTier {
+    fn from(choice: PriorityChoice) -> Self {
+        match choice {
+            PriorityChoice::Vip => PriorityTier::Vip,
+            PriorityChoice::Staff => PriorityTier::Staff,
+            PriorityChoice::Accessibility => PriorityTier::Accessibility,
+            PriorityChoice::Normal => PriorityTier::Normal,
+        }
+    }
+}
+
+fn priority_name(priority: PriorityTier) -> &'static str {
+    match priority {
+        PriorityTier::Vip => "VIP",
+        PriorityTier::Staff => "スタッフ",
+        PriorityTier::Accessibility => "配慮が必要なお客様",
+        PriorityTier::Normal => "通常",
     }
 }
 
-// Helper choice enum for flavor
-#[derive(Debug, Clone, poise::ChoiceParameter)]
-pub enum FlavorChoice {
-    Tsubuan,
-    Custard,
-    Kurikinton,
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum WasteReasonChoice {
+    #[name = "落とした"]
+    Dropped,
+    #[name = "焦げた"]
+    Burnt,
+    #[name = "時間切れ"]
+    Stale,
+    #[name = "その他"]
+    Other,
 }
 
-impl From<FlavorChoice> for Flavor {
-    fn from(choice: FlavorChoice) -> Self {
+impl From<WasteReasonChoice> for StockReason {
+    fn from(choice: WasteReasonChoice) -> Self {
         match choice {
-            FlavorChoice::Tsubuan => Flavor::Tsubuan,
-            FlavorChoice::Custard => Flavor::Custard,
-            FlavorChoice::Kurikinton => Flavor::Kurikinton,
+            WasteReasonChoice::Dropped => StockReason::Dropped,
+            WasteReasonChoice::Burnt => StockReason::Burnt,
+            WasteReasonChoice::Stale => StockReason::Stale,
+            WasteReasonChoice::Other => StockReason::Other,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum StockDispositionChoice {
+    #[name = "翌日に持ち越す"]
+    CarryOver,
+    #[name = "廃棄する"]
+    WriteOff,
+}
+
+impl From<StockDispositionChoice> for StockDisposition {
+    fn from(choice: StockDispositionChoice) -> Self {
+        match choice {
+            StockDispositionChoice::CarryOver => StockDisposition::CarryOver,
+            StockDispositionChoice::WriteOff => StockDisposition::WriteOff,
+        }
+    }
+}
+
+fn stock_reason_name(reason: StockReason) -> &'static str {
+    match reason {
+        StockReason::Dropped => "落とした",
+        StockReason::Burnt => "焦げた",
+        StockReason::Stale => "時間切れ",
+        StockReason::Miscount => "数え直し",
+        StockReason::Other => "その他",
+    }
+}
+
+fn payment_method_name(payment_method: Option<PaymentMethod>) -> &'static str {
+    match payment_method {
+        Some(PaymentMethod::Cash) => "現金",
+        Some(PaymentMethod::QrCode) => "QR決済",
+        Some(PaymentMethod::Ticket) => "チケット",
+        None => "未記録",
+    }
+}
+
+// Helper autocomplete for flavor parameters, backed by the menu catalog
+async fn autocomplete_flavor(ctx: PoiseContext<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Ok(snapshot) = ctx.data().snapshot().await else {
+        return Vec::new();
+    };
+    snapshot
+        .menu
+        .iter()
+        .filter(|entry| {
+            entry.id.as_str().starts_with(partial) || entry.display_name.contains(partial)
+        })
+        .map(|entry| AutocompleteChoice::new(entry.display_name.clone(), entry.id.as_str()))
+        .collect()
+}
+
+fn status_name(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Waiting => "待機中",
+        OrderStatus::Cooking => "調理中",
+        OrderStatus::Ready => "受け取り準備完了",
+        OrderStatus::Completed => "完了",
+        OrderStatus::Cancelled => "キャンセル",
+        OrderStatus::Expired => "受け取り期限切れ",
+    }
+}
+
+// Helper function for explaining a rejected order operation to staff
+fn format_order_error(snapshot: Option<&OrderSystemSnapshot>, error: &OrderError) -> String {
+    let flavor_name = |flavor: &Flavor| {
+        snapshot.map_or(flavor.to_string(), |snapshot| {
+            snapshot.flavor_name(flavor).to_owned()
+        })
+    };
+    match error {
+        OrderError::EmptyItems => "アイテムが指定されていません。".to_string(),
+        OrderError::ZeroQuantity(flavor) => {
+            format!("`{}` の数量は1以上にしてください。", flavor_name(flavor))
+        }
+        OrderError::DuplicateFlavor(flavor) => {
+            format!("`{}` が複数回指定されています。", flavor_name(flavor))
+        }
+        OrderError::TooManyPieces { flavor, max } => {
+            format!("`{}` は1回の注文で{}個までです。", flavor_name(flavor), max)
+        }
+        OrderError::TotalTooLarge => "合計金額が大きすぎます。".to_string(),
+        OrderError::NotOnSale(flavor) => {
+            format!("`{}` は販売していません。", flavor_name(flavor))
+        }
+        OrderError::UnknownPricingRule(code) => format!("コード `{}` は使用できません。", code),
+        OrderError::PricingRuleNotApplicable(code) => {
+            format!("この注文には `{}` を適用できません。", code)
+        }
+        OrderError::ExceedsRemaining { flavor, remaining } => format!(
+            "`{}` は残り{}個のため注文できません。",
+            flavor_name(flavor),
+            remaining
+        ),
+        OrderError::NotFound(id) => format!("注文 `{}` が見つかりません。", id),
+        OrderError::NotCompleted(id) => format!(
+            "注文 `{}` はまだ受け渡されていません。キャンセルしてください。",
+            id
+        ),
+        OrderError::AlreadyRefunded(id) => format!("注文 `{}` はすでに返金済みです。", id),
+        OrderError::NotEditable { id, status } => format!(
+            "注文 `{}` は{}のため変更・受け渡しできません。",
+            id,
+            status_name(*status)
+        ),
+        OrderError::BelowHandedOver {
+            flavor,
+            handed_over,
+        } => format!(
+            "`{}` はすでに{}個受け渡し済みのため、それより減らせません。",
+            flavor_name(flavor),
+            handed_over
+        ),
+        OrderError::ExceedsOutstanding {
+            flavor,
+            outstanding,
+        } => format!(
+            "`{}` の未受け渡しは{}個です。",
+            flavor_name(flavor),
+            outstanding
+        ),
+        OrderError::NotInStock { flavor, available } => {
+            format!(
+                "`{}` はこの注文に回せる在庫が{}個です。",
+                flavor_name(flavor),
+                available
+            )
+        }
+        OrderError::PickupTooSoon { earliest } => format!(
+            "予約は{}からの枠で受け付けています。",
+            store_time(*earliest).format("%H:%M")
+        ),
+        OrderError::PickupOutsideBusinessDay => {
+            "予約は本日の営業日の枠のみ受け付けています。".to_string()
+        }
+        OrderError::PickupSlotFull {
+            starts_at,
+            remaining,
+        } => format!(
+            "{}の枠は残り{}個までしか予約できません。",
+            store_time(*starts_at).format("%H:%M"),
+            remaining
+        ),
+        OrderError::BusinessDayNotOpen => {
+            "営業が開始されていません。`/staff open_day` で開店してください。".to_string()
+        }
+        OrderError::BusinessDayClosed(id) => {
+            format!("注文 `{}` の営業日はすでに締められています。", id)
+        }
+        OrderError::DisplayNumbersExhausted => {
+            "呼び出し番号がすべて使用中です。受け渡し済みの注文を完了にしてください。".to_string()
+        }
+        OrderError::NothingToHandOver(id) => {
+            format!("注文 `{}` の未受け渡し分はまだ在庫にありません。", id)
+        }
+        OrderError::FullyHandedOver(id) => {
+            format!("注文 `{}` はすべてお渡し済みのため再開できません。", id)
+        }
+        OrderError::InvalidTransition {
+            id,
+            from,
+            transition,
+        } => format!(
+            "注文 `{}` は{}のため{}できません。",
+            id,
+            status_name(*from),
+            match transition {
+                OrderTransition::Complete => "完了に",
+                OrderTransition::Cancel => "キャンセル",
+                OrderTransition::Reopen => "受け渡し前に戻すことは",
+                OrderTransition::Restore => "元に戻すことは",
+                OrderTransition::Expire => "期限切れに",
+            }
+        ),
+    }
+}
+
+// Helper autocomplete for batches on the grill
+async fn autocomplete_active_batch(
+    ctx: PoiseContext<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let Ok(snapshot) = ctx.data().snapshot().await else {
+        return Vec::new();
+    };
+    snapshot
+        .active_batches()
+        .filter(|batch| batch.id.to_string().starts_with(partial))
+        .map(|batch| {
+            AutocompleteChoice::new(
+                format!(
+                    "#{} {} x {}",
+                    batch.id,
+                    snapshot.flavor_name(&batch.flavor),
+                    batch.quantity
+                ),
+                batch.id,
+            )
+        })
+        .collect()
+}
+
+// Helper autocomplete for bundle and coupon codes
+async fn autocomplete_pricing_rule(
+    ctx: PoiseContext<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let Ok(snapshot) = ctx.data().snapshot().await else {
+        return Vec::new();
+    };
+    snapshot
+        .pricing_rules
+        .iter()
+        .filter(|rule| rule.is_active)
+        .filter(|rule| {
+            rule.code
+                .to_ascii_lowercase()
+                .starts_with(&partial.to_ascii_lowercase())
+                || rule.name.contains(partial)
+        })
+        .map(|rule| AutocompleteChoice::new(rule.name.clone(), rule.code.as_str()))
+        .collect()
+}
+
 /// スタッフ向け管理コマンド
 #[poise::command(
     slash_command,
@@ -271,11 +704,39 @@ impl From<FlavorChoice> for Flavor {
         "get_orders",
         "create_order",
         "update_production",
+        "plan",
+        "stock_lots",
+        "discard_stock",
+        "adjust_stock",
+        "waste",
+        "batches",
+        "start_batch",
+        "finish_batch",
         "complete_order",
         "cancel_order",
+        "reopen_order",
+        "restore_order",
+        "refund_order",
+        "undo",
+        "update_order_items",
+        "hand_over",
         "update_order_priority",
+        "revenue",
+        "open_day",
+        "close_day",
+        "day_summary",
+        "pricing_rules",
         "get_flavor_configs",
-        "set_flavor_config"
+        "set_flavor_config",
+        "add_flavor",
+        "set_flavor_active",
+        "set_flavor_price",
+        "set_sold_out",
+        "set_daily_limit",
+        "stations",
+        "add_station",
+        "set_station_plates",
+        "set_flavor_station"
     ),
     guild_only
 )]
@@ -305,6 +766,7 @@ async fn get_orders(
                     "ready" => Some(OrderStatus::Ready),
                     "completed" => Some(OrderStatus::Completed),
                     "cancelled" => Some(OrderStatus::Cancelled),
+                    "expired" => Some(OrderStatus::Expired),
                     _ => None,
                 }
             })
@@ -328,12 +790,34 @@ async fn get_orders(
         let items_str = order
             .items
             .iter()
-            .map(|i| format!("{} x {}", i.flavor, i.quantity))
+            .map(|i| {
+                let handed_over = order.handed_over_quantity(&i.flavor);
+                if handed_over > 0 {
+                    format!(
+                        "{} x {} (受け渡し済み {})",
+                        snapshot.flavor_name(&i.flavor),
+                        i.quantity,
+                        handed_over
+                    )
+                } else {
+                    format!("{} x {}", snapshot.flavor_name(&i.flavor), i.quantity)
+                }
+            })
             .collect::<Vec<_>>()
             .join(", ");
+        let no_show = if order.status == OrderStatus::Ready && order.no_show_at.is_some() {
+            " | ⚠ 未受け取り"
+        } else {
+            ""
+        };
         response.push_str(&format!(
-            "ID: `{}` | Status: `{:?}` | Priority: `{}` | Items: `{}`\n",
-            order.id, order.status, order.is_priority, items_str
+            "ID: `{}` | Status: `{:?}` | Priority: `{}` | Items: `{}` | Total: `{}円`{}\n",
+            order.id,
+            order.status,
+            priority_name(order.priority),
+            items_str,
+            order.total_price,
+            no_show
         ));
     }
 
@@ -358,45 +842,69 @@ async fn get_orders(
 async fn create_order(
     ctx: PoiseContext<'_>,
     #[description = "注文アイテム (例: tsubuan:2,custard:1,kurikinton:1)"] items: String,
-    #[description = "優先注文にするか"] is_priority: Option<bool>,
+    #[description = "優先度 (省略時は通常)"] priority: Option<PriorityChoice>,
+    #[description = "支払い方法"] payment_method: Option<PaymentMethodChoice>,
+    #[description = "セット・クーポンのコード"]
+    #[autocomplete = "autocomplete_pricing_rule"]
+    pricing_rule: Option<String>,
+    #[description = "受け取り時刻 (例: 14:00)。指定すると予約注文になります"] pickup: Option<
+        String,
+    >,
 ) -> Result<(), anyhow::Error> {
-    let mut parsed_items = Vec::<Item>::new();
-    for item_str in items.split(',') {
-        let parts: Vec<&str> = item_str.trim().split(':').collect();
-        if parts.len() != 2 {
-            ctx.say("アイテムのフォーマットが不正です。例: `tsubuan:2,custard:1,kurikinton:1`")
-                .await?;
+    let snapshot = ctx.data().snapshot().await?;
+    let parsed_items = match parse_items(&snapshot, &items) {
+        Ok(items) => items,
+        Err(message) => {
+            ctx.say(message).await?;
             return Ok(());
         }
-        let flavor = match parse_flavor(parts[0]) {
-            Ok(f) => f,
-            Err(e) => {
-                ctx.say(format!("{}: `{}`", e, parts[0])).await?;
-                return Ok(());
-            }
-        };
-        let quantity = match parts[1].parse::<usize>() {
-            Ok(q) => q,
-            Err(_) => {
-                ctx.say(format!("不正な数量です: `{}`", parts[1])).await?;
-                return Ok(());
-            }
-        };
-        parsed_items.push(Item { flavor, quantity });
-    }
-
-    if parsed_items.is_empty() {
-        ctx.say("アイテムが指定されていません。").await?;
-        return Ok(());
-    }
+    };
+    let pickup_at = match pickup
+        .map(|pickup| parse_pickup(&snapshot, &pickup))
+        .transpose()
+    {
+        Ok(pickup_at) => pickup_at,
+        Err(message) => {
+            ctx.say(message).await?;
+            return Ok(());
+        }
+    };
 
-    let new_order = ctx
-        .data()
-        .create_order(parsed_items, is_priority.unwrap_or(false))
-        .await?;
+    let new_order = match staff_registry(ctx)
+        .create_order(NewOrder {
+            items: parsed_items,
+            priority: priority.map(PriorityTier::from).unwrap_or_default(),
+            payment_method: payment_method.map(PaymentMethod::from),
+            pricing_rule: pricing_rule.map(|code| code.trim().to_owned()),
+            pickup_at,
+            ..Default::default()
+        })
+        .await?
+    {
+        Ok(order) => order,
+        Err(error) => {
+            ctx.say(format_order_error(Some(&snapshot), &error)).await?;
+            return Ok(());
+        }
+    };
 
-    ctx.say(format!("新しい注文を作成しました。ID: {}", new_order.id))
-        .await?;
+    let discount_str = match &new_order.pricing_rule {
+        Some(code) => format!(", `{}` で{}円引き", code, new_order.discount_amount),
+        None => String::new(),
+    };
+    let pickup_str = match new_order.pickup_at {
+        Some(pickup_at) => format!(", 受け取り: {}〜", store_time(pickup_at).format("%H:%M")),
+        None => String::new(),
+    };
+    ctx.say(format!(
+        "新しい注文を作成しました。ID: {} (合計: {}円{}, 支払い: {}{})",
+        new_order.id,
+        new_order.total_price,
+        discount_str,
+        payment_method_name(new_order.payment_method),
+        pickup_str
+    ))
+    .await?;
 
     Ok(())
 }
@@ -407,6 +915,7 @@ async fn update_production(
     ctx: PoiseContext<'_>,
     #[description = "生産したアイテム (例: tsubuan:9,custard:9)"] items: String,
 ) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
     let mut parsed_items = Vec::<Item>::new();
     for item_str in items.split(',') {
         let parts: Vec<&str> = item_str.trim().split(':').collect();
@@ -415,7 +924,7 @@ async fn update_production(
                 .await?;
             return Ok(());
         }
-        let flavor = match parse_flavor(parts[0]) {
+        let flavor = match parse_flavor(&snapshot, parts[0]) {
             Ok(f) => f,
             Err(e) => {
                 ctx.say(format!("{}: `{}`", e, parts[0])).await?;
@@ -437,8 +946,13 @@ async fn update_production(
         return Ok(());
     }
 
-    let (newly_ready_orders, unallocated_items) =
-        ctx.data().update_production(parsed_items).await?;
+    let Some((newly_ready_orders, unallocated_items)) =
+        staff_registry(ctx).update_production(parsed_items).await?
+    else {
+        ctx.say("メニューにないフレーバーが含まれています。")
+            .await?;
+        return Ok(());
+    };
 
     let ready_str = if newly_ready_orders.is_empty() {
         "なし".to_string()
@@ -455,7 +969,7 @@ async fn update_production(
     } else {
         unallocated_items
             .iter()
-            .map(|item| format!("{} x {}", item.flavor, item.quantity))
+            .map(|item| format!("{} x {}", snapshot.flavor_name(&item.flavor), item.quantity))
             .collect::<Vec<_>>()
             .join(", ")
     };
@@ -469,87 +983,1186 @@ async fn update_production(
     Ok(())
 }
 
-/// 注文を完了にします
+/// 焼いているバッチの一覧を表示します
 #[poise::command(slash_command)]
-async fn complete_order(
-    ctx: PoiseContext<'_>,
-    #[description = "注文ID"] id: u32,
-) -> Result<(), anyhow::Error> {
-    if let Some(order) = ctx.data().complete_order(id).await? {
-        ctx.say(format!("注文 `{}` を完了にしました。", order.id))
-            .await?;
+async fn batches(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let now = Utc::now();
+    let mut response = String::from("## 焼いているバッチ\n");
+    for batch in snapshot.active_batches() {
+        response.push_str(&format!(
+            "- ID: `{}` | {} x {} | 残り{}分\n",
+            batch.id,
+            snapshot.flavor_name(&batch.flavor),
+            batch.quantity,
+            station::remaining_cooking_minutes(&snapshot, batch, now)
+        ));
+    }
+    if snapshot.active_batches().next().is_none() {
+        response.push_str("なし\n");
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// 次に焼くバッチのおすすめを表示します
+#[poise::command(slash_command)]
+async fn plan(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let plan = ctx.data().get_production_plan().await?;
+    let mut response = String::from("## 次に焼くバッチ\n");
+    for batch in &plan.batches {
+        let start = if batch.start_in_minutes == 0 {
+            "今すぐ".to_owned()
+        } else {
+            format!("{}分後", batch.start_in_minutes)
+        };
+        let order_ids = batch
+            .order_ids
+            .iter()
+            .map(|id| format!("`{id}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        response.push_str(&format!(
+            "- {} x {} | {}に開始 ({}分後に焼き上がり) | 注文: {}\n",
+            batch.display_name, batch.quantity, start, batch.ready_in_minutes, order_ids
+        ));
+    }
+    if plan.batches.is_empty() {
+        response.push_str("待っている注文はすべて在庫と焼いているバッチでまかなえます。\n");
     } else {
-        ctx.say(format!("注文 `{}` が見つかりません。", id)).await?;
+        response.push_str(&format!(
+            "この順に焼くと待ち時間の合計は約{}分です。\n",
+            plan.total_wait_minutes
+        ));
+    }
+    if !plan.unplannable_flavors.is_empty() {
+        let snapshot = ctx.data().snapshot().await?;
+        let names = plan
+            .unplannable_flavors
+            .iter()
+            .map(|flavor| snapshot.flavor_name(flavor))
+            .collect::<Vec<_>>()
+            .join(", ");
+        response.push_str(&format!("⚠️ 焼き方が設定されていないフレーバー: {names}\n"));
     }
+    ctx.say(response).await?;
     Ok(())
 }
 
-/// 注文をキャンセルします
+/// 在庫を焼き上がり時刻ごとに表示します
 #[poise::command(slash_command)]
-async fn cancel_order(
+async fn stock_lots(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let lots = ctx.data().get_stock_lots().await?;
+    let mut response = String::from("## 在庫\n");
+    for lot in &lots {
+        response.push_str(&format!(
+            "- {}{} x {} | 焼き上がりから{}分\n",
+            if lot.is_expired { "⚠️ " } else { "" },
+            lot.display_name,
+            lot.quantity,
+            lot.age_minutes
+        ));
+    }
+    if lots.is_empty() {
+        response.push_str("なし\n");
+    } else if lots.iter().any(|lot| lot.is_expired) {
+        response.push_str(
+            "⚠️ は提供期限を過ぎています。`/staff discard_stock` で廃棄を記録してください。\n",
+        );
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// 在庫の廃棄を記録します
+#[poise::command(slash_command)]
+async fn discard_stock(
     ctx: PoiseContext<'_>,
-    #[description = "注文ID"] id: u32,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "個数"] quantity: usize,
+    #[description = "理由"] reason: WasteReasonChoice,
+    #[description = "メモ"] note: Option<String>,
 ) -> Result<(), anyhow::Error> {
-    if let Some(order) = ctx.data().cancel_order(id).await? {
-        ctx.say(format!("注文 `{}` をキャンセルしました。", order.id))
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    match staff_registry(ctx)
+        .discard_stock(flavor, quantity, reason.into(), note)
+        .await?
+    {
+        Ok(adjustment) => {
+            ctx.say(format!(
+                "{} x {} の廃棄を記録しました (理由: {})。",
+                snapshot.flavor_name(&adjustment.flavor),
+                adjustment.delta.unsigned_abs(),
+                stock_reason_name(adjustment.reason)
+            ))
             .await?;
-    } else {
-        ctx.say(format!("注文 `{}` が見つかりません。", id)).await?;
+        }
+        Err(error) => {
+            ctx.say(format_stock_error(&error)).await?;
+        }
     }
     Ok(())
 }
 
-/// 注文の優先度を更新します
+/// 数え直した在庫数に合わせます
 #[poise::command(slash_command)]
-async fn update_order_priority(
+async fn adjust_stock(
     ctx: PoiseContext<'_>,
-    #[description = "注文ID"] id: u32,
-    #[description = "優先注文にするか"] is_priority: bool,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "実際の在庫数"] quantity: usize,
+    #[description = "メモ"] note: Option<String>,
 ) -> Result<(), anyhow::Error> {
-    if let Some(order) = ctx.data().update_order_priority(id, is_priority).await? {
-        ctx.say(format!(
-            "注文 `{}` の優先度を `{}` に更新しました。",
-            order.id, is_priority
-        ))
-        .await?;
-    } else {
-        ctx.say(format!("注文 `{}` が見つかりません。", id)).await?;
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    match staff_registry(ctx)
+        .adjust_stock(flavor, quantity, note)
+        .await?
+    {
+        Ok(adjustment) => {
+            ctx.say(format!(
+                "{} の在庫を{}個に修正しました ({:+})。",
+                snapshot.flavor_name(&adjustment.flavor),
+                quantity,
+                adjustment.delta
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_stock_error(&error)).await?;
+        }
     }
     Ok(())
 }
 
-/// フレーバーの設定を取得します
+fn format_stock_error(error: &StockError) -> String {
+    match error {
+        StockError::UnknownFlavor => "メニューにないフレーバーです。".to_owned(),
+        StockError::ZeroQuantity => "個数は1以上にしてください。".to_owned(),
+        StockError::NotWasteReason => {
+            "数え直しは `/staff adjust_stock` で記録してください。".to_owned()
+        }
+        StockError::InsufficientStock { available } => {
+            format!("在庫が足りません (現在の在庫: {available}個)。")
+        }
+        StockError::Unchanged => "記録されている在庫数と同じです。".to_owned(),
+    }
+}
+
+/// 1日の廃棄を集計します
 #[poise::command(slash_command)]
-async fn get_flavor_configs(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
-    let snapshot = ctx.data().snapshot().await?;
-    let mut response = String::new();
-    response.push_str("## フレーバー設定一覧\n");
-    for (flavor, config) in snapshot.flavor_configs.iter() {
+async fn waste(
+    ctx: PoiseContext<'_>,
+    #[description = "日付 (例: 2026-01-01, 省略すると今日)"] date: Option<String>,
+) -> Result<(), anyhow::Error> {
+    let date = match date.map(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")) {
+        None => None,
+        Some(Ok(date)) => Some(date),
+        Some(Err(_)) => {
+            ctx.say("日付のフォーマットが不正です。例: `2026-01-01`")
+                .await?;
+            return Ok(());
+        }
+    };
+    let report = ctx.data().get_waste_report(date).await?;
+
+    let mut response = format!(
+        "## {} の廃棄\n- 合計: {}個 ({}円)\n",
+        report.date, report.total_quantity, report.total_value
+    );
+    for entry in &report.by_flavor {
+        let reasons = entry
+            .by_reason
+            .iter()
+            .map(|waste| format!("{} {}", stock_reason_name(waste.reason), waste.quantity))
+            .collect::<Vec<_>>()
+            .join(", ");
         response.push_str(&format!(
-            "- **{}**: 調理時間: {}分, バッチ生産数: {}\n",
-            flavor, config.cooking_time_minutes, config.quantity_per_batch
+            "- {}: {}個, {}円 ({})\n",
+            entry.display_name, entry.quantity, entry.value, reasons
         ));
     }
     ctx.say(response).await?;
     Ok(())
 }
 
-/// フレーバーの設定を更新します
+/// バッチを焼き始めます
 #[poise::command(slash_command)]
-async fn set_flavor_config(
+async fn start_batch(
     ctx: PoiseContext<'_>,
-    #[description = "フレーバー"] flavor: FlavorChoice,
-    #[description = "調理時間(分)"] cooking_time_minutes: u32,
-    #[description = "バッチあたりの生産数"] quantity_per_batch: u32,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "個数 (省略するとバッチあたりの生産数)"] quantity: Option<usize>,
 ) -> Result<(), anyhow::Error> {
-    let flavor: Flavor = flavor.into();
-    let config = FlavorConfig {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    match staff_registry(ctx).start_batch(flavor, quantity).await? {
+        Ok(batch) => {
+            ctx.say(format!(
+                "バッチ `{}` ({} x {}) を焼き始めました。",
+                batch.id,
+                snapshot.flavor_name(&batch.flavor),
+                batch.quantity
+            ))
+            .await?;
+        }
+        Err(BatchError::ZeroQuantity) => {
+            ctx.say("個数は1以上にしてください。").await?;
+        }
+        Err(_) => {
+            ctx.say("メニューにないフレーバーです。").await?;
+        }
+    }
+    Ok(())
+}
+
+/// バッチが焼き上がったことを報告します
+#[poise::command(slash_command)]
+async fn finish_batch(
+    ctx: PoiseContext<'_>,
+    #[description = "バッチID"]
+    #[autocomplete = "autocomplete_active_batch"]
+    id: u32,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    match staff_registry(ctx).finish_batch(id).await? {
+        Ok((batch, newly_ready_orders)) => {
+            let ready_str = if newly_ready_orders.is_empty() {
+                "なし".to_string()
+            } else {
+                newly_ready_orders
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            ctx.say(format!(
+                "バッチ `{}` ({} x {}) が焼き上がりました。\n新たに準備完了になった注文: {}",
+                batch.id,
+                snapshot.flavor_name(&batch.flavor),
+                batch.quantity,
+                ready_str
+            ))
+            .await?;
+        }
+        Err(BatchError::AlreadyFinished) => {
+            ctx.say(format!("バッチ `{}` はすでに焼き上がっています。", id))
+                .await?;
+        }
+        Err(_) => {
+            ctx.say(format!("バッチ `{}` が見つかりません。", id))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// 注文を完了にします
+#[poise::command(slash_command)]
+async fn complete_order(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).complete_order(id).await? {
+        Ok(order) => {
+            ctx.say(format!("注文 `{}` を完了にしました。", order.id))
+                .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(None, &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 注文をキャンセルします
+#[poise::command(slash_command)]
+async fn cancel_order(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).cancel_order(id).await? {
+        Ok(order) => {
+            ctx.say(format!("注文 `{}` をキャンセルしました。", order.id))
+                .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(None, &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 誤って完了にした注文を受け渡し前に戻します
+#[poise::command(slash_command)]
+async fn reopen_order(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).reopen_order(id).await? {
+        Ok(order) => {
+            ctx.say(format!(
+                "注文 `{}` を受け渡し前に戻しました。(現在: {})",
+                order.id,
+                status_name(order.status)
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(None, &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 誤ってキャンセルした注文を元に戻します
+#[poise::command(slash_command)]
+async fn restore_order(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).restore_order(id).await? {
+        Ok(order) => {
+            ctx.say(format!(
+                "注文 `{}` のキャンセルを取り消しました。(現在: {})",
+                order.id,
+                status_name(order.status)
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(None, &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 受け渡し済みの注文を返金済みにします
+#[poise::command(slash_command)]
+async fn refund_order(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).refund_order(id).await? {
+        Ok(order) => {
+            ctx.say(format!(
+                "注文 `{}` を返金済みにしました。({}円)",
+                order.id, order.total_price
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(None, &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// スタッフの直前の操作を取り消します
+#[poise::command(slash_command)]
+async fn undo(
+    ctx: PoiseContext<'_>,
+    #[description = "取り消す操作 (省略すると直前の操作)"]
+    #[autocomplete = "autocomplete_undo"]
+    id: Option<u32>,
+) -> Result<(), anyhow::Error> {
+    let message = match staff_registry(ctx).undo(id).await? {
+        Ok(entry) => format!("{} を取り消しました。", entry.action.description()),
+        Err(UndoError::NothingToUndo) => "取り消せる操作がありません。".to_string(),
+        Err(UndoError::Order(error)) => format_order_error(None, &error),
+        Err(UndoError::PiecesHandedOver(flavor)) => format!(
+            "`{}` はすでにお客様に渡しているため、生産報告を取り消せません。",
+            flavor
+        ),
+        Err(UndoError::FlavorGone(flavor)) => {
+            format!("`{}` または割り当て先の鉄板が見つかりません。", flavor)
+        }
+        Err(UndoError::NotUndoable) => {
+            "受け渡しは取り消せません。それより前の操作は番号を指定して取り消してください。"
+                .to_string()
+        }
+    };
+    ctx.say(message).await?;
+    Ok(())
+}
+
+// Helper autocomplete for actions that can be undone
+async fn autocomplete_undo(ctx: PoiseContext<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    ctx.data()
+        .undoable_actions()
+        .await
+        .into_iter()
+        .map(|entry| (entry.id, entry.action.description()))
+        .filter(|(_, description)| description.contains(partial))
+        .map(|(id, description)| AutocompleteChoice::new(format!("#{id} {description}"), id))
+        .collect()
+}
+
+/// 1日の売上を集計します
+#[poise::command(slash_command)]
+async fn revenue(
+    ctx: PoiseContext<'_>,
+    #[description = "日付 (例: 2026-01-01, 省略すると今日)"] date: Option<String>,
+) -> Result<(), anyhow::Error> {
+    let date = match date.map(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")) {
+        None => None,
+        Some(Ok(date)) => Some(date),
+        Some(Err(_)) => {
+            ctx.say("日付のフォーマットが不正です。例: `2026-01-01`")
+                .await?;
+            return Ok(());
+        }
+    };
+    let summary = ctx.data().get_revenue_summary(date).await?;
+
+    let mut response = format!("## {} の売上\n", summary.date);
+    response.push_str(&format!(
+        "- 注文数: {}件 / 売上: {}円\n- キャンセル・期限切れ: {}件 ({}円)\n- 返金: {}件 ({}円)\n- **差引売上: {}円**\n",
+        summary.order_count,
+        summary.gross_sales,
+        summary.cancelled_count,
+        summary.cancelled_amount,
+        summary.refunded_count,
+        summary.refunded_amount,
+        summary.net_sales
+    ));
+    for entry in &summary.by_payment_method {
+        response.push_str(&format!(
+            "- {}: {}件, {}円\n",
+            payment_method_name(entry.payment_method),
+            entry.order_count,
+            entry.net_sales
+        ));
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// 営業を開始します
+#[poise::command(slash_command)]
+async fn open_day(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).open_business_day().await? {
+        Ok(day) => {
+            ctx.say(format!(
+                "{} の営業を開始しました。呼び出し番号は最初から振り直されます。",
+                day.date
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_business_day_error(&error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 営業を終了し、1日の集計を表示します
+#[poise::command(slash_command)]
+async fn close_day(
+    ctx: PoiseContext<'_>,
+    #[description = "売れ残りの在庫"] stock: StockDispositionChoice,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx).close_business_day(stock.into()).await? {
+        Ok(summary) => {
+            let snapshot = ctx.data().snapshot().await?;
+            ctx.say(format!(
+                "営業を終了しました。未完了の注文はキャンセルし、お客様に通知しました。\n{}",
+                format_business_day_summary(&snapshot, &summary)
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_business_day_error(&error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 営業日の集計を表示します
+#[poise::command(slash_command)]
+async fn day_summary(
+    ctx: PoiseContext<'_>,
+    #[description = "日付 (例: 2026-01-01, 省略すると今日)"] date: Option<String>,
+) -> Result<(), anyhow::Error> {
+    let date = match date.map(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")) {
+        None => None,
+        Some(Ok(date)) => Some(date),
+        Some(Err(_)) => {
+            ctx.say("日付のフォーマットが不正です。例: `2026-01-01`")
+                .await?;
+            return Ok(());
+        }
+    };
+    let summary = ctx.data().get_business_day_summary(date).await?;
+    let snapshot = ctx.data().snapshot().await?;
+    ctx.say(format_business_day_summary(&snapshot, &summary))
+        .await?;
+    Ok(())
+}
+
+fn format_business_day_error(error: &BusinessDayError) -> String {
+    match error {
+        BusinessDayError::AlreadyOpen(date) => format!(
+            "{date} の営業がまだ終了していません。先に `/staff close_day` で締めてください。"
+        ),
+        BusinessDayError::NotOpen => "営業中の日がありません。".to_owned(),
+    }
+}
+
+fn format_business_day_summary(
+    snapshot: &OrderSystemSnapshot,
+    summary: &BusinessDaySummary,
+) -> String {
+    let status = match (summary.opened_at, summary.closed_at) {
+        (None, _) => "営業記録なし",
+        (Some(_), None) => "営業中",
+        (Some(_), Some(_)) => "営業終了",
+    };
+    let mut response = format!("## {} の集計 ({})\n", summary.date, status);
+    response.push_str(&format!(
+        "- 受け渡し済み: {}件\n- **差引売上: {}円** (キャンセル {}件, 返金 {}件)\n- 廃棄: {}個 ({}円)\n",
+        summary.completed_count,
+        summary.revenue.net_sales,
+        summary.revenue.cancelled_count,
+        summary.revenue.refunded_count,
+        summary.waste.total_quantity,
+        summary.waste.total_value
+    ));
+    for item in &summary.sold {
+        response.push_str(&format!(
+            "- {}: {}個\n",
+            snapshot.flavor_name(&item.flavor),
+            item.quantity
+        ));
+    }
+    if let Some(stock_disposition) = summary.stock_disposition {
+        response.push_str(match stock_disposition {
+            StockDisposition::CarryOver => "売れ残りは翌日に持ち越しました。\n",
+            StockDisposition::WriteOff => "売れ残りは廃棄しました。\n",
+        });
+    }
+    response
+}
+
+/// セット・割引の一覧を表示します
+#[poise::command(slash_command)]
+async fn pricing_rules(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let mut response = String::from("## セット・割引一覧\n");
+    for rule in &snapshot.pricing_rules {
+        let kind_str = match rule.kind {
+            PricingRuleKind::Bundle { quantity, price } => {
+                format!("{}個で{}円", quantity, price)
+            }
+            PricingRuleKind::PercentageDiscount { percent } => format!("{}%引き", percent),
+            PricingRuleKind::FixedDiscount { amount } => format!("{}円引き", amount),
+        };
+        response.push_str(&format!(
+            "- **{}** (`{}`{}): {}\n",
+            rule.name,
+            rule.code,
+            if rule.is_active { "" } else { ", 停止中" },
+            kind_str
+        ));
+    }
+    if snapshot.pricing_rules.is_empty() {
+        response.push_str("登録されていません。\n");
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// 受け渡し前の注文の内容を変更します
+#[poise::command(slash_command)]
+async fn update_order_items(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+    #[description = "変更後の注文アイテム (例: tsubuan:1,custard:1)"] items: String,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let mut parsed_items = Vec::<Item>::new();
+    for item_str in items.split(',') {
+        let parts: Vec<&str> = item_str.trim().split(':').collect();
+        if parts.len() != 2 {
+            ctx.say("アイテムのフォーマットが不正です。例: `tsubuan:1,custard:1`")
+                .await?;
+            return Ok(());
+        }
+        let flavor = match parse_flavor(&snapshot, parts[0]) {
+            Ok(f) => f,
+            Err(e) => {
+                ctx.say(format!("{}: `{}`", e, parts[0])).await?;
+                return Ok(());
+            }
+        };
+        let quantity = match parts[1].parse::<usize>() {
+            Ok(q) => q,
+            Err(_) => {
+                ctx.say(format!("不正な数量です: `{}`", parts[1])).await?;
+                return Ok(());
+            }
+        };
+        parsed_items.push(Item { flavor, quantity });
+    }
+
+    match staff_registry(ctx)
+        .update_order_items(id, parsed_items)
+        .await?
+    {
+        Ok(order) => {
+            let items_str = order
+                .items
+                .iter()
+                .map(|item| format!("{} x {}", snapshot.flavor_name(&item.flavor), item.quantity))
+                .collect::<Vec<_>>()
+                .join(", ");
+            ctx.say(format!(
+                "注文 `{}` の内容を {} に変更しました (合計: {}円, 状態: {})。",
+                order.id,
+                items_str,
+                order.total_price,
+                status_name(order.status)
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(Some(&snapshot), &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 注文のうち用意できた分だけを先に受け渡します
+#[poise::command(slash_command)]
+async fn hand_over(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+    #[description = "受け渡すアイテム (例: tsubuan:4, 省略すると在庫にある分すべて)"] items: Option<
+        String,
+    >,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let parsed_items = match items {
+        None => None,
+        Some(items) => {
+            let mut parsed_items = Vec::<Item>::new();
+            for item_str in items.split(',') {
+                let parts: Vec<&str> = item_str.trim().split(':').collect();
+                if parts.len() != 2 {
+                    ctx.say("アイテムのフォーマットが不正です。例: `tsubuan:4`")
+                        .await?;
+                    return Ok(());
+                }
+                let flavor = match parse_flavor(&snapshot, parts[0]) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        ctx.say(format!("{}: `{}`", e, parts[0])).await?;
+                        return Ok(());
+                    }
+                };
+                let quantity = match parts[1].parse::<usize>() {
+                    Ok(q) => q,
+                    Err(_) => {
+                        ctx.say(format!("不正な数量です: `{}`", parts[1])).await?;
+                        return Ok(());
+                    }
+                };
+                parsed_items.push(Item { flavor, quantity });
+            }
+            Some(parsed_items)
+        }
+    };
+
+    match staff_registry(ctx)
+        .hand_over_items(id, parsed_items)
+        .await?
+    {
+        Ok(order) if order.status == OrderStatus::Completed => {
+            ctx.say(format!(
+                "注文 `{}` の残りをすべて受け渡し、完了にしました。",
+                order.id
+            ))
+            .await?;
+        }
+        Ok(order) => {
+            let outstanding_str = order
+                .outstanding()
+                .map(|(flavor, quantity)| {
+                    format!("{} x {}", snapshot.flavor_name(flavor), quantity)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            ctx.say(format!(
+                "注文 `{}` の一部を受け渡しました。残り: {} (状態: {})",
+                order.id,
+                outstanding_str,
+                status_name(order.status)
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(Some(&snapshot), &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 注文の優先度を更新します
+#[poise::command(slash_command)]
+async fn update_order_priority(
+    ctx: PoiseContext<'_>,
+    #[description = "注文ID"] id: u32,
+    #[description = "優先度"] priority: PriorityChoice,
+) -> Result<(), anyhow::Error> {
+    match staff_registry(ctx)
+        .update_order_priority(id, priority.into())
+        .await?
+    {
+        Ok(order) => {
+            ctx.say(format!(
+                "注文 `{}` の優先度を `{}` に更新しました。",
+                order.id,
+                priority_name(order.priority)
+            ))
+            .await?;
+        }
+        Err(error) => {
+            ctx.say(format_order_error(None, &error)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// フレーバーの設定を取得します
+#[poise::command(slash_command)]
+async fn get_flavor_configs(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let mut response = String::new();
+    response.push_str("## フレーバー設定一覧\n");
+    for entry in &snapshot.menu {
+        let config_str = snapshot
+            .flavor_configs
+            .get(&entry.id)
+            .map_or("設定なし".to_string(), |config| {
+                format_flavor_config(&snapshot, config)
+            });
+        response.push_str(&format!(
+            "- **{}** (`{}`{}): 単価: {}円, {}\n",
+            entry.display_name,
+            entry.id,
+            if entry.is_active {
+                ""
+            } else {
+                ", 販売終了"
+            },
+            entry.unit_price,
+            config_str
+        ));
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+// Helper function for formatting a flavor config line
+fn format_flavor_config(snapshot: &OrderSystemSnapshot, config: &FlavorConfig) -> String {
+    format!(
+        "調理時間: {}分, バッチ生産数: {}, 鉄板: {}, 1日の上限: {}{}",
+        config.cooking_time_minutes,
+        config.quantity_per_batch,
+        config
+            .station_id
+            .and_then(|id| snapshot.station(id))
+            .map_or("専用1面".to_string(), |station| station.name.clone()),
+        config
+            .daily_limit
+            .map_or("なし".to_string(), |limit| format!("{}個", limit)),
+        if config.is_sold_out {
+            ", 売り切れ"
+        } else {
+            ""
+        }
+    )
+}
+
+/// フレーバーの設定を更新します
+#[poise::command(slash_command)]
+async fn set_flavor_config(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "調理時間(分)"] cooking_time_minutes: u32,
+    #[description = "バッチあたりの生産数"] quantity_per_batch: u32,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    let Some(current) = snapshot.flavor_configs.get(&flavor) else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+        return Ok(());
+    };
+    let config = FlavorConfig {
         cooking_time_minutes,
         quantity_per_batch,
+        ..*current
     };
-    ctx.data().set_flavor_config(flavor, config).await?;
-    ctx.say(format!("`{}` の設定を更新しました。", flavor))
+    if staff_registry(ctx)
+        .set_flavor_config(flavor.clone(), config)
+        .await?
+        .is_some()
+    {
+        ctx.say(format!(
+            "`{}` の設定を更新しました。",
+            snapshot.flavor_name(&flavor)
+        ))
         .await?;
+    } else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+    }
+    Ok(())
+}
+
+/// メニューにフレーバーを追加します
+#[poise::command(slash_command)]
+async fn add_flavor(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバーID (半角英小文字・数字・_・-)"] id: String,
+    #[description = "表示名"] display_name: String,
+    #[description = "表示順"] sort_order: i64,
+    #[description = "調理時間(分)"] cooking_time_minutes: u32,
+    #[description = "バッチあたりの生産数"] quantity_per_batch: u32,
+    #[description = "単価(円)"] unit_price: u32,
+) -> Result<(), anyhow::Error> {
+    let flavor = MenuFlavor {
+        id: Flavor::new(id.trim()),
+        display_name,
+        sort_order,
+        is_active: true,
+        unit_price,
+    };
+    let config = FlavorConfig {
+        cooking_time_minutes,
+        quantity_per_batch,
+        daily_limit: None,
+        is_sold_out: false,
+        station_id: None,
+    };
+    match staff_registry(ctx).add_flavor(flavor, config).await? {
+        Ok(flavor) => {
+            ctx.say(format!(
+                "`{}` ({}) をメニューに追加しました。",
+                flavor.display_name, flavor.id
+            ))
+            .await?;
+        }
+        Err(AddFlavorError::InvalidId) => {
+            ctx.say(format!(
+                "不正なフレーバーIDです: `{}` (半角英小文字・数字・_・- のみ使用できます)",
+                id
+            ))
+            .await?;
+        }
+        Err(AddFlavorError::AlreadyExists) => {
+            ctx.say(format!("フレーバー `{}` はすでに存在します。", id))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// フレーバーの販売状態を切り替えます
+#[poise::command(slash_command)]
+async fn set_flavor_active(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "販売するか"] is_active: bool,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    if let Some(updated) = staff_registry(ctx)
+        .set_flavor_active(&flavor, is_active)
+        .await?
+    {
+        ctx.say(format!(
+            "`{}` を{}にしました。",
+            updated.display_name,
+            if updated.is_active {
+                "販売中"
+            } else {
+                "販売終了"
+            }
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+    }
+    Ok(())
+}
+
+/// フレーバーの単価を変更します
+#[poise::command(slash_command)]
+async fn set_flavor_price(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "単価(円)"] unit_price: u32,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    if let Some(updated) = staff_registry(ctx)
+        .set_flavor_price(&flavor, unit_price)
+        .await?
+    {
+        ctx.say(format!(
+            "`{}` の単価を{}円にしました。",
+            updated.display_name, updated.unit_price
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+    }
+    Ok(())
+}
+
+/// フレーバーの売り切れ状態を切り替えます
+#[poise::command(slash_command)]
+async fn set_sold_out(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "売り切れにするか"] is_sold_out: bool,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    if staff_registry(ctx)
+        .set_flavor_sold_out(&flavor, is_sold_out)
+        .await?
+        .is_some()
+    {
+        ctx.say(format!(
+            "`{}` を{}にしました。",
+            snapshot.flavor_name(&flavor),
+            if is_sold_out {
+                "売り切れ"
+            } else {
+                "販売再開"
+            }
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+    }
+    Ok(())
+}
+
+/// フレーバーの1日の販売上限を設定します
+#[poise::command(slash_command)]
+async fn set_daily_limit(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "1日の販売上限 (省略すると上限なし)"] daily_limit: Option<u32>,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    if staff_registry(ctx)
+        .set_flavor_daily_limit(&flavor, daily_limit)
+        .await?
+        .is_some()
+    {
+        ctx.say(format!(
+            "`{}` の1日の販売上限を{}にしました。",
+            snapshot.flavor_name(&flavor),
+            daily_limit.map_or("なし".to_string(), |limit| format!("{}個", limit))
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+    }
+    Ok(())
+}
+
+/// 鉄板の一覧を表示します
+#[poise::command(slash_command)]
+async fn stations(ctx: PoiseContext<'_>) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let mut response = String::from("## 鉄板一覧\n");
+    for station in &snapshot.stations {
+        let flavors = snapshot
+            .menu
+            .iter()
+            .filter(|entry| {
+                snapshot
+                    .flavor_configs
+                    .get(&entry.id)
+                    .is_some_and(|config| config.station_id == Some(station.id))
+            })
+            .map(|entry| entry.display_name.as_str())
+            .collect::<Vec<_>>();
+        response.push_str(&format!(
+            "- **{}** (ID: `{}`): {}面, フレーバー: {}\n",
+            station.name,
+            station.id,
+            station.plate_count,
+            if flavors.is_empty() {
+                "なし".to_string()
+            } else {
+                flavors.join(", ")
+            }
+        ));
+    }
+    if snapshot.stations.is_empty() {
+        response.push_str("登録されていません。鉄板のないフレーバーは専用の1面で焼きます。\n");
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// 鉄板を追加します
+#[poise::command(slash_command)]
+async fn add_station(
+    ctx: PoiseContext<'_>,
+    #[description = "鉄板の名前"] name: String,
+    #[description = "同時に焼ける面数"] plate_count: u32,
+) -> Result<(), anyhow::Error> {
+    let station = staff_registry(ctx).add_station(name, plate_count).await?;
+    ctx.say(format!(
+        "鉄板 **{}** (ID: `{}`, {}面) を追加しました。",
+        station.name, station.id, station.plate_count
+    ))
+    .await?;
+    Ok(())
+}
+
+/// 鉄板の面数を変更します
+#[poise::command(slash_command)]
+async fn set_station_plates(
+    ctx: PoiseContext<'_>,
+    #[description = "鉄板ID"] id: u32,
+    #[description = "同時に焼ける面数"] plate_count: u32,
+) -> Result<(), anyhow::Error> {
+    if let Some(station) = staff_registry(ctx)
+        .set_station_plate_count(id, plate_count)
+        .await?
+    {
+        ctx.say(format!(
+            "鉄板 **{}** を{}面にしました。",
+            station.name, station.plate_count
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("鉄板 `{}` が見つかりません。", id)).await?;
+    }
+    Ok(())
+}
+
+/// フレーバーを焼く鉄板を設定します
+#[poise::command(slash_command)]
+async fn set_flavor_station(
+    ctx: PoiseContext<'_>,
+    #[description = "フレーバー"]
+    #[autocomplete = "autocomplete_flavor"]
+    flavor: String,
+    #[description = "鉄板ID (省略すると専用の1面)"] station_id: Option<u32>,
+) -> Result<(), anyhow::Error> {
+    let snapshot = ctx.data().snapshot().await?;
+    let flavor = match parse_flavor(&snapshot, &flavor) {
+        Ok(f) => f,
+        Err(e) => {
+            ctx.say(format!("{}: `{}`", e, flavor)).await?;
+            return Ok(());
+        }
+    };
+    let Some(current) = snapshot.flavor_configs.get(&flavor) else {
+        ctx.say(format!("フレーバー `{}` が見つかりません。", flavor))
+            .await?;
+        return Ok(());
+    };
+    let config = FlavorConfig {
+        station_id,
+        ..*current
+    };
+    if staff_registry(ctx)
+        .set_flavor_config(flavor.clone(), config)
+        .await?
+        .is_some()
+    {
+        ctx.say(format!(
+            "`{}` の鉄板を{}にしました。",
+            snapshot.flavor_name(&flavor),
+            station_id.map_or("専用の1面".to_string(), |id| format!("`{}`", id))
+        ))
+        .await?;
+    } else {
+        ctx.say(format!(
+            "鉄板 `{}` が見つかりません。",
+            station_id.unwrap_or_default()
+        ))
+        .await?;
+    }
     Ok(())
 }
 
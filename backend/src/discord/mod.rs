@@ -48,3 +48,14 @@ pub async fn send_notification(
         .await?;
     Ok(())
 }
+
+pub async fn send_channel_message(
+    ctx: &Context,
+    channel_id: u64,
+    message: &str,
+) -> anyhow::Result<()> {
+    ChannelId::from(channel_id)
+        .send_message(ctx, CreateMessage::new().content(message))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::domain::snapshot::{Flavor, Order};
+
+/// Decides which queued orders are filled from stock.
+pub trait AllocationPolicy {
+    /// `queue` is in service order. Returns the positions in `queue` of the orders to fill and
+    /// takes their pieces out of `stock`.
+    fn allocate(&self, queue: &[&Order], stock: &mut HashMap<Flavor, usize>) -> Vec<usize>;
+
+    /// Pieces of `stock` an order queued right after `queue` may take once the orders in it have
+    /// been served.
+    fn left_for_next(
+        &self,
+        queue: &[&Order],
+        stock: HashMap<Flavor, usize>,
+    ) -> HashMap<Flavor, usize>;
+}
+
+/// Fills every order that stock covers, so a small order may overtake a large one ahead of it.
+pub struct Greedy;
+
+/// Fills orders strictly in queue order and stops at the first one stock cannot cover.
+pub struct StrictFifo;
+
+/// Sets aside what stock there is for the first order that cannot be filled yet and fills the
+/// rest greedily from what is left, so the head of the line is not starved of its flavors.
+pub struct ReserveHeadOfLine;
+
+impl AllocationPolicy for Greedy {
+    fn allocate(&self, queue: &[&Order], stock: &mut HashMap<Flavor, usize>) -> Vec<usize> {
+        let mut filled = Vec::new();
+        for (position, order) in queue.iter().enumerate() {
+            if can_fulfill(order, stock) {
+                fulfill(order, stock);
+                filled.push(position);
+            }
+        }
+        filled
+    }
+
+    fn left_for_next(
+        &self,
+        queue: &[&Order],
+        mut stock: HashMap<Flavor, usize>,
+    ) -> HashMap<Flavor, usize> {
+        self.allocate(queue, &mut stock);
+        stock
+    }
+}
+
+impl AllocationPolicy for StrictFifo {
+    fn allocate(&self, queue: &[&Order], stock: &mut HashMap<Flavor, usize>) -> Vec<usize> {
+        let mut filled = Vec::new();
+        for (position, order) in queue.iter().enumerate() {
+            if !can_fulfill(order, stock) {
+                break;
+            }
+            fulfill(order, stock);
+            filled.push(position);
+        }
+        filled
+    }
+
+    // Every order ahead comes first, filled or not.
+    fn left_for_next(
+        &self,
+        queue: &[&Order],
+        mut stock: HashMap<Flavor, usize>,
+    ) -> HashMap<Flavor, usize> {
+        for order in queue {
+            fulfill(order, &mut stock);
+        }
+        stock
+    }
+}
+
+impl AllocationPolicy for ReserveHeadOfLine {
+    fn allocate(&self, queue: &[&Order], stock: &mut HashMap<Flavor, usize>) -> Vec<usize> {
+        // Reserved pieces stay in stock; they only stop counting for the orders behind.
+        let mut available = stock.clone();
+        let mut has_reserved = false;
+        let mut filled = Vec::new();
+        for (position, order) in queue.iter().enumerate() {
+            if can_fulfill(order, &available) {
+                fulfill(order, &mut available);
+                fulfill(order, stock);
+                filled.push(position);
+            } else if !has_reserved {
+                fulfill(order, &mut available);
+                has_reserved = true;
+            }
+        }
+        filled
+    }
+
+    fn left_for_next(
+        &self,
+        queue: &[&Order],
+        stock: HashMap<Flavor, usize>,
+    ) -> HashMap<Flavor, usize> {
+        let mut available = stock;
+        let mut has_reserved = false;
+        for order in queue {
+            if can_fulfill(order, &available) {
+                fulfill(order, &mut available);
+            } else if !has_reserved {
+                fulfill(order, &mut available);
+                has_reserved = true;
+            }
+        }
+        available
+    }
+}
+
+/// Allocation policy chosen in configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AllocationPolicyKind {
+    #[default]
+    Greedy,
+    StrictFifo,
+    ReserveHeadOfLine,
+}
+
+impl AllocationPolicyKind {
+    pub fn policy(self) -> &'static dyn AllocationPolicy {
+        match self {
+            Self::Greedy => &Greedy,
+            Self::StrictFifo => &StrictFifo,
+            Self::ReserveHeadOfLine => &ReserveHeadOfLine,
+        }
+    }
+}
+
+impl FromStr for AllocationPolicyKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "greedy" => Ok(Self::Greedy),
+            "fifo" => Ok(Self::StrictFifo),
+            "reserve-head" => Ok(Self::ReserveHeadOfLine),
+            other => Err(format!("unknown allocation policy `{other}`")),
+        }
+    }
+}
+
+// Pieces handed over earlier have already left stock.
+fn can_fulfill(order: &Order, stock: &HashMap<Flavor, usize>) -> bool {
+    order
+        .outstanding()
+        .all(|(flavor, quantity)| stock.get(flavor).copied().unwrap_or(0) >= quantity)
+}
+
+// Takes what there is, which for an order that cannot be filled is a reservation.
+fn fulfill(order: &Order, stock: &mut HashMap<Flavor, usize>) {
+    for (flavor, quantity) in order.outstanding() {
+        if let Some(in_stock) = stock.get_mut(flavor) {
+            *in_stock = in_stock.saturating_sub(quantity);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+
+use crate::domain::business_day::{business_date_at, business_date_of};
+use crate::domain::snapshot::{Flavor, OrderStatus, OrderSystemSnapshot};
+
+// The store runs in JST; daily caps reset at local midnight.
+const STORE_UTC_OFFSET_SECONDS: i32 = 9 * 60 * 60;
+
+pub fn store_date(at: DateTime<Utc>) -> NaiveDate {
+    store_time(at).date_naive()
+}
+
+/// Wall-clock time at the store, for showing times to customers and staff.
+pub fn store_time(at: DateTime<Utc>) -> DateTime<FixedOffset> {
+    at.with_timezone(&store_offset())
+}
+
+pub fn store_offset() -> FixedOffset {
+    FixedOffset::east_opt(STORE_UTC_OFFSET_SECONDS).expect("valid store offset")
+}
+
+/// Pieces of the flavor ordered on the business day of `now`, excluding cancelled and expired
+/// orders, whose pieces went back to stock.
+pub fn ordered_quantity_on_day(
+    snapshot: &OrderSystemSnapshot,
+    flavor: &Flavor,
+    now: DateTime<Utc>,
+) -> usize {
+    let today = business_date_at(snapshot, now);
+    snapshot
+        .orders
+        .iter()
+        .filter(|order| {
+            !matches!(order.status, OrderStatus::Cancelled | OrderStatus::Expired)
+                && business_date_of(snapshot, order) == today
+        })
+        .flat_map(|order| &order.items)
+        .filter(|item| &item.flavor == flavor)
+        .map(|item| item.quantity)
+        .sum()
+}
+
+/// Pieces that can still be ordered today, or `None` when the flavor is not capped.
+pub fn remaining_quantity(
+    snapshot: &OrderSystemSnapshot,
+    flavor: &Flavor,
+    now: DateTime<Utc>,
+) -> Option<usize> {
+    let config = snapshot.flavor_configs.get(flavor)?;
+    if config.is_sold_out {
+        return Some(0);
+    }
+    let daily_limit = config.daily_limit? as usize;
+    Some(daily_limit.saturating_sub(ordered_quantity_on_day(snapshot, flavor, now)))
+}
+
+pub fn is_sold_out(snapshot: &OrderSystemSnapshot, flavor: &Flavor, now: DateTime<Utc>) -> bool {
+    remaining_quantity(snapshot, flavor, now) == Some(0)
+}
+
+/// Bounds on what one order can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderLimits {
+    /// Pieces of one flavor in an order.
+    pub max_item_quantity: usize,
+}
+
+impl Default for OrderLimits {
+    fn default() -> Self {
+        Self {
+            max_item_quantity: 50,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{
+        Flavor, FlavorConfig, Item, MenuFlavor, Order, OrderStatus, OrderSystemSnapshot,
+    };
+    use crate::test_fixtures::{flavor_config, menu_flavor, waiting_order};
+
+    use super::{is_sold_out, remaining_quantity};
+
+    fn kurikinton() -> Flavor {
+        Flavor::new("kurikinton")
+    }
+
+    fn snapshot_with_limit(daily_limit: Option<u32>) -> OrderSystemSnapshot {
+        let mut snapshot = OrderSystemSnapshot::default();
+        snapshot.add_flavor(
+            MenuFlavor {
+                display_name: "栗きんとん".to_owned(),
+                ..menu_flavor("kurikinton", 1)
+            },
+            FlavorConfig {
+                quantity_per_batch: 2,
+                daily_limit,
+                ..flavor_config()
+            },
+        );
+        snapshot
+    }
+
+    fn order(id: u32, quantity: usize, status: OrderStatus) -> Order {
+        Order {
+            items: vec![Item {
+                flavor: kurikinton(),
+                quantity,
+            }],
+            status,
+            // 2026-01-01 10:00 JST
+            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap(),
+            ..waiting_order(id, quantity)
+        }
+    }
+
+    #[test]
+    fn remaining_quantity_ignores_cancelled_orders_and_other_days() {
+        let mut snapshot = snapshot_with_limit(Some(10));
+        let mut yesterday = order(3, 4, OrderStatus::Completed);
+        yesterday.ordered_at -= Duration::days(1);
+        snapshot.orders = vec![
+            order(1, 3, OrderStatus::Completed),
+            order(2, 5, OrderStatus::Cancelled),
+            yesterday,
+        ];
+
+        // 2026-01-01 23:30 JST is still the same store day.
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 14, 30, 0).unwrap();
+
+        assert_eq!(remaining_quantity(&snapshot, &kurikinton(), now), Some(7));
+    }
+
+    #[test]
+    fn remaining_quantity_ignores_expired_orders() {
+        let mut snapshot = snapshot_with_limit(Some(10));
+        snapshot.orders = vec![
+            order(1, 3, OrderStatus::Ready),
+            order(2, 5, OrderStatus::Expired),
+        ];
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 2, 0, 0).unwrap();
+
+        assert_eq!(remaining_quantity(&snapshot, &kurikinton(), now), Some(7));
+    }
+
+    #[test]
+    fn uncapped_flavor_is_sold_out_only_by_manual_switch() {
+        let mut snapshot = snapshot_with_limit(None);
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        assert_eq!(remaining_quantity(&snapshot, &kurikinton(), now), None);
+
+        snapshot
+            .flavor_configs
+            .get_mut(&kurikinton())
+            .expect("config exists")
+            .is_sold_out = true;
+
+        assert!(is_sold_out(&snapshot, &kurikinton(), now));
+    }
+}
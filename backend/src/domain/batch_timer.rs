@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::snapshot::{Batch, OrderSystemSnapshot};
+
+/// Alert raised when a batch on the grill is due to be turned over or taken out.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchAlert {
+    pub batch: Batch,
+    pub display_name: String,
+    pub due_at: DateTime<Utc>,
+    pub overdue_minutes: i64,
+    /// 0 when the batch has just become due, raised each time it stays unfinished for another
+    /// escalation interval.
+    pub escalation_level: u32,
+}
+
+impl BatchAlert {
+    pub fn message(&self) -> String {
+        if self.escalation_level == 0 {
+            format!(
+                "{} (バッチ #{}, {}個) が焼き上がりました。今すぐ返す・取り出してください。",
+                self.display_name, self.batch.id, self.batch.quantity
+            )
+        } else {
+            format!(
+                "【至急】{} (バッチ #{}, {}個) が{}分超過しています。取り出したら完了を報告してください。",
+                self.display_name, self.batch.id, self.batch.quantity, self.overdue_minutes
+            )
+        }
+    }
+}
+
+/// Alerts for every unfinished batch that is past its cooking time at `now`.
+pub fn due_batch_alerts(
+    snapshot: &OrderSystemSnapshot,
+    now: DateTime<Utc>,
+    escalation_minutes: i64,
+) -> Vec<BatchAlert> {
+    snapshot
+        .active_batches()
+        .filter_map(|batch| {
+            let config = snapshot.flavor_configs.get(&batch.flavor)?;
+            let due_at =
+                batch.started_at + chrono::Duration::minutes(config.cooking_time_minutes as i64);
+            if now < due_at {
+                return None;
+            }
+            let overdue_minutes = (now - due_at).num_minutes();
+            Some(BatchAlert {
+                batch: batch.clone(),
+                display_name: snapshot.flavor_name(&batch.flavor).to_owned(),
+                due_at,
+                overdue_minutes,
+                escalation_level: (overdue_minutes / escalation_minutes.max(1)) as u32,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{Batch, OrderSystemSnapshot};
+    use crate::test_fixtures::{snapshot_with_orders, tsubuan};
+
+    use super::due_batch_alerts;
+
+    #[test]
+    fn batch_escalates_while_it_stays_on_the_grill() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        let started_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        snapshot.batches.push(Batch {
+            id: 1,
+            flavor: tsubuan(),
+            quantity: 9,
+            started_at,
+            finished_at: None,
+        });
+        let levels = |snapshot: &OrderSystemSnapshot, minutes| {
+            due_batch_alerts(snapshot, started_at + Duration::minutes(minutes), 3)
+                .iter()
+                .map(|alert| alert.escalation_level)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(levels(&snapshot, 14), Vec::<u32>::new());
+        assert_eq!(levels(&snapshot, 15), vec![0]);
+        assert_eq!(levels(&snapshot, 20), vec![1]);
+
+        snapshot.batches[0].finished_at = Some(started_at + Duration::minutes(21));
+        assert_eq!(levels(&snapshot, 30), Vec::<u32>::new());
+    }
+}
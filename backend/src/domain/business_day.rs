@@ -0,0 +1,154 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::domain::availability::store_date;
+use crate::domain::revenue::{self, RevenueSummary};
+use crate::domain::snapshot::{Item, Order, OrderStatus, OrderSystemSnapshot};
+use crate::domain::stock::{self, WasteReport};
+
+/// Sales session between opening and closing the store. Orders taken during it belong to it
+/// and are locked once it is closed.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessDay {
+    pub id: u32,
+    /// Store date (JST) the day was opened on; reports are keyed by it.
+    pub date: NaiveDate,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    /// What happened to the unsold stock at closing.
+    pub stock_disposition: Option<StockDisposition>,
+}
+
+impl BusinessDay {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.opened_at <= at && self.closed_at.is_none_or(|closed_at| at <= closed_at)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StockDisposition {
+    /// Unsold pieces stay in stock for the next day.
+    CarryOver,
+    /// Unsold pieces are discarded and show up in the waste report.
+    WriteOff,
+}
+
+impl StockDisposition {
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            StockDisposition::CarryOver => "carry_over",
+            StockDisposition::WriteOff => "write_off",
+        }
+    }
+
+    pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "carry_over" => Ok(StockDisposition::CarryOver),
+            "write_off" => Ok(StockDisposition::WriteOff),
+            _ => anyhow::bail!("invalid stock disposition: {value}"),
+        }
+    }
+}
+
+pub fn open_day(snapshot: &OrderSystemSnapshot) -> Option<&BusinessDay> {
+    snapshot
+        .business_days
+        .iter()
+        .rev()
+        .find(|day| day.is_open())
+}
+
+/// Date reports file a moment under: the business day it fell in, or the store date outside
+/// business hours.
+pub fn business_date_at(snapshot: &OrderSystemSnapshot, at: DateTime<Utc>) -> NaiveDate {
+    snapshot
+        .business_days
+        .iter()
+        .rev()
+        .find(|day| day.contains(at))
+        .map_or_else(|| store_date(at), |day| day.date)
+}
+
+/// Orders taken before business days were tracked fall back to when they were taken.
+pub fn business_date_of(snapshot: &OrderSystemSnapshot, order: &Order) -> NaiveDate {
+    order
+        .business_day_id
+        .and_then(|id| snapshot.business_day(id))
+        .map_or_else(
+            || business_date_at(snapshot, order.ordered_at),
+            |day| day.date,
+        )
+}
+
+/// Orders of a closed day can no longer be changed.
+pub fn is_locked(snapshot: &OrderSystemSnapshot, order: &Order) -> bool {
+    order
+        .business_day_id
+        .and_then(|id| snapshot.business_day(id))
+        .is_some_and(|day| !day.is_open())
+}
+
+/// Everything staff check when closing up, for one business date.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessDaySummary {
+    pub date: NaiveDate,
+    /// First opening on the date; `None` when the store was never opened on it.
+    pub opened_at: Option<DateTime<Utc>>,
+    /// Last closing on the date; `None` while the day is still open.
+    pub closed_at: Option<DateTime<Utc>>,
+    pub stock_disposition: Option<StockDisposition>,
+    pub completed_count: usize,
+    /// Pieces of completed orders per flavor, in menu order.
+    pub sold: Vec<Item>,
+    pub revenue: RevenueSummary,
+    pub waste: WasteReport,
+}
+
+pub fn summarize(snapshot: &OrderSystemSnapshot, date: NaiveDate) -> BusinessDaySummary {
+    let days = snapshot
+        .business_days
+        .iter()
+        .filter(|day| day.date == date)
+        .collect::<Vec<_>>();
+    let completed = snapshot
+        .orders
+        .iter()
+        .filter(|order| {
+            order.status == OrderStatus::Completed && business_date_of(snapshot, order) == date
+        })
+        .collect::<Vec<_>>();
+    let sold = snapshot
+        .menu
+        .iter()
+        .map(|entry| Item {
+            flavor: entry.id.clone(),
+            quantity: completed
+                .iter()
+                .flat_map(|order| &order.items)
+                .filter(|item| item.flavor == entry.id)
+                .map(|item| item.quantity)
+                .sum(),
+        })
+        .filter(|item| item.quantity > 0)
+        .collect();
+    let last = days.last();
+
+    BusinessDaySummary {
+        date,
+        opened_at: days.first().map(|day| day.opened_at),
+        closed_at: last.and_then(|day| day.closed_at),
+        stock_disposition: last.and_then(|day| day.stock_disposition),
+        completed_count: completed.len(),
+        sold,
+        revenue: revenue::summarize_day(snapshot, date),
+        waste: stock::waste_report(snapshot, date),
+    }
+}
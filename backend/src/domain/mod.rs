@@ -1,5 +1,19 @@
+pub mod allocation;
+pub mod availability;
+pub mod batch_timer;
+pub mod business_day;
+pub mod no_show;
 pub mod notification;
+pub mod order_event;
 pub mod order_number;
 pub mod order_status;
+pub mod pre_order;
+pub mod production_plan;
+pub mod revenue;
 pub mod snapshot;
+pub mod snapshot_change;
+pub mod station;
+pub mod stock;
+pub mod undo;
+pub mod wait_history;
 pub mod wait_time;
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::snapshot::{Order, OrderStatus, OrderSystemSnapshot};
+
+/// How long ready orders wait for their customer before being chased and given up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoShowSettings {
+    /// Minutes after an order became ready that the customer is reminded and staff are alerted.
+    pub reminder_minutes: i64,
+    /// Minutes after an order became ready that it expires and its pieces go back to stock.
+    /// `None` leaves uncollected orders to staff.
+    pub expire_minutes: Option<i64>,
+}
+
+impl Default for NoShowSettings {
+    fn default() -> Self {
+        Self {
+            reminder_minutes: 15,
+            expire_minutes: None,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NoShowStep {
+    /// The customer was reminded and the order flagged on the staff panel.
+    Reminded,
+    /// The order was given up and its pieces returned to stock.
+    Expired,
+}
+
+/// Step taken for a ready order whose customer has not come for it.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NoShowNotice {
+    pub order_id: u32,
+    pub display_number: String,
+    pub step: NoShowStep,
+    pub ready_at: DateTime<Utc>,
+    pub waited_minutes: i64,
+}
+
+impl NoShowNotice {
+    pub fn new(
+        order: &Order,
+        step: NoShowStep,
+        ready_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            order_id: order.id,
+            display_number: order.display_number.to_string(),
+            step,
+            ready_at,
+            waited_minutes: (now - ready_at).num_minutes(),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self.step {
+            NoShowStep::Reminded => format!(
+                "#{}番 (注文 {}) が{}分間受け取られていません。",
+                self.display_number, self.order_id, self.waited_minutes
+            ),
+            NoShowStep::Expired => format!(
+                "#{}番 (注文 {}) が{}分間受け取られなかったため期限切れにし、在庫に戻しました。",
+                self.display_number, self.order_id, self.waited_minutes
+            ),
+        }
+    }
+}
+
+/// Steps due at `now` for ready orders, in the order they should be taken. An order that is
+/// already past its expiry without a reminder gets both.
+pub fn due_no_show_steps(
+    snapshot: &OrderSystemSnapshot,
+    settings: NoShowSettings,
+    now: DateTime<Utc>,
+) -> Vec<(u32, NoShowStep)> {
+    let mut steps = Vec::new();
+    for order in &snapshot.orders {
+        if order.status != OrderStatus::Ready {
+            continue;
+        }
+        let Some(ready_at) = order.ready_at else {
+            continue;
+        };
+        if order.no_show_at.is_none()
+            && ready_at + Duration::minutes(settings.reminder_minutes) <= now
+        {
+            steps.push((order.id, NoShowStep::Reminded));
+        }
+        if settings
+            .expire_minutes
+            .is_some_and(|minutes| ready_at + Duration::minutes(minutes) <= now)
+        {
+            steps.push((order.id, NoShowStep::Expired));
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{Order, OrderStatus, OrderSystemSnapshot};
+    use crate::test_fixtures::waiting_order;
+
+    use super::{NoShowSettings, NoShowStep, due_no_show_steps};
+
+    fn ready_order(id: u32, ready_at: DateTime<Utc>) -> Order {
+        Order {
+            items: Vec::new(),
+            status: OrderStatus::Ready,
+            ordered_at: ready_at - Duration::minutes(10),
+            ready_at: Some(ready_at),
+            ..waiting_order(id, 0)
+        }
+    }
+
+    #[test]
+    fn ready_order_is_reminded_once_and_then_expires() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 5, 0, 0).unwrap();
+        let settings = NoShowSettings {
+            reminder_minutes: 10,
+            expire_minutes: Some(30),
+        };
+        let ready = |id, minutes_ago| ready_order(id, now - Duration::minutes(minutes_ago));
+        let mut snapshot = OrderSystemSnapshot::default();
+        snapshot.orders.push(ready(1, 5));
+        snapshot.orders.push(ready(2, 12));
+        snapshot.orders.push(Order {
+            no_show_at: Some(now - Duration::minutes(8)),
+            ..ready(3, 18)
+        });
+        snapshot.orders.push(ready(4, 40));
+
+        assert_eq!(
+            due_no_show_steps(&snapshot, settings, now),
+            vec![
+                (2, NoShowStep::Reminded),
+                (4, NoShowStep::Reminded),
+                (4, NoShowStep::Expired),
+            ]
+        );
+        assert_eq!(
+            due_no_show_steps(
+                &snapshot,
+                NoShowSettings {
+                    expire_minutes: None,
+                    ..settings
+                },
+                now
+            )
+            .len(),
+            2
+        );
+    }
+}
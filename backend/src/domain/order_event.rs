@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::domain::snapshot::{Flavor, Notify, OrderStatus, OrderSystemSnapshot};
+
+/// Who made a change.
+#[derive(Serialize, ToSchema, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Actor {
+    /// Staff panel or API; staff share one token, so individuals are not told apart.
+    Staff,
+    Discord {
+        user_id: u64,
+    },
+    Line {
+        user_id: String,
+    },
+    /// Background jobs such as pre-order release and no-show expiry.
+    #[default]
+    System,
+}
+
+impl Actor {
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Actor::Staff => "staff",
+            Actor::Discord { .. } => "discord",
+            Actor::Line { .. } => "line",
+            Actor::System => "system",
+        }
+    }
+
+    pub fn db_id(&self) -> Option<String> {
+        match self {
+            Actor::Discord { user_id } => Some(user_id.to_string()),
+            Actor::Line { user_id } => Some(user_id.clone()),
+            Actor::Staff | Actor::System => None,
+        }
+    }
+
+    pub fn from_db(kind: &str, id: Option<String>) -> anyhow::Result<Self> {
+        match (kind, id) {
+            ("staff", _) => Ok(Actor::Staff),
+            ("discord", Some(id)) => Ok(Actor::Discord {
+                user_id: id.parse()?,
+            }),
+            ("line", Some(user_id)) => Ok(Actor::Line { user_id }),
+            ("system", _) => Ok(Actor::System),
+            _ => anyhow::bail!("invalid actor: {kind}"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderEventKind {
+    /// `before` is empty when the order was taken.
+    StatusChanged,
+    PriorityChanged,
+    NotificationRegistered,
+    NotificationCancelled,
+    /// Pieces reported as grilled; `before` and `after` are the flavor's stock.
+    ProductionReported,
+    /// Pieces discarded or recounted; `before` and `after` are the flavor's stock.
+    StockAdjusted,
+}
+
+impl OrderEventKind {
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            OrderEventKind::StatusChanged => "status_changed",
+            OrderEventKind::PriorityChanged => "priority_changed",
+            OrderEventKind::NotificationRegistered => "notification_registered",
+            OrderEventKind::NotificationCancelled => "notification_cancelled",
+            OrderEventKind::ProductionReported => "production_reported",
+            OrderEventKind::StockAdjusted => "stock_adjusted",
+        }
+    }
+
+    pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "status_changed" => Ok(OrderEventKind::StatusChanged),
+            "priority_changed" => Ok(OrderEventKind::PriorityChanged),
+            "notification_registered" => Ok(OrderEventKind::NotificationRegistered),
+            "notification_cancelled" => Ok(OrderEventKind::NotificationCancelled),
+            "production_reported" => Ok(OrderEventKind::ProductionReported),
+            "stock_adjusted" => Ok(OrderEventKind::StockAdjusted),
+            _ => anyhow::bail!("invalid order event kind: {value}"),
+        }
+    }
+}
+
+/// Which events of the audit trail to list; a bound left out is open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// Inclusive.
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive.
+    pub to: Option<DateTime<Utc>>,
+    pub kind: Option<OrderEventKind>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &OrderEvent) -> bool {
+        self.from.is_none_or(|from| from <= event.recorded_at)
+            && self.to.is_none_or(|to| event.recorded_at < to)
+            && self.kind.is_none_or(|kind| kind == event.kind)
+    }
+}
+
+/// Why an order changed when no one asked for it.
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EventReason {
+    /// Still in progress when the business day was closed.
+    BusinessDayClosed,
+}
+
+impl EventReason {
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            EventReason::BusinessDayClosed => "business_day_closed",
+        }
+    }
+
+    pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "business_day_closed" => Ok(EventReason::BusinessDayClosed),
+            _ => anyhow::bail!("invalid order event reason: {value}"),
+        }
+    }
+}
+
+/// Entry of the append-only audit trail.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderEvent {
+    /// `None` for production and stock events, which belong to no order.
+    pub order_id: Option<u32>,
+    pub kind: OrderEventKind,
+    pub actor: Actor,
+    pub flavor: Option<Flavor>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    /// `None` for changes made on request.
+    pub reason: Option<EventReason>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl OrderEvent {
+    pub fn stock_change(
+        kind: OrderEventKind,
+        flavor: &Flavor,
+        before: usize,
+        after: usize,
+        actor: &Actor,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            order_id: None,
+            kind,
+            actor: actor.clone(),
+            flavor: Some(flavor.clone()),
+            before: Some(before.to_string()),
+            after: Some(after.to_string()),
+            reason: None,
+            recorded_at: now,
+        }
+    }
+
+    /// Cancellation of an order that was still in progress when the business day closed.
+    pub fn cancelled_at_closing(
+        order_id: u32,
+        from: OrderStatus,
+        actor: &Actor,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            reason: Some(EventReason::BusinessDayClosed),
+            ..Self::order_change(
+                order_id,
+                OrderEventKind::StatusChanged,
+                Some(from.as_db_str().to_owned()),
+                Some(OrderStatus::Cancelled.as_db_str().to_owned()),
+                actor,
+                now,
+            )
+        }
+    }
+
+    fn order_change(
+        order_id: u32,
+        kind: OrderEventKind,
+        before: Option<String>,
+        after: Option<String>,
+        actor: &Actor,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            order_id: Some(order_id),
+            kind,
+            actor: actor.clone(),
+            flavor: None,
+            before,
+            after,
+            reason: None,
+            recorded_at: now,
+        }
+    }
+}
+
+/// Events for what a mutation changed, read off the state before and after it. Production
+/// without a batch leaves no trace in the state, so its caller records it itself.
+pub fn events_between(
+    before: &OrderSystemSnapshot,
+    after: &OrderSystemSnapshot,
+    actor: &Actor,
+    now: DateTime<Utc>,
+) -> Vec<OrderEvent> {
+    let mut events = Vec::new();
+    let previous_orders: HashMap<_, _> = before
+        .orders
+        .iter()
+        .map(|order| (order.id, order))
+        .collect();
+    for order in &after.orders {
+        let previous = previous_orders.get(&order.id);
+        let previous_status = previous.map(|previous| previous.status);
+        if previous_status != Some(order.status) {
+            events.push(OrderEvent::order_change(
+                order.id,
+                OrderEventKind::StatusChanged,
+                previous_status.map(|status| status.as_db_str().to_owned()),
+                Some(order.status.as_db_str().to_owned()),
+                actor,
+                now,
+            ));
+        }
+        if let Some(previous) = previous
+            && previous.priority != order.priority
+        {
+            events.push(OrderEvent::order_change(
+                order.id,
+                OrderEventKind::PriorityChanged,
+                Some(previous.priority.as_db_str().to_owned()),
+                Some(order.priority.as_db_str().to_owned()),
+                actor,
+                now,
+            ));
+        }
+
+        let mut registered: Vec<_> = order
+            .notify
+            .iter()
+            .filter(|notify| previous.is_none_or(|previous| !previous.notify.contains(notify)))
+            .map(describe_notify)
+            .collect();
+        registered.sort();
+        events.extend(registered.into_iter().map(|target| {
+            OrderEvent::order_change(
+                order.id,
+                OrderEventKind::NotificationRegistered,
+                None,
+                Some(target),
+                actor,
+                now,
+            )
+        }));
+        if let Some(previous) = previous {
+            let mut cancelled: Vec<_> = previous
+                .notify
+                .iter()
+                .filter(|notify| !order.notify.contains(notify))
+                .map(describe_notify)
+                .collect();
+            cancelled.sort();
+            events.extend(cancelled.into_iter().map(|target| {
+                OrderEvent::order_change(
+                    order.id,
+                    OrderEventKind::NotificationCancelled,
+                    Some(target),
+                    None,
+                    actor,
+                    now,
+                )
+            }));
+        }
+    }
+
+    // Stock is tracked per flavor so that several changes in one mutation chain up.
+    let mut stock: HashMap<&Flavor, usize> = HashMap::new();
+    let unfinished: HashMap<_, _> = before
+        .batches
+        .iter()
+        .filter(|batch| batch.is_active())
+        .map(|batch| (batch.id, batch))
+        .collect();
+    for batch in &after.batches {
+        if batch.is_active() || !unfinished.contains_key(&batch.id) {
+            continue;
+        }
+        let current = stock
+            .entry(&batch.flavor)
+            .or_insert_with(|| before.stock_of(&batch.flavor));
+        let previous = *current;
+        *current += batch.quantity;
+        events.push(OrderEvent::stock_change(
+            OrderEventKind::ProductionReported,
+            &batch.flavor,
+            previous,
+            *current,
+            actor,
+            now,
+        ));
+    }
+    let last_adjustment = before
+        .stock_adjustments
+        .iter()
+        .map(|adjustment| adjustment.id)
+        .max()
+        .unwrap_or(0);
+    for adjustment in &after.stock_adjustments {
+        if adjustment.id <= last_adjustment {
+            continue;
+        }
+        let current = stock
+            .entry(&adjustment.flavor)
+            .or_insert_with(|| before.stock_of(&adjustment.flavor));
+        let previous = *current;
+        *current = current.saturating_add_signed(adjustment.delta as isize);
+        events.push(OrderEvent::stock_change(
+            OrderEventKind::StockAdjusted,
+            &adjustment.flavor,
+            previous,
+            *current,
+            actor,
+            now,
+        ));
+    }
+    events
+}
+
+fn describe_notify(notify: &Notify) -> String {
+    match notify {
+        Notify::Discord { user_id, .. } => format!("discord:{user_id}"),
+        Notify::Line { user_id } => format!("line:{user_id}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use crate::domain::snapshot::{
+        Batch, Notify, OrderStatus, OrderSystemSnapshot, PriorityTier, StockAdjustment, StockReason,
+    };
+    use crate::test_fixtures::{tsubuan, waiting_order};
+
+    use super::{Actor, OrderEvent, OrderEventKind, events_between};
+
+    #[test]
+    fn changes_become_events_by_the_actor() {
+        let now = Utc::now();
+        let tsubuan = tsubuan();
+        let mut before = OrderSystemSnapshot::default();
+        before.orders.push(waiting_order(1, 1));
+        before.orders.push(waiting_order(2, 1));
+        before.unallocated_stock.insert(tsubuan.clone(), 2);
+        before.batches.push(Batch {
+            id: 1,
+            flavor: tsubuan.clone(),
+            quantity: 8,
+            started_at: now - Duration::minutes(10),
+            finished_at: None,
+        });
+
+        let mut after = before.clone();
+        after.orders[0].status = OrderStatus::Cancelled;
+        after.orders[1].priority = PriorityTier::Vip;
+        after.orders[1].notify.insert(Notify::Line {
+            user_id: "line-user".to_owned(),
+        });
+        after.orders.push(waiting_order(3, 1));
+        after.batches[0].finished_at = Some(now);
+        after.stock_adjustments.push(StockAdjustment {
+            id: 1,
+            flavor: tsubuan.clone(),
+            delta: -3,
+            reason: StockReason::Dropped,
+            note: None,
+            recorded_at: now,
+        });
+
+        let actor = Actor::Discord { user_id: 42 };
+        let event = |order_id, kind, before: Option<&str>, after: Option<&str>| OrderEvent {
+            order_id: Some(order_id),
+            kind,
+            actor: actor.clone(),
+            flavor: None,
+            before: before.map(str::to_owned),
+            after: after.map(str::to_owned),
+            reason: None,
+            recorded_at: now,
+        };
+        assert_eq!(
+            events_between(&before, &after, &actor, now),
+            vec![
+                event(
+                    1,
+                    OrderEventKind::StatusChanged,
+                    Some("waiting"),
+                    Some("cancelled")
+                ),
+                event(
+                    2,
+                    OrderEventKind::PriorityChanged,
+                    Some("normal"),
+                    Some("vip")
+                ),
+                event(
+                    2,
+                    OrderEventKind::NotificationRegistered,
+                    None,
+                    Some("line:line-user")
+                ),
+                event(3, OrderEventKind::StatusChanged, None, Some("waiting")),
+                OrderEvent::stock_change(
+                    OrderEventKind::ProductionReported,
+                    &tsubuan,
+                    2,
+                    10,
+                    &actor,
+                    now
+                ),
+                OrderEvent::stock_change(
+                    OrderEventKind::StockAdjusted,
+                    &tsubuan,
+                    10,
+                    7,
+                    &actor,
+                    now
+                ),
+            ]
+        );
+        assert!(events_between(&after, &after, &actor, now).is_empty());
+    }
+}
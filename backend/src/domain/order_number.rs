@@ -1,31 +1,223 @@
-use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
-pub struct DisplayOrderNumber(u8);
+use serde::{Deserialize, Serialize};
+
+use crate::domain::snapshot::{Order, OrderStatus};
+
+/// Number called out to customers and shown on the display, e.g. `23` or `A05`.
+/// Numbers are reused once their order is completed or cancelled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DisplayOrderNumber(String);
 
 impl DisplayOrderNumber {
-    pub fn from_order_id(order_id: u32) -> Self {
-        Self((order_id % 100) as u8)
+    pub fn new(number: impl Into<String>) -> Self {
+        Self(number.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Accepts what customers type: surrounding spaces, a leading `#` and lowercase prefixes.
+    pub fn parse_input(input: &str) -> Option<Self> {
+        let number = input.trim().trim_start_matches(['#', '＃']).trim();
+        (!number.is_empty() && number.chars().all(|c| c.is_ascii_alphanumeric()))
+            .then(|| Self(number.to_ascii_uppercase()))
+    }
+}
+
+impl fmt::Display for DisplayOrderNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Range of display numbers such as `01-99`, `001-999` or `A01-A99`.
+/// The width of the first number sets the zero padding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayNumberPool {
+    prefix: String,
+    first: u32,
+    last: u32,
+    width: usize,
+}
+
+impl DisplayNumberPool {
+    fn number(&self, value: u32) -> DisplayOrderNumber {
+        DisplayOrderNumber(format!(
+            "{}{:0width$}",
+            self.prefix,
+            value,
+            width = self.width
+        ))
+    }
+
+    fn value_of(&self, number: &DisplayOrderNumber) -> Option<u32> {
+        let value = number.as_str().strip_prefix(self.prefix.as_str())?;
+        if value.len() != self.width || !value.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let value = value.parse().ok()?;
+        (self.first <= value && value <= self.last).then_some(value)
+    }
+
+    /// Next free number after the one this pool handed out last, so a number just freed is not
+    /// called again right away. Numbers of orders that are not completed or cancelled are
+    /// skipped; `None` when every number is in use.
+    pub fn allocate<'a>(
+        &self,
+        orders: impl IntoIterator<Item = &'a Order>,
+    ) -> Option<DisplayOrderNumber> {
+        let orders = orders.into_iter().collect::<Vec<_>>();
+        let in_use = orders
+            .iter()
+            .filter(|order| is_holding_number(order))
+            .map(|order| &order.display_number)
+            .collect::<HashSet<_>>();
+        let last_allocated = orders
+            .iter()
+            .rev()
+            .find_map(|order| self.value_of(&order.display_number));
+        let size = self.last - self.first + 1;
+        let start = last_allocated.map_or(0, |value| value - self.first + 1);
+
+        (0..size)
+            .map(|offset| self.first + (start + offset) % size)
+            .map(|value| self.number(value))
+            .find(|number| !in_use.contains(number))
     }
+}
 
-    pub fn as_str(self) -> String {
-        format!("{:02}", self.0)
+impl FromStr for DisplayNumberPool {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid display number range `{value}`, e.g. `01-99` or `A01-A99`");
+        let (first, last) = value.trim().split_once('-').ok_or_else(invalid)?;
+        let split = |bound: &str| {
+            let digits_at = bound
+                .find(|c: char| c.is_ascii_digit())
+                .ok_or_else(invalid)?;
+            let (prefix, digits) = bound.split_at(digits_at);
+            let number = digits.parse::<u32>().map_err(|_| invalid())?;
+            Ok::<_, String>((prefix.to_ascii_uppercase(), number, digits.len()))
+        };
+        let (prefix, first, width) = split(first.trim())?;
+        let (last_prefix, last, _) = split(last.trim())?;
+        if !prefix.chars().all(|c| c.is_ascii_alphabetic()) || prefix != last_prefix || first > last
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            prefix,
+            first,
+            last,
+            width,
+        })
     }
 }
 
+/// Pools display numbers are drawn from. Priority orders can get their own range so they stand
+/// out on the display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayNumberSettings {
+    pub regular: DisplayNumberPool,
+    pub priority: Option<DisplayNumberPool>,
+}
+
+impl DisplayNumberSettings {
+    pub fn pool_for(&self, is_priority: bool) -> &DisplayNumberPool {
+        match &self.priority {
+            Some(pool) if is_priority => pool,
+            _ => &self.regular,
+        }
+    }
+}
+
+impl Default for DisplayNumberSettings {
+    fn default() -> Self {
+        Self {
+            regular: "01-99".parse().expect("valid default range"),
+            priority: None,
+        }
+    }
+}
+
+pub(crate) fn is_holding_number(order: &Order) -> bool {
+    !matches!(
+        order.status,
+        OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Expired
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use super::DisplayOrderNumber;
+    use chrono::{TimeZone, Utc};
 
-    #[test]
-    fn formats_order_id_as_two_digit_reusable_number() {
-        let cases = [(1, "01"), (23, "23"), (100, "00"), (123, "23")];
-
-        for (order_id, expected) in cases {
-            assert_eq!(
-                DisplayOrderNumber::from_order_id(order_id).as_str(),
-                expected
-            );
+    use crate::domain::snapshot::{Order, OrderStatus};
+
+    use super::{DisplayNumberPool, DisplayOrderNumber};
+
+    fn order(id: u32, number: &str, status: OrderStatus) -> Order {
+        Order {
+            id,
+            items: Vec::new(),
+            status,
+            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+            ready_at: None,
+            completed_at: None,
+            notify: Default::default(),
+            priority: Default::default(),
+            total_price: 0,
+            pricing_rule: None,
+            discount_amount: 0,
+            payment_method: None,
+            refunded_at: None,
+            handed_over: Vec::new(),
+            display_number: DisplayOrderNumber::new(number),
+            business_day_id: None,
+            pickup_at: None,
+            release_at: None,
+            no_show_at: None,
+            expired_at: None,
         }
     }
+
+    #[test]
+    fn allocation_skips_numbers_of_active_orders() {
+        let pool = "01-03".parse::<DisplayNumberPool>().expect("valid range");
+        let mut orders = vec![
+            order(1, "01", OrderStatus::Waiting),
+            order(2, "02", OrderStatus::Completed),
+            order(3, "03", OrderStatus::Ready),
+        ];
+
+        // 01 is still waiting, so the pool wraps around to the freed 02.
+        assert_eq!(pool.allocate(&orders), Some(DisplayOrderNumber::new("02")));
+
+        orders[1].status = OrderStatus::Cooking;
+        assert_eq!(pool.allocate(&orders), None);
+    }
+
+    #[test]
+    fn prefixed_ranges_keep_their_padding() {
+        let pool = "a001-a500"
+            .parse::<DisplayNumberPool>()
+            .expect("valid range");
+
+        assert_eq!(pool.allocate(&[]), Some(DisplayOrderNumber::new("A001")));
+        assert_eq!(
+            pool.allocate(&[order(1, "A041", OrderStatus::Completed)]),
+            Some(DisplayOrderNumber::new("A042"))
+        );
+        assert!("A01-B99".parse::<DisplayNumberPool>().is_err());
+        assert_eq!(
+            DisplayOrderNumber::parse_input(" #a042 "),
+            Some(DisplayOrderNumber::new("A042"))
+        );
+    }
 }
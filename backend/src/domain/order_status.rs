@@ -1,10 +1,10 @@
 use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, Utc};
-use enum_map::EnumMap;
 
-use crate::domain::order_number::DisplayOrderNumber;
 use crate::domain::snapshot::{Flavor, Notify, Order, OrderStatus, OrderSystemSnapshot};
+use crate::domain::stock;
+use crate::domain::wait_time;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PendingNotification {
@@ -19,6 +19,117 @@ pub struct StatusUpdate {
     pub notifications: Vec<PendingNotification>,
 }
 
+/// Status changes requested by staff. Moves between Waiting, Cooking and Ready are left to
+/// allocation in `update_order_statuses`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderTransition {
+    Complete,
+    Cancel,
+    /// Undoes a mistaken completion.
+    Reopen,
+    /// Undoes a mistaken cancellation, or takes back an expired order whose customer turned up.
+    Restore,
+    /// Gives up on a ready order nobody came for.
+    Expire,
+}
+
+impl OrderTransition {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderTransition::Complete => "complete",
+            OrderTransition::Cancel => "cancel",
+            OrderTransition::Reopen => "reopen",
+            OrderTransition::Restore => "restore",
+            OrderTransition::Expire => "expire",
+        }
+    }
+
+    pub fn target(self) -> OrderStatus {
+        match self {
+            OrderTransition::Complete => OrderStatus::Completed,
+            OrderTransition::Cancel => OrderStatus::Cancelled,
+            OrderTransition::Reopen | OrderTransition::Restore => OrderStatus::Waiting,
+            OrderTransition::Expire => OrderStatus::Expired,
+        }
+    }
+
+    pub fn is_allowed_from(self, from: OrderStatus) -> bool {
+        match self {
+            OrderTransition::Complete | OrderTransition::Cancel => matches!(
+                from,
+                OrderStatus::Waiting | OrderStatus::Cooking | OrderStatus::Ready
+            ),
+            OrderTransition::Reopen => from == OrderStatus::Completed,
+            OrderTransition::Restore => {
+                matches!(from, OrderStatus::Cancelled | OrderStatus::Expired)
+            }
+            OrderTransition::Expire => from == OrderStatus::Ready,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub from: OrderStatus,
+    pub transition: OrderTransition,
+}
+
+/// Moves the order to the transition's target and returns the status it left.
+/// Stock is not touched here; callers return or re-allocate it afterwards.
+pub fn apply_transition(
+    order: &mut Order,
+    transition: OrderTransition,
+    now: DateTime<Utc>,
+) -> Result<OrderStatus, InvalidTransition> {
+    let from = order.status;
+    if !transition.is_allowed_from(from) {
+        return Err(InvalidTransition { from, transition });
+    }
+
+    order.status = transition.target();
+    match transition {
+        OrderTransition::Complete => {
+            order.completed_at = Some(now);
+        }
+        OrderTransition::Cancel => {}
+        OrderTransition::Reopen | OrderTransition::Restore => {
+            order.ready_at = None;
+            order.completed_at = None;
+            order.no_show_at = None;
+            order.expired_at = None;
+        }
+        OrderTransition::Expire => {
+            order.expired_at = Some(now);
+        }
+    }
+    Ok(from)
+}
+
+/// Apologies to the customers of `told_ready` orders that are back in the queue.
+pub fn correction_notifications(
+    snapshot: &OrderSystemSnapshot,
+    told_ready: &HashSet<u32>,
+) -> Vec<PendingNotification> {
+    snapshot
+        .orders
+        .iter()
+        .filter(|order| {
+            told_ready.contains(&order.id)
+                && matches!(order.status, OrderStatus::Waiting | OrderStatus::Cooking)
+        })
+        .flat_map(|order| {
+            order.notify.iter().cloned().map(|notify| PendingNotification {
+                order_id: order.id,
+                notify,
+                message: format!(
+                    "申し訳ありません。#{}番 のご注文の準備完了のお知らせは誤りでした。準備ができ次第あらためてお知らせします。",
+                    order.display_number
+                ),
+            })
+        })
+        .collect()
+}
+
 pub fn update_order_statuses(snapshot: &mut OrderSystemSnapshot) -> StatusUpdate {
     update_order_statuses_at(snapshot, Utc::now())
 }
@@ -45,93 +156,104 @@ fn update_order_statuses_at(
         order.status = OrderStatus::Waiting;
     }
 
+    // Pre-orders are left out until their lead time before pickup.
     let mut stock = std::mem::take(&mut snapshot.unallocated_stock);
     let mut waiting_order_indices = snapshot
         .orders
         .iter()
         .enumerate()
-        .filter(|(_, order)| order.status == OrderStatus::Waiting)
+        .filter(|(_, order)| order.status == OrderStatus::Waiting && !order.is_held(now))
         .map(|(index, _)| index)
         .collect::<Vec<_>>();
 
-    waiting_order_indices.sort_by_key(|&index| {
-        (
-            !snapshot.orders[index].is_priority,
-            snapshot.orders[index].ordered_at,
-            snapshot.orders[index].id,
-        )
-    });
+    let aging = snapshot.priority_aging;
+    waiting_order_indices
+        .sort_by_key(|&index| wait_time::order_priority_key(&snapshot.orders[index], aging, now));
 
-    for index in waiting_order_indices {
+    let queue = waiting_order_indices
+        .iter()
+        .map(|&index| &snapshot.orders[index])
+        .collect::<Vec<_>>();
+    let filled_order_indices = snapshot
+        .allocation_policy
+        .policy()
+        .allocate(&queue, &mut stock)
+        .into_iter()
+        .map(|position| waiting_order_indices[position])
+        .collect::<Vec<_>>();
+
+    for index in filled_order_indices {
         let order = &mut snapshot.orders[index];
-        if can_fulfill(order, &stock) {
-            fulfill(order, &mut stock);
-            order.status = OrderStatus::Ready;
-            order.ready_at.replace(now);
-            newly_ready_orders.push(order.id);
-            notifications.extend(
-                order
-                    .notify
-                    .iter()
-                    .cloned()
-                    .map(|notify| PendingNotification {
-                        order_id: order.id,
-                        notify,
-                        message: format!(
-                            "#{}番 のご注文の準備ができました！",
-                            DisplayOrderNumber::from_order_id(order.id).as_str()
-                        ),
-                    }),
-            );
-        }
+        order.status = OrderStatus::Ready;
+        order.ready_at.replace(now);
+        newly_ready_orders.push(order.id);
+        notifications.extend(
+            order
+                .notify
+                .iter()
+                .cloned()
+                .map(|notify| PendingNotification {
+                    order_id: order.id,
+                    notify,
+                    message: format!("#{}番 のご注文の準備ができました！", order.display_number),
+                }),
+        );
     }
     snapshot.unallocated_stock = stock;
+    stock::reconcile_lots(snapshot, now);
 
-    let mut waiting_orders = snapshot
+    let mut waiting_order_indices = snapshot
         .orders
-        .iter_mut()
-        .filter(|order| order.status == OrderStatus::Waiting)
+        .iter()
+        .enumerate()
+        .filter(|(_, order)| order.status == OrderStatus::Waiting && !order.is_held(now))
+        .map(|(index, _)| index)
         .collect::<Vec<_>>();
-    waiting_orders.sort_by_key(|order| (!order.is_priority, order.ordered_at, order.id));
+    waiting_order_indices
+        .sort_by_key(|&index| wait_time::order_priority_key(&snapshot.orders[index], aging, now));
 
+    // An order is cooking when the batches on the grill cover what stock cannot.
+    let mut on_the_grill = HashMap::<Flavor, usize>::new();
+    for batch in snapshot.active_batches() {
+        *on_the_grill.entry(batch.flavor.clone()).or_insert(0) += batch.quantity;
+    }
     let mut cumulative_demand = HashMap::new();
+    let mut cooking_order_indices = Vec::new();
 
-    for order in waiting_orders {
-        let mut is_cooking = !order.items.is_empty();
-
-        for item in &order.items {
-            let demand_so_far = cumulative_demand.get(&item.flavor).copied().unwrap_or(0);
-            let current_stock = snapshot.unallocated_stock[item.flavor];
-            let total_demand_for_item = demand_so_far + item.quantity;
-            let needed_from_production = total_demand_for_item.saturating_sub(current_stock);
-
-            if needed_from_production > 0 {
-                let config = snapshot.flavor_configs[item.flavor];
-                if needed_from_production > config.quantity_per_batch as usize {
-                    is_cooking = false;
-                    break;
-                }
-            }
+    for index in waiting_order_indices {
+        let order = &snapshot.orders[index];
+        for (flavor, quantity) in order.outstanding() {
+            *cumulative_demand.entry(flavor.clone()).or_insert(0) += quantity;
+        }
+
+        if order.outstanding().next().is_some()
+            && order.outstanding().all(|(flavor, _)| {
+                cumulative_demand[flavor]
+                    <= snapshot.stock_of(flavor) + on_the_grill.get(flavor).copied().unwrap_or(0)
+            })
+        {
+            cooking_order_indices.push(index);
         }
+    }
 
-        if is_cooking {
-            order.status = OrderStatus::Cooking;
-            if !previously_cooking_order_ids.contains(&order.id) {
-                notifications.extend(order.notify.iter().cloned().map(|notify| {
-                    PendingNotification {
+    for index in cooking_order_indices {
+        let order = &mut snapshot.orders[index];
+        order.status = OrderStatus::Cooking;
+        if !previously_cooking_order_ids.contains(&order.id) {
+            notifications.extend(
+                order
+                    .notify
+                    .iter()
+                    .cloned()
+                    .map(|notify| PendingNotification {
                         order_id: order.id,
                         notify,
                         message: format!(
                             "#{}番 調理中です！\n遠くにいる場合は近くでお待ちください。",
-                            DisplayOrderNumber::from_order_id(order.id).as_str()
+                            order.display_number
                         ),
-                    }
-                }));
-            }
-        }
-
-        for item in &order.items {
-            *cumulative_demand.entry(item.flavor).or_insert(0) += item.quantity;
+                    }),
+            );
         }
     }
 
@@ -141,51 +263,65 @@ fn update_order_statuses_at(
     }
 }
 
-fn can_fulfill(order: &Order, stock: &EnumMap<Flavor, usize>) -> bool {
-    order
-        .items
+/// Unallocated pieces the allocation policy leaves for order `id` once the orders queued ahead of
+/// it are served.
+pub fn stock_left_for(snapshot: &OrderSystemSnapshot, id: u32) -> HashMap<Flavor, usize> {
+    let now = Utc::now();
+    let aging = snapshot.priority_aging;
+    let Some(order) = snapshot.orders.iter().find(|order| order.id == id) else {
+        return HashMap::new();
+    };
+    let key = wait_time::order_priority_key(order, aging, now);
+    let mut ahead = snapshot
+        .orders
         .iter()
-        .all(|item| stock[item.flavor] >= item.quantity)
-}
-
-fn fulfill(order: &Order, stock: &mut EnumMap<Flavor, usize>) {
-    for item in &order.items {
-        stock[item.flavor] -= item.quantity;
-    }
+        .filter(|other| {
+            matches!(other.status, OrderStatus::Waiting | OrderStatus::Cooking)
+                && !other.is_held(now)
+                && wait_time::order_priority_key(other, aging, now) < key
+        })
+        .collect::<Vec<_>>();
+    ahead.sort_by_key(|other| wait_time::order_priority_key(other, aging, now));
+    snapshot
+        .allocation_policy
+        .policy()
+        .left_for_next(&ahead, snapshot.unallocated_stock.clone())
 }
 
 #[cfg(test)]
 mod tests {
     use chrono::{Duration, TimeZone, Utc};
 
-    use crate::domain::snapshot::{Flavor, Item, Notify, Order, OrderStatus, OrderSystemSnapshot};
+    use crate::domain::allocation::AllocationPolicyKind;
+    use crate::domain::order_number::DisplayOrderNumber;
+    use crate::domain::snapshot::{
+        Batch, Flavor, Notify, Order, OrderStatus, PriorityAging, PriorityTier,
+    };
+    use crate::test_fixtures::{snapshot_with_orders, tsubuan, waiting_order};
 
-    use super::update_order_statuses_at;
+    use super::{OrderTransition, apply_transition, update_order_statuses_at};
 
-    fn order(id: u32, is_priority: bool) -> Order {
+    fn order(id: u32, priority: PriorityTier) -> Order {
         Order {
-            id,
-            items: vec![Item {
-                flavor: Flavor::Tsubuan,
-                quantity: 1,
-            }],
-            status: OrderStatus::Waiting,
-            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
-                + Duration::seconds(id.into()),
-            ready_at: None,
-            completed_at: None,
-            notify: Default::default(),
-            is_priority,
+            priority,
+            ..waiting_order(id, 1)
         }
     }
 
     #[test]
     fn priority_order_can_overtake_normal_order() {
-        let mut snapshot = OrderSystemSnapshot {
-            orders: vec![order(1, false), order(2, true)],
-            ..Default::default()
-        };
-        snapshot.unallocated_stock[Flavor::Tsubuan] = 1;
+        let mut snapshot = snapshot_with_orders(vec![
+            order(1, PriorityTier::Normal),
+            order(2, PriorityTier::Vip),
+        ]);
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
+        snapshot.batches.push(Batch {
+            id: 1,
+            flavor: tsubuan(),
+            quantity: 9,
+            started_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 50, 0).unwrap(),
+            finished_at: None,
+        });
 
         let update = update_order_statuses_at(
             &mut snapshot,
@@ -197,17 +333,91 @@ mod tests {
         assert_eq!(snapshot.orders[1].status, OrderStatus::Ready);
     }
 
+    #[test]
+    fn long_waiting_order_is_promoted_past_newer_higher_tier_orders() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 25, 0).unwrap();
+        let staff = Order {
+            ordered_at: now - Duration::minutes(5),
+            ..order(2, PriorityTier::Staff)
+        };
+        let accessibility = Order {
+            ordered_at: now - Duration::minutes(1),
+            ..order(3, PriorityTier::Accessibility)
+        };
+        let mut snapshot =
+            snapshot_with_orders(vec![order(1, PriorityTier::Normal), staff, accessibility]);
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
+
+        let update = update_order_statuses_at(&mut snapshot.clone(), now);
+        assert_eq!(update.newly_ready_orders, vec![2]);
+
+        // Two promotions in 25 minutes lift the normal order to the staff tier, where it is the
+        // oldest.
+        snapshot.priority_aging = PriorityAging {
+            promote_every_minutes: Some(10),
+        };
+        let update = update_order_statuses_at(&mut snapshot, now);
+        assert_eq!(update.newly_ready_orders, vec![1]);
+    }
+
+    #[test]
+    fn allocation_policies_differ_on_a_large_order_at_the_head() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        let custard = Flavor::new("custard");
+        let mut large = order(1, PriorityTier::Normal);
+        large.items[0].quantity = 3;
+        let mut other_flavor = order(3, PriorityTier::Normal);
+        other_flavor.items[0].flavor = custard.clone();
+        let mut snapshot =
+            snapshot_with_orders(vec![large, order(2, PriorityTier::Normal), other_flavor]);
+        snapshot.unallocated_stock.insert(tsubuan(), 2);
+        snapshot.unallocated_stock.insert(custard, 1);
+
+        let ready_under = |policy| {
+            let mut snapshot = snapshot.clone();
+            snapshot.allocation_policy = policy;
+            update_order_statuses_at(&mut snapshot, now).newly_ready_orders
+        };
+
+        assert_eq!(ready_under(AllocationPolicyKind::Greedy), vec![2, 3]);
+        assert_eq!(
+            ready_under(AllocationPolicyKind::StrictFifo),
+            Vec::<u32>::new()
+        );
+        assert_eq!(
+            ready_under(AllocationPolicyKind::ReserveHeadOfLine),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn pre_order_takes_stock_only_after_its_release() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        let mut pre_order = order(1, PriorityTier::Normal);
+        pre_order.pickup_at = Some(now + Duration::minutes(45));
+        pre_order.release_at = Some(now + Duration::minutes(15));
+        let mut snapshot = snapshot_with_orders(vec![pre_order, order(2, PriorityTier::Normal)]);
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
+
+        let update = update_order_statuses_at(&mut snapshot, now);
+
+        assert_eq!(update.newly_ready_orders, vec![2]);
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Waiting);
+
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
+        let update = update_order_statuses_at(&mut snapshot, now + Duration::minutes(15));
+        assert_eq!(update.newly_ready_orders, vec![1]);
+    }
+
     #[test]
     fn ready_notification_uses_display_order_number() {
-        let mut target = order(123, false);
+        let mut target = order(123, PriorityTier::Normal);
+        target.display_number = DisplayOrderNumber::new("23");
         target.notify.insert(Notify::Line {
             user_id: "user".to_owned(),
         });
-        let mut snapshot = OrderSystemSnapshot {
-            orders: vec![target],
-            ..Default::default()
-        };
-        snapshot.unallocated_stock[Flavor::Tsubuan] = 1;
+        let mut snapshot = snapshot_with_orders(vec![target]);
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
 
         let update = update_order_statuses_at(
             &mut snapshot,
@@ -217,4 +427,24 @@ mod tests {
         assert_eq!(update.notifications.len(), 1);
         assert!(update.notifications[0].message.contains("#23番"));
     }
+
+    #[test]
+    fn finished_orders_only_leave_through_undo_transitions() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        let mut cancelled = order(1, PriorityTier::Normal);
+        cancelled.status = OrderStatus::Cancelled;
+
+        let error = apply_transition(&mut cancelled, OrderTransition::Complete, now)
+            .expect_err("cancelled order cannot be completed");
+        assert_eq!(error.from, OrderStatus::Cancelled);
+        assert!(apply_transition(&mut cancelled, OrderTransition::Reopen, now).is_err());
+
+        apply_transition(&mut cancelled, OrderTransition::Restore, now).expect("restore");
+        assert_eq!(cancelled.status, OrderStatus::Waiting);
+
+        apply_transition(&mut cancelled, OrderTransition::Complete, now).expect("complete");
+        assert!(apply_transition(&mut cancelled, OrderTransition::Cancel, now).is_err());
+        apply_transition(&mut cancelled, OrderTransition::Reopen, now).expect("reopen");
+        assert_eq!(cancelled.completed_at, None);
+    }
 }
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::availability::{store_offset, store_time};
+use crate::domain::snapshot::{OrderStatus, OrderSystemSnapshot};
+
+/// Pickup slots for pre-orders and how long before its slot a pre-order is cooked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreOrderSettings {
+    /// Slots start at midnight and every `slot_minutes` after it, store time.
+    pub slot_minutes: u32,
+    /// Pieces that can be reserved per slot.
+    pub slot_capacity: usize,
+    /// Minutes before its slot that a pre-order joins the queue.
+    pub lead_minutes: i64,
+}
+
+impl Default for PreOrderSettings {
+    fn default() -> Self {
+        Self {
+            slot_minutes: 30,
+            slot_capacity: 36,
+            lead_minutes: 30,
+        }
+    }
+}
+
+impl PreOrderSettings {
+    /// Start of the slot `at` falls in.
+    pub fn slot_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let local = store_time(at);
+        let minutes = local.hour() * 60 + local.minute();
+        let offset = minutes % self.slot_minutes.max(1);
+        at - Duration::minutes(offset.into())
+            - Duration::seconds(local.second().into())
+            - Duration::nanoseconds(local.nanosecond().into())
+    }
+
+    pub fn release_at(&self, starts_at: DateTime<Utc>) -> DateTime<Utc> {
+        starts_at - Duration::minutes(self.lead_minutes)
+    }
+
+    /// First slot that can still be cooked in full before it starts.
+    pub fn earliest_slot(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let starts_at = self.slot_start(now + Duration::minutes(self.lead_minutes));
+        if self.release_at(starts_at) < now {
+            starts_at + Duration::minutes(self.slot_minutes.into())
+        } else {
+            starts_at
+        }
+    }
+}
+
+/// Pickup slot with what is left of its capacity.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PickupSlot {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reserved: usize,
+    pub remaining: usize,
+}
+
+/// Pieces of pre-orders that are not cancelled, for the slot starting at `starts_at`.
+pub fn reserved_in_slot(snapshot: &OrderSystemSnapshot, starts_at: DateTime<Utc>) -> usize {
+    snapshot
+        .orders
+        .iter()
+        .filter(|order| {
+            order.status != OrderStatus::Cancelled && order.pickup_at == Some(starts_at)
+        })
+        .flat_map(|order| &order.items)
+        .map(|item| item.quantity)
+        .sum()
+}
+
+/// Slots that can still be booked, up to the end of the store date `date`.
+pub fn open_slots(
+    snapshot: &OrderSystemSnapshot,
+    settings: PreOrderSettings,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+) -> Vec<PickupSlot> {
+    let slot = Duration::minutes(settings.slot_minutes.max(1).into());
+    let Some(end) = at_store_time(date.succ_opt().unwrap_or(date), NaiveTime::MIN) else {
+        return Vec::new();
+    };
+    let mut starts_at = settings.earliest_slot(now);
+    let mut slots = Vec::new();
+    while starts_at < end {
+        let reserved = reserved_in_slot(snapshot, starts_at);
+        slots.push(PickupSlot {
+            starts_at,
+            ends_at: starts_at + slot,
+            reserved,
+            remaining: settings.slot_capacity.saturating_sub(reserved),
+        });
+        starts_at += slot;
+    }
+    slots
+}
+
+/// Reads times customers type, e.g. `14:00` or `14時`, as a time on the store date `date`.
+pub fn parse_pickup_time(input: &str, date: NaiveDate) -> Option<DateTime<Utc>> {
+    let input = input.trim().replace('：', ":");
+    let time = NaiveTime::parse_from_str(&input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(&input, "%H時%M分"))
+        .or_else(|_| NaiveTime::parse_from_str(&format!("{input}00分"), "%H時%M分"))
+        .ok()?;
+    at_store_time(date, time)
+}
+
+fn at_store_time(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+    store_offset()
+        .from_local_datetime(&date.and_time(time))
+        .single()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::{PreOrderSettings, parse_pickup_time};
+
+    #[test]
+    fn slots_are_aligned_to_store_time_and_leave_the_lead_time() {
+        let settings = PreOrderSettings::default();
+        // 2026-01-01 13:47 JST
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 4, 47, 12).unwrap();
+
+        assert_eq!(
+            settings.slot_start(now),
+            Utc.with_ymd_and_hms(2026, 1, 1, 4, 30, 0).unwrap()
+        );
+        // 14:00 should have joined the queue at 13:30 already.
+        assert_eq!(
+            settings.earliest_slot(now),
+            Utc.with_ymd_and_hms(2026, 1, 1, 5, 30, 0).unwrap()
+        );
+        assert_eq!(
+            parse_pickup_time("14時", NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()),
+            Some(Utc.with_ymd_and_hms(2026, 1, 1, 5, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_pickup_time("１４:00", NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()),
+            None
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::snapshot::{Flavor, OrderSystemSnapshot};
+use crate::domain::station::{self, PlateSchedule};
+use crate::domain::wait_time;
+
+/// Batches to grill next, in the order they should go on the plates.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductionPlan {
+    pub batches: Vec<PlannedBatch>,
+    /// Sum of the estimated waits of all queued orders if the plan is followed.
+    pub total_wait_minutes: i64,
+    /// Flavors with queued demand that cannot be grilled, e.g. without a cooking config.
+    pub unplannable_flavors: Vec<Flavor>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedBatch {
+    pub flavor: Flavor,
+    pub display_name: String,
+    pub quantity: usize,
+    /// Minutes from now until a plate is free for the batch; 0 means it can go on now.
+    pub start_in_minutes: i64,
+    pub ready_in_minutes: i64,
+    /// Queued orders that get pieces from this batch.
+    pub order_ids: Vec<u32>,
+}
+
+// Pieces of a flavor that will become available, soonest first.
+struct Supply {
+    quantity: usize,
+    ready_in: i64,
+    planned_index: Option<usize>,
+}
+
+/// Plans full batches so that queued orders are served in priority order, each batch going
+/// on the plate that frees up first. Stock and the batches on the grill are used up before
+/// anything new is planned, so every planned batch shortens some order's wait.
+pub fn plan_production(snapshot: &OrderSystemSnapshot, now: DateTime<Utc>) -> ProductionPlan {
+    let mut queued_orders = snapshot
+        .orders
+        .iter()
+        .filter(|order| wait_time::is_queued(order, now))
+        .collect::<Vec<_>>();
+    queued_orders
+        .sort_by_key(|order| wait_time::order_priority_key(order, snapshot.priority_aging, now));
+
+    let mut supplies = HashMap::<&Flavor, VecDeque<Supply>>::new();
+    for order in &queued_orders {
+        for (flavor, _) in order.outstanding() {
+            supplies.entry(flavor).or_insert_with(|| {
+                let mut supply = snapshot
+                    .active_batches()
+                    .filter(|batch| &batch.flavor == flavor)
+                    .map(|batch| Supply {
+                        quantity: batch.quantity,
+                        ready_in: station::remaining_cooking_minutes(snapshot, batch, now),
+                        planned_index: None,
+                    })
+                    .collect::<Vec<_>>();
+                supply.sort_by_key(|supply| supply.ready_in);
+                let stock = Supply {
+                    quantity: snapshot.stock_of(flavor),
+                    ready_in: 0,
+                    planned_index: None,
+                };
+                std::iter::once(stock).chain(supply).collect()
+            });
+        }
+    }
+
+    let mut schedule = PlateSchedule::new(snapshot, now);
+    let mut batches = Vec::<PlannedBatch>::new();
+    let mut unplannable_flavors = Vec::<Flavor>::new();
+    let mut total_wait_minutes = 0;
+
+    for order in queued_orders {
+        let mut order_ready_in = 0;
+        for (flavor, quantity) in order.outstanding() {
+            let supply = supplies.get_mut(flavor).expect("supply of queued flavor");
+            let mut needed = quantity;
+            while needed > 0 {
+                if supply.front().is_none_or(|supply| supply.quantity == 0) {
+                    supply.pop_front();
+                }
+                if supply.is_empty() {
+                    let Some(batch) = plan_batch(snapshot, &mut schedule, flavor) else {
+                        if !unplannable_flavors.contains(flavor) {
+                            unplannable_flavors.push(flavor.clone());
+                        }
+                        break;
+                    };
+                    supply.push_back(Supply {
+                        quantity: batch.quantity,
+                        ready_in: batch.ready_in_minutes,
+                        planned_index: Some(batches.len()),
+                    });
+                    batches.push(batch);
+                }
+                let Some(next) = supply.front_mut() else {
+                    break;
+                };
+                let taken = needed.min(next.quantity);
+                next.quantity -= taken;
+                needed -= taken;
+                if taken > 0 {
+                    order_ready_in = order_ready_in.max(next.ready_in);
+                    if let Some(index) = next.planned_index {
+                        let order_ids = &mut batches[index].order_ids;
+                        if !order_ids.contains(&order.id) {
+                            order_ids.push(order.id);
+                        }
+                    }
+                }
+            }
+        }
+        total_wait_minutes += order_ready_in;
+    }
+
+    ProductionPlan {
+        batches,
+        total_wait_minutes,
+        unplannable_flavors,
+    }
+}
+
+fn plan_batch(
+    snapshot: &OrderSystemSnapshot,
+    schedule: &mut PlateSchedule,
+    flavor: &Flavor,
+) -> Option<PlannedBatch> {
+    let config = snapshot.flavor_configs.get(flavor)?;
+    if config.quantity_per_batch == 0 {
+        return None;
+    }
+    let ready_in_minutes = schedule.grill_batch(flavor)?;
+    Some(PlannedBatch {
+        flavor: flavor.clone(),
+        display_name: snapshot.flavor_name(flavor).to_owned(),
+        quantity: config.quantity_per_batch as usize,
+        start_in_minutes: ready_in_minutes - config.cooking_time_minutes as i64,
+        ready_in_minutes,
+        order_ids: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::domain::snapshot::{Flavor, FlavorConfig, Item, Order, OrderSystemSnapshot};
+    use crate::test_fixtures::{self, flavor_config, menu_flavor};
+
+    use super::plan_production;
+
+    fn snapshot_with_flavors() -> OrderSystemSnapshot {
+        let mut snapshot = OrderSystemSnapshot::default();
+        for (id, sort_order, quantity_per_batch) in [("tsubuan", 1, 9), ("kurikinton", 2, 2)] {
+            snapshot.add_flavor(
+                menu_flavor(id, sort_order),
+                FlavorConfig {
+                    quantity_per_batch,
+                    ..flavor_config()
+                },
+            );
+        }
+        snapshot
+    }
+
+    fn waiting_order(id: u32, items: &[(&str, usize)]) -> Order {
+        Order {
+            items: items
+                .iter()
+                .map(|&(flavor, quantity)| Item {
+                    flavor: Flavor::new(flavor),
+                    quantity,
+                })
+                .collect(),
+            ..test_fixtures::waiting_order(id, 0)
+        }
+    }
+
+    #[test]
+    fn plan_covers_queue_after_stock_in_priority_order() {
+        let mut snapshot = snapshot_with_flavors();
+        snapshot.unallocated_stock.insert(Flavor::new("tsubuan"), 2);
+        snapshot.orders = vec![
+            waiting_order(1, &[("tsubuan", 5)]),
+            waiting_order(2, &[("kurikinton", 3), ("tsubuan", 4)]),
+        ];
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+
+        let plan = plan_production(&snapshot, now);
+
+        let batches = plan
+            .batches
+            .iter()
+            .map(|batch| {
+                (
+                    batch.flavor.as_str(),
+                    batch.start_in_minutes,
+                    batch.order_ids.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            vec![
+                ("tsubuan", 0, vec![1, 2]),
+                ("kurikinton", 0, vec![2]),
+                ("kurikinton", 15, vec![2]),
+            ]
+        );
+        assert_eq!(plan.total_wait_minutes, 15 + 30);
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::business_day::business_date_of;
+use crate::domain::snapshot::{Order, OrderStatus, OrderSystemSnapshot, PaymentMethod};
+
+/// Takings of one store day, for reconciling the cash box.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevenueSummary {
+    pub date: NaiveDate,
+    /// Orders taken, excluding cancelled and expired ones.
+    pub order_count: usize,
+    pub gross_sales: u64,
+    /// Discounts given through pricing rules, already deducted from gross sales.
+    pub discount_amount: u64,
+    /// Cancelled orders and ones expired after the customer never came.
+    pub cancelled_count: usize,
+    pub cancelled_amount: u64,
+    pub refunded_count: usize,
+    pub refunded_amount: u64,
+    /// Gross sales minus refunds; what should be left in hand.
+    pub net_sales: u64,
+    pub by_payment_method: Vec<PaymentMethodRevenue>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentMethodRevenue {
+    /// `None` for orders taken without recording a payment method.
+    pub payment_method: Option<PaymentMethod>,
+    pub order_count: usize,
+    pub net_sales: u64,
+}
+
+const PAYMENT_METHODS: [Option<PaymentMethod>; 4] = [
+    Some(PaymentMethod::Cash),
+    Some(PaymentMethod::QrCode),
+    Some(PaymentMethod::Ticket),
+    None,
+];
+
+pub fn summarize_day(snapshot: &OrderSystemSnapshot, date: NaiveDate) -> RevenueSummary {
+    let orders = snapshot
+        .orders
+        .iter()
+        .filter(|order| business_date_of(snapshot, order) == date)
+        .collect::<Vec<_>>();
+    let (cancelled, sold): (Vec<&Order>, Vec<&Order>) = orders
+        .into_iter()
+        .partition(|order| matches!(order.status, OrderStatus::Cancelled | OrderStatus::Expired));
+    let refunded = sold
+        .iter()
+        .filter(|order| order.refunded_at.is_some())
+        .collect::<Vec<_>>();
+
+    let gross_sales = total_price(sold.iter().copied());
+    let refunded_amount = total_price(refunded.iter().copied().copied());
+
+    let by_payment_method = PAYMENT_METHODS
+        .into_iter()
+        .filter_map(|payment_method| {
+            let orders = sold
+                .iter()
+                .filter(|order| order.payment_method == payment_method)
+                .collect::<Vec<_>>();
+            if orders.is_empty() {
+                return None;
+            }
+            Some(PaymentMethodRevenue {
+                payment_method,
+                order_count: orders.len(),
+                net_sales: total_price(
+                    orders
+                        .iter()
+                        .copied()
+                        .copied()
+                        .filter(|order| order.refunded_at.is_none()),
+                ),
+            })
+        })
+        .collect();
+
+    RevenueSummary {
+        date,
+        order_count: sold.len(),
+        gross_sales,
+        discount_amount: sold
+            .iter()
+            .map(|order| u64::from(order.discount_amount))
+            .sum(),
+        cancelled_count: cancelled.len(),
+        cancelled_amount: total_price(cancelled.iter().copied()),
+        refunded_count: refunded.len(),
+        refunded_amount,
+        net_sales: gross_sales - refunded_amount,
+        by_payment_method,
+    }
+}
+
+// Summed in u64 so a busy day cannot overflow the per-order u32 prices.
+fn total_price<'a>(orders: impl Iterator<Item = &'a Order>) -> u64 {
+    orders.map(|order| u64::from(order.total_price)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::domain::snapshot::{Order, OrderStatus, OrderSystemSnapshot, PaymentMethod};
+    use crate::test_fixtures::waiting_order;
+
+    use super::summarize_day;
+
+    fn order(id: u32, status: OrderStatus, payment_method: Option<PaymentMethod>) -> Order {
+        Order {
+            items: Vec::new(),
+            status,
+            // 2026-01-01 10:00 JST
+            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap(),
+            total_price: 400,
+            payment_method,
+            ..waiting_order(id, 0)
+        }
+    }
+
+    #[test]
+    fn summary_excludes_cancellations_and_refunds_from_net_sales() {
+        let mut refunded = order(3, OrderStatus::Completed, Some(PaymentMethod::Cash));
+        refunded.refunded_at = Some(Utc.with_ymd_and_hms(2026, 1, 1, 2, 0, 0).unwrap());
+        let mut next_day = order(5, OrderStatus::Completed, Some(PaymentMethod::Cash));
+        next_day.ordered_at = Utc.with_ymd_and_hms(2026, 1, 1, 16, 0, 0).unwrap();
+        let snapshot = OrderSystemSnapshot {
+            orders: vec![
+                order(1, OrderStatus::Completed, Some(PaymentMethod::Cash)),
+                order(2, OrderStatus::Ready, Some(PaymentMethod::QrCode)),
+                refunded,
+                order(4, OrderStatus::Cancelled, Some(PaymentMethod::Cash)),
+                next_day,
+            ],
+            ..Default::default()
+        };
+
+        let summary = summarize_day(&snapshot, NaiveDate::from_ymd_opt(2026, 1, 1).unwrap());
+
+        assert_eq!(summary.order_count, 3);
+        assert_eq!(summary.gross_sales, 1200);
+        assert_eq!(summary.cancelled_amount, 400);
+        assert_eq!(summary.refunded_amount, 400);
+        assert_eq!(summary.net_sales, 800);
+        let cash = &summary.by_payment_method[0];
+        assert_eq!(cash.payment_method, Some(PaymentMethod::Cash));
+        assert_eq!((cash.order_count, cash.net_sales), (2, 400));
+    }
+
+    #[test]
+    fn summary_leaves_expired_orders_out_of_sales() {
+        let snapshot = OrderSystemSnapshot {
+            orders: vec![
+                order(1, OrderStatus::Completed, Some(PaymentMethod::Cash)),
+                order(2, OrderStatus::Expired, Some(PaymentMethod::Cash)),
+            ],
+            ..Default::default()
+        };
+
+        let summary = summarize_day(&snapshot, NaiveDate::from_ymd_opt(2026, 1, 1).unwrap());
+
+        assert_eq!(summary.order_count, 1);
+        assert_eq!(summary.gross_sales, 400);
+        assert_eq!(summary.cancelled_count, 1);
+        assert_eq!(summary.net_sales, 400);
+        assert_eq!(summary.by_payment_method[0].order_count, 1);
+    }
+
+    #[test]
+    fn summary_totals_do_not_overflow_order_prices() {
+        let mut first = order(1, OrderStatus::Completed, None);
+        first.total_price = u32::MAX;
+        let mut second = order(2, OrderStatus::Completed, None);
+        second.total_price = u32::MAX;
+        let snapshot = OrderSystemSnapshot {
+            orders: vec![first, second],
+            ..Default::default()
+        };
+
+        let summary = summarize_day(&snapshot, NaiveDate::from_ymd_opt(2026, 1, 1).unwrap());
+
+        assert_eq!(summary.gross_sales, u64::from(u32::MAX) * 2);
+        assert_eq!(summary.net_sales, u64::from(u32::MAX) * 2);
+    }
+}
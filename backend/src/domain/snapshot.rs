@@ -1,90 +1,280 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 use chrono::{DateTime, Utc};
-use enum_map::{Enum, EnumMap, enum_map};
 use serde::{Deserialize, Serialize};
-use strum::EnumIter;
 use utoipa::ToSchema;
 
-#[derive(
-    Serialize, Deserialize, ToSchema, Debug, Enum, EnumIter, Clone, PartialEq, Eq, Hash, Copy,
-)]
-#[serde(rename_all = "camelCase")]
-pub enum Flavor {
-    Tsubuan,
-    Custard,
-    Kurikinton,
-}
+use crate::domain::allocation::AllocationPolicyKind;
+use crate::domain::business_day::BusinessDay;
+use crate::domain::order_number::DisplayOrderNumber;
+
+/// Flavor id as registered in the menu catalog (e.g. `tsubuan`).
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct Flavor(String);
 
 impl fmt::Display for Flavor {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Flavor::Tsubuan => write!(f, "つぶあん"),
-            Flavor::Custard => write!(f, "カスタード"),
-            Flavor::Kurikinton => write!(f, "栗きんとん"),
-        }
+        f.write_str(&self.0)
     }
 }
 
 impl Flavor {
-    pub fn as_db_str(self) -> &'static str {
-        match self {
-            Flavor::Tsubuan => "tsubuan",
-            Flavor::Custard => "custard",
-            Flavor::Kurikinton => "kurikinton",
-        }
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn as_db_str(&self) -> &str {
+        &self.0
     }
 
     pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
-        match value {
-            "tsubuan" => Ok(Flavor::Tsubuan),
-            "custard" => Ok(Flavor::Custard),
-            "kurikinton" => Ok(Flavor::Kurikinton),
-            _ => anyhow::bail!("invalid flavor: {value}"),
+        if !Self::is_valid_id(value) {
+            anyhow::bail!("invalid flavor: {value}");
         }
+        Ok(Self::new(value))
+    }
+
+    // Ids are used in URLs, Discord options and LINE postbacks, so keep them simple.
+    pub fn is_valid_id(value: &str) -> bool {
+        !value.is_empty()
+            && value
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
     }
 }
 
-#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy)]
+/// Catalog entry of a flavor on the menu.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuFlavor {
+    pub id: Flavor,
+    pub display_name: String,
+    pub sort_order: i64,
+    pub is_active: bool,
+    /// Price of one piece in yen.
+    pub unit_price: u32,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct FlavorConfig {
     pub cooking_time_minutes: u32,
     pub quantity_per_batch: u32,
+    /// Pieces that may be ordered per business day; `None` means no cap.
+    #[serde(default)]
+    pub daily_limit: Option<u32>,
+    /// Manual switch for when the filling runs out before the cap is reached.
+    #[serde(default)]
+    pub is_sold_out: bool,
+    /// Station whose plates the flavor is grilled on; without one it has a single plate of its own.
+    #[serde(default)]
+    pub station_id: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Grill with plates that run batches in parallel, shared by the flavors assigned to it.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Station {
+    pub id: u32,
+    pub name: String,
+    pub plate_count: u32,
+}
+
+/// Set menu or discount that staff can apply to an order by its code.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingRule {
+    pub code: String,
+    pub name: String,
+    pub kind: PricingRuleKind,
+    pub is_active: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum PricingRuleKind {
+    /// Every `quantity` pieces of any flavor are sold for `price` yen.
+    Bundle {
+        quantity: u32,
+        price: u32,
+    },
+    PercentageDiscount {
+        percent: u32,
+    },
+    FixedDiscount {
+        amount: u32,
+    },
+}
+
+impl PricingRuleKind {
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            PricingRuleKind::Bundle { .. } => "bundle",
+            PricingRuleKind::PercentageDiscount { .. } => "percentage_discount",
+            PricingRuleKind::FixedDiscount { .. } => "fixed_discount",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct OrderSystemSnapshot {
+    /// Menu catalog, ordered by `sort_order`.
+    pub menu: Vec<MenuFlavor>,
+    pub pricing_rules: Vec<PricingRule>,
+    pub stations: Vec<Station>,
+    pub batches: Vec<Batch>,
+    pub business_days: Vec<BusinessDay>,
     pub orders: Vec<Order>,
-    pub unallocated_stock: EnumMap<Flavor, usize>,
-    pub flavor_configs: EnumMap<Flavor, FlavorConfig>,
-}
-
-impl Default for OrderSystemSnapshot {
-    fn default() -> Self {
-        let flavor_configs = enum_map! {
-            Flavor::Tsubuan => FlavorConfig {
-                cooking_time_minutes: 15,
-                quantity_per_batch: 9,
-            },
-            Flavor::Custard => FlavorConfig {
-                cooking_time_minutes: 15,
-                quantity_per_batch: 9,
-            },
-            Flavor::Kurikinton => FlavorConfig {
-                cooking_time_minutes: 15,
-                quantity_per_batch: 2,
-            },
-        };
-        Self {
-            orders: Vec::new(),
-            unallocated_stock: EnumMap::from_fn(|_| 0),
+    /// Highest order id issued so far, which may belong to an order a working copy left out.
+    pub last_order_id: u32,
+    pub unallocated_stock: HashMap<Flavor, usize>,
+    /// Ages of the pieces in `unallocated_stock`, oldest first per flavor.
+    pub stock_lots: Vec<StockLot>,
+    pub stock_adjustments: Vec<StockAdjustment>,
+    pub flavor_configs: HashMap<Flavor, FlavorConfig>,
+    /// Comes from configuration rather than storage; set by the app after loading.
+    #[serde(skip)]
+    pub priority_aging: PriorityAging,
+    /// Comes from configuration like `priority_aging`.
+    #[serde(skip)]
+    pub allocation_policy: AllocationPolicyKind,
+}
+
+impl OrderSystemSnapshot {
+    pub fn menu_flavor(&self, flavor: &Flavor) -> Option<&MenuFlavor> {
+        self.menu.iter().find(|entry| &entry.id == flavor)
+    }
+
+    pub fn active_flavors(&self) -> impl Iterator<Item = &MenuFlavor> {
+        self.menu.iter().filter(|entry| entry.is_active)
+    }
+
+    pub fn is_on_sale(&self, flavor: &Flavor) -> bool {
+        self.menu_flavor(flavor)
+            .is_some_and(|entry| entry.is_active)
+    }
+
+    /// Display name of the flavor, falling back to its id for unknown flavors.
+    pub fn flavor_name<'a>(&'a self, flavor: &'a Flavor) -> &'a str {
+        self.menu_flavor(flavor)
+            .map_or(flavor.as_str(), |entry| entry.display_name.as_str())
+    }
+
+    /// Total in yen for the items at the current menu prices.
+    pub fn items_total(&self, items: &[Item]) -> u64 {
+        items
+            .iter()
+            .map(|item| u64::from(self.unit_price(&item.flavor)) * item.quantity as u64)
+            .sum()
+    }
+
+    /// Menu price of the flavor, `0` for unknown flavors.
+    pub fn unit_price(&self, flavor: &Flavor) -> u32 {
+        self.menu_flavor(flavor).map_or(0, |entry| entry.unit_price)
+    }
+
+    pub fn pricing_rule(&self, code: &str) -> Option<&PricingRule> {
+        self.pricing_rules
+            .iter()
+            .find(|rule| rule.code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn active_batches(&self) -> impl Iterator<Item = &Batch> {
+        self.batches.iter().filter(|batch| batch.is_active())
+    }
+
+    pub fn business_day(&self, id: u32) -> Option<&BusinessDay> {
+        self.business_days.iter().find(|day| day.id == id)
+    }
+
+    pub fn station(&self, id: u32) -> Option<&Station> {
+        self.stations.iter().find(|station| station.id == id)
+    }
+
+    pub fn next_order_id(&self) -> u32 {
+        self.orders
+            .iter()
+            .map(|order| order.id)
+            .max()
+            .unwrap_or(0)
+            .max(self.last_order_id)
+            + 1
+    }
+
+    /// Copy holding only the orders `keep` picks, for mutations that need not see the rest of
+    /// the history. Hand it back with `merge`.
+    pub fn working_copy(&self, mut keep: impl FnMut(&Order) -> bool) -> Self {
+        // Spelled out so that a new field is not forgotten here.
+        let Self {
+            menu,
+            pricing_rules,
+            stations,
+            batches,
+            business_days,
+            orders,
+            last_order_id,
+            unallocated_stock,
+            stock_lots,
+            stock_adjustments,
             flavor_configs,
+            priority_aging,
+            allocation_policy,
+        } = self;
+        Self {
+            menu: menu.clone(),
+            pricing_rules: pricing_rules.clone(),
+            stations: stations.clone(),
+            batches: batches.clone(),
+            business_days: business_days.clone(),
+            orders: orders.iter().filter(|order| keep(order)).cloned().collect(),
+            last_order_id: *last_order_id,
+            unallocated_stock: unallocated_stock.clone(),
+            stock_lots: stock_lots.clone(),
+            stock_adjustments: stock_adjustments.clone(),
+            flavor_configs: flavor_configs.clone(),
+            priority_aging: *priority_aging,
+            allocation_policy: *allocation_policy,
+        }
+    }
+
+    /// Takes over a working copy, keeping the orders it left out.
+    pub fn merge(&mut self, mut working: Self) {
+        let mut orders = std::mem::take(&mut self.orders);
+        for order in working.orders.drain(..) {
+            match orders.binary_search_by_key(&order.id, |order| order.id) {
+                Ok(index) => orders[index] = order,
+                Err(index) => orders.insert(index, order),
+            }
         }
+        working.orders = orders;
+        *self = working;
+    }
+
+    pub fn stock_of(&self, flavor: &Flavor) -> usize {
+        self.unallocated_stock.get(flavor).copied().unwrap_or(0)
+    }
+
+    /// Registers a flavor in the catalog together with its production config.
+    pub fn add_flavor(&mut self, flavor: MenuFlavor, config: FlavorConfig) {
+        self.flavor_configs.insert(flavor.id.clone(), config);
+        self.unallocated_stock.entry(flavor.id.clone()).or_insert(0);
+        self.menu.push(flavor);
+        self.menu
+            .sort_by(|a, b| (a.sort_order, &a.id).cmp(&(b.sort_order, &b.id)));
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Order {
     pub id: u32,
@@ -94,7 +284,83 @@ pub struct Order {
     pub ready_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub notify: HashSet<Notify>,
-    pub is_priority: bool,
+    pub priority: PriorityTier,
+    /// Amount charged in yen after discounts, fixed when the order is taken.
+    pub total_price: u32,
+    /// Code of the pricing rule applied when the order was taken.
+    pub pricing_rule: Option<String>,
+    pub discount_amount: u32,
+    pub payment_method: Option<PaymentMethod>,
+    pub refunded_at: Option<DateTime<Utc>>,
+    /// Pieces already given to the customer while the rest of the order is still queued.
+    pub handed_over: Vec<Item>,
+    pub display_number: DisplayOrderNumber,
+    /// Business day the order was taken in; `None` for orders from before days were tracked.
+    pub business_day_id: Option<u32>,
+    /// Start of the pickup slot of a pre-order.
+    pub pickup_at: Option<DateTime<Utc>>,
+    /// When a pre-order joins the queue; until then stock is not allocated to it.
+    pub release_at: Option<DateTime<Utc>>,
+    /// When the customer was reminded and the order flagged as not collected.
+    pub no_show_at: Option<DateTime<Utc>>,
+    /// When the uncollected order was given up and its pieces went back to stock.
+    pub expired_at: Option<DateTime<Utc>>,
+}
+
+impl Order {
+    pub fn handed_over_quantity(&self, flavor: &Flavor) -> usize {
+        self.handed_over
+            .iter()
+            .find(|item| &item.flavor == flavor)
+            .map_or(0, |item| item.quantity)
+    }
+
+    /// Pieces still to be handed over, per flavor.
+    pub fn outstanding(&self) -> impl Iterator<Item = (&Flavor, usize)> {
+        self.items.iter().filter_map(|item| {
+            let quantity = item.quantity - self.handed_over_quantity(&item.flavor);
+            (quantity > 0).then_some((&item.flavor, quantity))
+        })
+    }
+
+    /// Pre-order still waiting for its lead time before pickup.
+    pub fn is_held(&self, now: DateTime<Utc>) -> bool {
+        self.status == OrderStatus::Waiting
+            && self.release_at.is_some_and(|release_at| now < release_at)
+    }
+
+    /// Tier the order is served at by `now`, after promotions for waiting. Pre-orders only
+    /// start waiting once they are released.
+    pub fn effective_priority(&self, aging: PriorityAging, now: DateTime<Utc>) -> PriorityTier {
+        let Some(minutes) = aging.promote_every_minutes.filter(|minutes| *minutes > 0) else {
+            return self.priority;
+        };
+        let waiting_since = self.release_at.unwrap_or(self.ordered_at);
+        let promotions = (now - waiting_since).num_minutes().max(0) / minutes;
+        let rank = i64::from(self.priority.rank()) - promotions;
+        PriorityTier::from_rank(rank.max(0) as u8)
+    }
+
+    pub fn is_partially_handed_over(&self) -> bool {
+        !self.handed_over.is_empty() && self.outstanding().next().is_some()
+    }
+}
+
+/// Batch put on the grill by staff. Finished batches are kept as a production record.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Batch {
+    pub id: u32,
+    pub flavor: Flavor,
+    pub quantity: usize,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Batch {
+    pub fn is_active(&self) -> bool {
+        self.finished_at.is_none()
+    }
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
@@ -112,6 +378,8 @@ pub enum OrderStatus {
     Ready,
     Completed,
     Cancelled,
+    /// Ready but never collected; the pieces went back to stock.
+    Expired,
 }
 
 impl OrderStatus {
@@ -122,6 +390,7 @@ impl OrderStatus {
             OrderStatus::Ready => "ready",
             OrderStatus::Completed => "completed",
             OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Expired => "expired",
         }
     }
 
@@ -140,11 +409,186 @@ impl OrderStatus {
             "ready" => Ok(OrderStatus::Ready),
             "completed" => Ok(OrderStatus::Completed),
             "cancelled" => Ok(OrderStatus::Cancelled),
+            "expired" => Ok(OrderStatus::Expired),
             _ => anyhow::bail!("invalid order status: {value}"),
         }
     }
 }
 
+/// Who the order is for. Orders in a higher tier are served before any in a lower one.
+#[derive(
+    Serialize,
+    Deserialize,
+    ToSchema,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum PriorityTier {
+    Vip,
+    Staff,
+    /// Customers who cannot wait in line.
+    Accessibility,
+    #[default]
+    Normal,
+}
+
+impl PriorityTier {
+    pub const ALL: [PriorityTier; 4] = [
+        PriorityTier::Vip,
+        PriorityTier::Staff,
+        PriorityTier::Accessibility,
+        PriorityTier::Normal,
+    ];
+
+    /// 0 for the tier served first.
+    pub fn rank(self) -> u8 {
+        self as u8
+    }
+
+    /// Clamps ranks past the last tier to `Normal`.
+    pub fn from_rank(rank: u8) -> Self {
+        Self::ALL
+            .get(usize::from(rank))
+            .copied()
+            .unwrap_or(PriorityTier::Normal)
+    }
+
+    pub fn is_priority(self) -> bool {
+        self != PriorityTier::Normal
+    }
+
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            PriorityTier::Vip => "vip",
+            PriorityTier::Staff => "staff",
+            PriorityTier::Accessibility => "accessibility",
+            PriorityTier::Normal => "normal",
+        }
+    }
+
+    pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "vip" => Ok(PriorityTier::Vip),
+            "staff" => Ok(PriorityTier::Staff),
+            "accessibility" => Ok(PriorityTier::Accessibility),
+            "normal" => Ok(PriorityTier::Normal),
+            _ => anyhow::bail!("invalid priority tier: {value}"),
+        }
+    }
+}
+
+/// Promotes orders one tier for every interval they wait, so a stream of higher-tier orders
+/// cannot hold back a lower-tier one forever. Off unless an interval is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityAging {
+    pub promote_every_minutes: Option<i64>,
+}
+
+/// Pieces that went into stock at the same time.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StockLot {
+    pub flavor: Flavor,
+    pub quantity: usize,
+    pub produced_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum StockReason {
+    Dropped,
+    Burnt,
+    /// Past the freshness limit.
+    Stale,
+    /// Recount that corrected the stock in either direction.
+    Miscount,
+    Other,
+}
+
+impl StockReason {
+    pub const ALL: [StockReason; 5] = [
+        StockReason::Dropped,
+        StockReason::Burnt,
+        StockReason::Stale,
+        StockReason::Miscount,
+        StockReason::Other,
+    ];
+
+    /// Whether pieces removed for this reason count as waste.
+    pub fn is_waste(self) -> bool {
+        self != StockReason::Miscount
+    }
+
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            StockReason::Dropped => "dropped",
+            StockReason::Burnt => "burnt",
+            StockReason::Stale => "stale",
+            StockReason::Miscount => "miscount",
+            StockReason::Other => "other",
+        }
+    }
+
+    pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "dropped" => Ok(StockReason::Dropped),
+            "burnt" => Ok(StockReason::Burnt),
+            "stale" => Ok(StockReason::Stale),
+            "miscount" => Ok(StockReason::Miscount),
+            "other" => Ok(StockReason::Other),
+            _ => anyhow::bail!("invalid stock reason: {value}"),
+        }
+    }
+}
+
+/// Change to unallocated stock outside production and allocation.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StockAdjustment {
+    pub id: u32,
+    pub flavor: Flavor,
+    /// Pieces added (positive) or removed (negative).
+    pub delta: i64,
+    pub reason: StockReason,
+    pub note: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum PaymentMethod {
+    Cash,
+    QrCode,
+    Ticket,
+}
+
+impl PaymentMethod {
+    pub fn as_db_str(self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "cash",
+            PaymentMethod::QrCode => "qr_code",
+            PaymentMethod::Ticket => "ticket",
+        }
+    }
+
+    pub fn from_db_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "cash" => Ok(PaymentMethod::Cash),
+            "qr_code" => Ok(PaymentMethod::QrCode),
+            "ticket" => Ok(PaymentMethod::Ticket),
+            _ => anyhow::bail!("invalid payment method: {value}"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Notify {
     Discord { channel_id: u64, user_id: u64 },
@@ -0,0 +1,434 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::business_day::BusinessDay;
+use crate::domain::snapshot::{
+    Batch, Flavor, FlavorConfig, Item, MenuFlavor, Notify, Order, OrderStatus, OrderSystemSnapshot,
+    PricingRule, Station, StockAdjustment, StockLot,
+};
+
+/// Row-level change between two versions of the snapshot, for storage to apply instead of
+/// rewriting everything. Nothing is ever removed from the snapshot, so there are no deletions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotChange {
+    UpsertFlavor(MenuFlavor),
+    UpsertPricingRule(PricingRule),
+    UpsertStation(Station),
+    UpsertFlavorConfig {
+        flavor: Flavor,
+        config: FlavorConfig,
+    },
+    UpsertBusinessDay(BusinessDay),
+    UpsertBatch(Batch),
+    SetStock {
+        flavor: Flavor,
+        quantity: usize,
+    },
+    /// Lots of one flavor, oldest first.
+    ReplaceStockLots {
+        flavor: Flavor,
+        lots: Vec<StockLot>,
+    },
+    InsertStockAdjustment(StockAdjustment),
+    /// New order together with its items and notification targets.
+    InsertOrder(Order),
+    /// Only the status and its timestamps moved, as allocation and staff transitions do.
+    UpdateOrderStatus {
+        id: u32,
+        status: OrderStatus,
+        ready_at: Option<DateTime<Utc>>,
+        completed_at: Option<DateTime<Utc>>,
+        no_show_at: Option<DateTime<Utc>>,
+        expired_at: Option<DateTime<Utc>>,
+    },
+    /// Any other column of the order row changed.
+    UpdateOrder(Order),
+    SetOrderItems {
+        order_id: u32,
+        items: Vec<Item>,
+        handed_over: Vec<Item>,
+    },
+    SetOrderNotifications {
+        order_id: u32,
+        notify: HashSet<Notify>,
+    },
+}
+
+/// Changes that turn `before` into `after`, ordered so rows are written after the rows they
+/// refer to.
+pub fn changes_between(
+    before: &OrderSystemSnapshot,
+    after: &OrderSystemSnapshot,
+) -> Vec<SnapshotChange> {
+    let mut changes = Vec::new();
+
+    changes.extend(
+        changed(&before.menu, &after.menu, |entry| &entry.id)
+            .map(|entry| SnapshotChange::UpsertFlavor(entry.clone())),
+    );
+    changes.extend(
+        changed(&before.pricing_rules, &after.pricing_rules, |rule| {
+            &rule.code
+        })
+        .map(|rule| SnapshotChange::UpsertPricingRule(rule.clone())),
+    );
+    changes.extend(
+        changed(&before.stations, &after.stations, |station| &station.id)
+            .map(|station| SnapshotChange::UpsertStation(station.clone())),
+    );
+    let mut flavor_configs = after
+        .flavor_configs
+        .iter()
+        .filter(|(flavor, config)| before.flavor_configs.get(flavor) != Some(config))
+        .collect::<Vec<_>>();
+    flavor_configs.sort_by_key(|(flavor, _)| *flavor);
+    changes.extend(flavor_configs.into_iter().map(|(flavor, &config)| {
+        SnapshotChange::UpsertFlavorConfig {
+            flavor: flavor.clone(),
+            config,
+        }
+    }));
+    changes.extend(
+        changed(&before.business_days, &after.business_days, |day| &day.id)
+            .map(|day| SnapshotChange::UpsertBusinessDay(day.clone())),
+    );
+    changes.extend(
+        changed(&before.batches, &after.batches, |batch| &batch.id)
+            .map(|batch| SnapshotChange::UpsertBatch(batch.clone())),
+    );
+
+    let mut stocked_flavors = before
+        .unallocated_stock
+        .keys()
+        .chain(after.unallocated_stock.keys())
+        .collect::<Vec<_>>();
+    stocked_flavors.sort();
+    stocked_flavors.dedup();
+    for flavor in stocked_flavors {
+        if before.unallocated_stock.get(flavor) != after.unallocated_stock.get(flavor) {
+            changes.push(SnapshotChange::SetStock {
+                flavor: flavor.clone(),
+                quantity: after.stock_of(flavor),
+            });
+        }
+    }
+    let lots_before = lots_by_flavor(before);
+    let lots_after = lots_by_flavor(after);
+    let mut lot_flavors = lots_before
+        .keys()
+        .chain(lots_after.keys())
+        .copied()
+        .collect::<Vec<_>>();
+    lot_flavors.sort();
+    lot_flavors.dedup();
+    for flavor in lot_flavors {
+        if lots_before.get(flavor) != lots_after.get(flavor) {
+            changes.push(SnapshotChange::ReplaceStockLots {
+                flavor: flavor.clone(),
+                lots: lots_after
+                    .get(flavor)
+                    .map(|lots| lots.iter().map(|&lot| lot.clone()).collect())
+                    .unwrap_or_default(),
+            });
+        }
+    }
+    changes.extend(
+        changed(
+            &before.stock_adjustments,
+            &after.stock_adjustments,
+            |adjustment| &adjustment.id,
+        )
+        .map(|adjustment| SnapshotChange::InsertStockAdjustment(adjustment.clone())),
+    );
+
+    let orders_before = before
+        .orders
+        .iter()
+        .map(|order| (order.id, order))
+        .collect::<HashMap<_, _>>();
+    for order in &after.orders {
+        let Some(&previous) = orders_before.get(&order.id) else {
+            changes.push(SnapshotChange::InsertOrder(order.clone()));
+            continue;
+        };
+        if previous == order {
+            continue;
+        }
+        if row_without_status(previous) != row_without_status(order) {
+            changes.push(SnapshotChange::UpdateOrder(order.clone()));
+        } else if status_of(previous) != status_of(order) {
+            changes.push(SnapshotChange::UpdateOrderStatus {
+                id: order.id,
+                status: order.status,
+                ready_at: order.ready_at,
+                completed_at: order.completed_at,
+                no_show_at: order.no_show_at,
+                expired_at: order.expired_at,
+            });
+        }
+        if previous.items != order.items || previous.handed_over != order.handed_over {
+            changes.push(SnapshotChange::SetOrderItems {
+                order_id: order.id,
+                items: order.items.clone(),
+                handed_over: order.handed_over.clone(),
+            });
+        }
+        if previous.notify != order.notify {
+            changes.push(SnapshotChange::SetOrderNotifications {
+                order_id: order.id,
+                notify: order.notify.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Applies `changes` to an in-memory snapshot the way storage applies them to its rows.
+pub fn apply_changes(snapshot: &mut OrderSystemSnapshot, changes: &[SnapshotChange]) {
+    for change in changes {
+        match change {
+            SnapshotChange::UpsertFlavor(entry) => {
+                upsert(&mut snapshot.menu, entry.clone(), |entry| entry.id.clone());
+                snapshot
+                    .menu
+                    .sort_by(|a, b| (a.sort_order, &a.id).cmp(&(b.sort_order, &b.id)));
+            }
+            SnapshotChange::UpsertPricingRule(rule) => {
+                upsert(&mut snapshot.pricing_rules, rule.clone(), |rule| {
+                    rule.code.clone()
+                });
+            }
+            SnapshotChange::UpsertStation(station) => {
+                upsert(&mut snapshot.stations, station.clone(), |station| {
+                    station.id
+                });
+            }
+            SnapshotChange::UpsertFlavorConfig { flavor, config } => {
+                snapshot.flavor_configs.insert(flavor.clone(), *config);
+            }
+            SnapshotChange::UpsertBusinessDay(day) => {
+                upsert(&mut snapshot.business_days, day.clone(), |day| day.id);
+            }
+            SnapshotChange::UpsertBatch(batch) => {
+                upsert(&mut snapshot.batches, batch.clone(), |batch| batch.id);
+            }
+            SnapshotChange::SetStock { flavor, quantity } => {
+                snapshot.unallocated_stock.insert(flavor.clone(), *quantity);
+            }
+            SnapshotChange::ReplaceStockLots { flavor, lots } => {
+                snapshot.stock_lots.retain(|lot| &lot.flavor != flavor);
+                snapshot.stock_lots.extend(lots.iter().cloned());
+                snapshot.stock_lots.sort_by(|a, b| a.flavor.cmp(&b.flavor));
+            }
+            SnapshotChange::InsertStockAdjustment(adjustment) => {
+                snapshot.stock_adjustments.push(adjustment.clone());
+            }
+            SnapshotChange::InsertOrder(order) => {
+                snapshot.last_order_id = snapshot.last_order_id.max(order.id);
+                upsert(&mut snapshot.orders, order.clone(), |order| order.id);
+            }
+            SnapshotChange::UpdateOrderStatus {
+                id,
+                status,
+                ready_at,
+                completed_at,
+                no_show_at,
+                expired_at,
+            } => {
+                if let Some(order) = snapshot.orders.iter_mut().find(|order| order.id == *id) {
+                    order.status = *status;
+                    order.ready_at = *ready_at;
+                    order.completed_at = *completed_at;
+                    order.no_show_at = *no_show_at;
+                    order.expired_at = *expired_at;
+                }
+            }
+            SnapshotChange::UpdateOrder(updated) => {
+                if let Some(order) = snapshot
+                    .orders
+                    .iter_mut()
+                    .find(|order| order.id == updated.id)
+                {
+                    *order = Order {
+                        items: std::mem::take(&mut order.items),
+                        handed_over: std::mem::take(&mut order.handed_over),
+                        notify: std::mem::take(&mut order.notify),
+                        ..updated.clone()
+                    };
+                }
+            }
+            SnapshotChange::SetOrderItems {
+                order_id,
+                items,
+                handed_over,
+            } => {
+                if let Some(order) = snapshot
+                    .orders
+                    .iter_mut()
+                    .find(|order| order.id == *order_id)
+                {
+                    order.items = items.clone();
+                    order.handed_over = handed_over.clone();
+                }
+            }
+            SnapshotChange::SetOrderNotifications { order_id, notify } => {
+                if let Some(order) = snapshot
+                    .orders
+                    .iter_mut()
+                    .find(|order| order.id == *order_id)
+                {
+                    order.notify = notify.clone();
+                }
+            }
+        }
+    }
+}
+
+// Entries of `after` that are new or differ from the entry with the same key in `before`.
+fn changed<'a, T: PartialEq, K: Eq + Hash + 'a>(
+    before: &'a [T],
+    after: &'a [T],
+    key: impl Fn(&T) -> &K,
+) -> impl Iterator<Item = &'a T> {
+    let before = before
+        .iter()
+        .map(|entry| (key(entry), entry))
+        .collect::<HashMap<_, _>>();
+    after
+        .iter()
+        .filter(move |entry| before.get(key(entry)) != Some(entry))
+}
+
+fn upsert<T, K: PartialEq>(entries: &mut Vec<T>, entry: T, key: impl Fn(&T) -> K) {
+    match entries
+        .iter_mut()
+        .find(|existing| key(existing) == key(&entry))
+    {
+        Some(existing) => *existing = entry,
+        None => entries.push(entry),
+    }
+}
+
+fn lots_by_flavor(snapshot: &OrderSystemSnapshot) -> HashMap<&Flavor, Vec<&StockLot>> {
+    let mut lots = HashMap::<_, Vec<_>>::new();
+    for lot in &snapshot.stock_lots {
+        lots.entry(&lot.flavor).or_default().push(lot);
+    }
+    lots
+}
+
+// The order row apart from its status columns, items and notification targets.
+fn row_without_status(order: &Order) -> Order {
+    Order {
+        items: Vec::new(),
+        handed_over: Vec::new(),
+        notify: HashSet::new(),
+        status: OrderStatus::default(),
+        ready_at: None,
+        completed_at: None,
+        no_show_at: None,
+        expired_at: None,
+        ..order.clone()
+    }
+}
+
+type StatusColumns = (
+    OrderStatus,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+);
+
+fn status_of(order: &Order) -> StatusColumns {
+    (
+        order.status,
+        order.ready_at,
+        order.completed_at,
+        order.no_show_at,
+        order.expired_at,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::domain::snapshot::{Notify, Order, OrderStatus, OrderSystemSnapshot, StockLot};
+    use crate::test_fixtures::{tsubuan, waiting_order};
+
+    use super::{SnapshotChange, apply_changes, changes_between};
+
+    fn order(id: u32) -> Order {
+        Order {
+            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap(),
+            total_price: 400,
+            ..waiting_order(id, 2)
+        }
+    }
+
+    #[test]
+    fn only_touched_rows_become_changes() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 2, 0, 0).unwrap();
+        let tsubuan = tsubuan();
+        let mut before = OrderSystemSnapshot {
+            orders: (1..=3).map(order).collect(),
+            ..Default::default()
+        };
+        before.unallocated_stock.insert(tsubuan.clone(), 2);
+        before.stock_lots.push(StockLot {
+            flavor: tsubuan.clone(),
+            quantity: 2,
+            produced_at: now,
+        });
+
+        let mut after = before.clone();
+        after.orders[0].status = OrderStatus::Ready;
+        after.orders[0].ready_at = Some(now);
+        after.orders[1].total_price = 300;
+        after.orders[1].notify.insert(Notify::Line {
+            user_id: "line-user".to_owned(),
+        });
+        after.orders.push(order(4));
+        after.unallocated_stock.insert(tsubuan.clone(), 0);
+        after.stock_lots.clear();
+
+        let changes = changes_between(&before, &after);
+
+        assert_eq!(
+            changes,
+            vec![
+                SnapshotChange::SetStock {
+                    flavor: tsubuan.clone(),
+                    quantity: 0,
+                },
+                SnapshotChange::ReplaceStockLots {
+                    flavor: tsubuan,
+                    lots: Vec::new(),
+                },
+                SnapshotChange::UpdateOrderStatus {
+                    id: 1,
+                    status: OrderStatus::Ready,
+                    ready_at: Some(now),
+                    completed_at: None,
+                    no_show_at: None,
+                    expired_at: None,
+                },
+                SnapshotChange::UpdateOrder(after.orders[1].clone()),
+                SnapshotChange::SetOrderNotifications {
+                    order_id: 2,
+                    notify: after.orders[1].notify.clone(),
+                },
+                SnapshotChange::InsertOrder(order(4)),
+            ]
+        );
+
+        let mut applied = before;
+        apply_changes(&mut applied, &changes);
+        after.last_order_id = 4;
+        assert_eq!(applied, after);
+    }
+}
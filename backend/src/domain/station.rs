@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::snapshot::{Batch, Flavor, OrderSystemSnapshot};
+
+/// Plates a batch competes for: a configured station, or the single plate of a flavor without one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PlateGroup {
+    Station(u32),
+    Dedicated(Flavor),
+}
+
+/// The flavor's plate group and its plate count, or `None` when the flavor cannot be grilled.
+pub fn plate_group(snapshot: &OrderSystemSnapshot, flavor: &Flavor) -> Option<(PlateGroup, usize)> {
+    let config = snapshot.flavor_configs.get(flavor)?;
+    match config.station_id {
+        Some(id) => {
+            let station = snapshot.station(id)?;
+            Some((PlateGroup::Station(id), station.plate_count as usize))
+        }
+        None => Some((PlateGroup::Dedicated(flavor.clone()), 1)),
+    }
+}
+
+/// Batches needed to grill `needed` more pieces of the flavor, or `None` when it cannot be grilled.
+pub fn batches_needed(
+    snapshot: &OrderSystemSnapshot,
+    flavor: &Flavor,
+    needed: usize,
+) -> Option<usize> {
+    if needed == 0 {
+        return Some(0);
+    }
+    let config = snapshot.flavor_configs.get(flavor)?;
+    if config.quantity_per_batch == 0 {
+        return None;
+    }
+    Some(needed.div_ceil(config.quantity_per_batch as usize))
+}
+
+/// Minutes until the batch comes off the plate; zero once it is overdue.
+pub fn remaining_cooking_minutes(
+    snapshot: &OrderSystemSnapshot,
+    batch: &Batch,
+    now: DateTime<Utc>,
+) -> i64 {
+    let cooking_time_minutes = snapshot
+        .flavor_configs
+        .get(&batch.flavor)
+        .map_or(0, |config| config.cooking_time_minutes as i64);
+    let elapsed_minutes = (now - batch.started_at).num_minutes();
+    (cooking_time_minutes - elapsed_minutes).max(0)
+}
+
+/// Simulates grilling batches in the given order, each on the plate of its group that frees up first.
+/// Plates start out occupied by the batches already on the grill.
+pub struct PlateSchedule<'a> {
+    snapshot: &'a OrderSystemSnapshot,
+    free_at: HashMap<PlateGroup, BinaryHeap<Reverse<i64>>>,
+}
+
+impl<'a> PlateSchedule<'a> {
+    pub fn new(snapshot: &'a OrderSystemSnapshot, now: DateTime<Utc>) -> Self {
+        let mut schedule = Self {
+            snapshot,
+            free_at: HashMap::new(),
+        };
+        for batch in snapshot.active_batches() {
+            let remaining = remaining_cooking_minutes(snapshot, batch, now);
+            schedule.occupy_plate(&batch.flavor, remaining);
+        }
+        schedule
+    }
+
+    /// Schedules one batch of the flavor and returns the minute it comes off the plate.
+    pub fn grill_batch(&mut self, flavor: &Flavor) -> Option<i64> {
+        let config = self.snapshot.flavor_configs.get(flavor)?;
+        self.occupy_plate(flavor, config.cooking_time_minutes as i64)
+    }
+
+    fn occupy_plate(&mut self, flavor: &Flavor, minutes: i64) -> Option<i64> {
+        let (group, plate_count) = plate_group(self.snapshot, flavor)?;
+        let plates = self
+            .free_at
+            .entry(group)
+            .or_insert_with(|| (0..plate_count).map(|_| Reverse(0)).collect());
+        let Reverse(start) = plates.pop()?;
+        let done = start + minutes;
+        plates.push(Reverse(done));
+        Some(done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{Batch, Flavor, FlavorConfig, OrderSystemSnapshot, Station};
+    use crate::test_fixtures::{flavor_config, menu_flavor};
+
+    use super::PlateSchedule;
+
+    fn snapshot_with_shared_station(plate_count: u32) -> OrderSystemSnapshot {
+        let mut snapshot = OrderSystemSnapshot {
+            stations: vec![Station {
+                id: 1,
+                name: "鉄板A".to_owned(),
+                plate_count,
+            }],
+            ..Default::default()
+        };
+        for (id, sort_order) in [("tsubuan", 1), ("custard", 2)] {
+            snapshot.add_flavor(
+                menu_flavor(id, sort_order),
+                FlavorConfig {
+                    station_id: Some(1),
+                    ..flavor_config()
+                },
+            );
+        }
+        snapshot
+    }
+
+    #[test]
+    fn shared_plates_run_batches_of_different_flavors_in_parallel() {
+        let snapshot = snapshot_with_shared_station(2);
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        let mut schedule = PlateSchedule::new(&snapshot, now);
+
+        let done = [
+            schedule.grill_batch(&Flavor::new("tsubuan")),
+            schedule.grill_batch(&Flavor::new("custard")),
+            schedule.grill_batch(&Flavor::new("tsubuan")),
+        ];
+
+        assert_eq!(done, [Some(15), Some(15), Some(30)]);
+    }
+
+    #[test]
+    fn batches_on_the_grill_keep_their_plates_busy() {
+        let mut snapshot = snapshot_with_shared_station(2);
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        snapshot.batches.push(Batch {
+            id: 1,
+            flavor: Flavor::new("custard"),
+            quantity: 9,
+            started_at: now - Duration::minutes(10),
+            finished_at: None,
+        });
+        let mut schedule = PlateSchedule::new(&snapshot, now);
+
+        let done = [
+            schedule.grill_batch(&Flavor::new("tsubuan")),
+            schedule.grill_batch(&Flavor::new("tsubuan")),
+        ];
+
+        assert_eq!(done, [Some(15), Some(20)]);
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::business_day::business_date_at;
+use crate::domain::snapshot::{Flavor, OrderSystemSnapshot, StockLot, StockReason};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StockSettings {
+    /// Minutes a piece may wait in stock before it is considered stale.
+    pub freshness_minutes: i64,
+    /// Discard stale pieces automatically instead of only flagging them.
+    pub auto_discard: bool,
+}
+
+impl Default for StockSettings {
+    fn default() -> Self {
+        Self {
+            freshness_minutes: 60,
+            auto_discard: false,
+        }
+    }
+}
+
+/// Puts pieces into unallocated stock as a lot produced at `produced_at`.
+pub fn add_stock(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: &Flavor,
+    quantity: usize,
+    produced_at: DateTime<Utc>,
+) {
+    if quantity == 0 {
+        return;
+    }
+    *snapshot
+        .unallocated_stock
+        .entry(flavor.clone())
+        .or_insert(0) += quantity;
+    snapshot.stock_lots.push(StockLot {
+        flavor: flavor.clone(),
+        quantity,
+        produced_at,
+    });
+}
+
+/// Makes the lots add up to the unallocated stock again.
+/// Pieces taken out of stock come from the oldest lots; pieces of unknown age are dated `now`.
+pub fn reconcile_lots(snapshot: &mut OrderSystemSnapshot, now: DateTime<Utc>) {
+    snapshot
+        .stock_lots
+        .sort_by(|a, b| (&a.flavor, a.produced_at).cmp(&(&b.flavor, b.produced_at)));
+
+    let mut in_lots = HashMap::<Flavor, usize>::new();
+    for lot in &snapshot.stock_lots {
+        *in_lots.entry(lot.flavor.clone()).or_insert(0) += lot.quantity;
+    }
+
+    for lot in &mut snapshot.stock_lots {
+        let stock = snapshot
+            .unallocated_stock
+            .get(&lot.flavor)
+            .copied()
+            .unwrap_or(0);
+        let lotted = in_lots.get_mut(&lot.flavor).expect("counted lot flavor");
+        let excess = lotted.saturating_sub(stock).min(lot.quantity);
+        lot.quantity -= excess;
+        *lotted -= excess;
+    }
+    snapshot.stock_lots.retain(|lot| lot.quantity > 0);
+
+    for (flavor, &stock) in &snapshot.unallocated_stock {
+        let lotted = in_lots.get(flavor).copied().unwrap_or(0);
+        if stock > lotted {
+            snapshot.stock_lots.push(StockLot {
+                flavor: flavor.clone(),
+                quantity: stock - lotted,
+                produced_at: now,
+            });
+        }
+    }
+}
+
+pub fn is_expired(lot: &StockLot, now: DateTime<Utc>, settings: StockSettings) -> bool {
+    now - lot.produced_at >= Duration::minutes(settings.freshness_minutes)
+}
+
+/// Stale pieces per flavor.
+pub fn expired_quantities(
+    snapshot: &OrderSystemSnapshot,
+    now: DateTime<Utc>,
+    settings: StockSettings,
+) -> Vec<(Flavor, usize)> {
+    let mut expired = Vec::<(Flavor, usize)>::new();
+    for lot in snapshot
+        .stock_lots
+        .iter()
+        .filter(|lot| is_expired(lot, now, settings))
+    {
+        match expired.iter_mut().find(|(flavor, _)| flavor == &lot.flavor) {
+            Some((_, quantity)) => *quantity += lot.quantity,
+            None => expired.push((lot.flavor.clone(), lot.quantity)),
+        }
+    }
+    expired
+}
+
+/// Pieces thrown away on one store day.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WasteReport {
+    pub date: NaiveDate,
+    pub total_quantity: usize,
+    /// Menu value of the wasted pieces in yen.
+    pub total_value: u32,
+    /// Flavors with waste, in menu order.
+    pub by_flavor: Vec<FlavorWaste>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FlavorWaste {
+    pub flavor: Flavor,
+    pub display_name: String,
+    pub quantity: usize,
+    pub value: u32,
+    pub by_reason: Vec<ReasonWaste>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReasonWaste {
+    pub reason: StockReason,
+    pub quantity: usize,
+}
+
+pub fn waste_report(snapshot: &OrderSystemSnapshot, date: NaiveDate) -> WasteReport {
+    let wasted = snapshot
+        .stock_adjustments
+        .iter()
+        .filter(|adjustment| {
+            adjustment.reason.is_waste()
+                && adjustment.delta < 0
+                && business_date_at(snapshot, adjustment.recorded_at) == date
+        })
+        .collect::<Vec<_>>();
+
+    let by_flavor = snapshot
+        .menu
+        .iter()
+        .filter_map(|entry| {
+            let by_reason = StockReason::ALL
+                .into_iter()
+                .filter_map(|reason| {
+                    let quantity = wasted
+                        .iter()
+                        .filter(|adjustment| {
+                            adjustment.flavor == entry.id && adjustment.reason == reason
+                        })
+                        .map(|adjustment| adjustment.delta.unsigned_abs() as usize)
+                        .sum::<usize>();
+                    (quantity > 0).then_some(ReasonWaste { reason, quantity })
+                })
+                .collect::<Vec<_>>();
+            let quantity = by_reason.iter().map(|waste| waste.quantity).sum::<usize>();
+            if quantity == 0 {
+                return None;
+            }
+            Some(FlavorWaste {
+                flavor: entry.id.clone(),
+                display_name: entry.display_name.clone(),
+                quantity,
+                value: entry.unit_price * quantity as u32,
+                by_reason,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    WasteReport {
+        date,
+        total_quantity: by_flavor.iter().map(|waste| waste.quantity).sum(),
+        total_value: by_flavor.iter().map(|waste| waste.value).sum(),
+        by_flavor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{OrderSystemSnapshot, StockLot};
+    use crate::test_fixtures::tsubuan;
+
+    use super::{StockSettings, add_stock, expired_quantities, reconcile_lots};
+
+    #[test]
+    fn allocated_pieces_come_from_the_oldest_lot() {
+        let mut snapshot = OrderSystemSnapshot::default();
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 3, 0, 0).unwrap();
+        add_stock(&mut snapshot, &tsubuan(), 4, now - Duration::minutes(90));
+        add_stock(&mut snapshot, &tsubuan(), 5, now - Duration::minutes(10));
+
+        // An order takes 6 pieces.
+        snapshot.unallocated_stock.insert(tsubuan(), 3);
+        reconcile_lots(&mut snapshot, now);
+
+        assert_eq!(
+            snapshot.stock_lots,
+            vec![StockLot {
+                flavor: tsubuan(),
+                quantity: 3,
+                produced_at: now - Duration::minutes(10),
+            }]
+        );
+        assert!(expired_quantities(&snapshot, now, StockSettings::default()).is_empty());
+    }
+
+    #[test]
+    fn stock_without_lots_is_dated_now() {
+        let mut snapshot = OrderSystemSnapshot::default();
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 3, 0, 0).unwrap();
+        snapshot.unallocated_stock.insert(tsubuan(), 2);
+
+        reconcile_lots(&mut snapshot, now);
+
+        assert_eq!(snapshot.stock_lots[0].quantity, 2);
+        assert_eq!(
+            expired_quantities(
+                &snapshot,
+                now + Duration::minutes(60),
+                StockSettings::default()
+            ),
+            vec![(tsubuan(), 2)]
+        );
+    }
+}
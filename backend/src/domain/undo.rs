@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::order_event::Actor;
+use crate::domain::snapshot::{Flavor, FlavorConfig, Item, PriorityTier};
+
+/// Staff action that can be taken back, with what it takes to revert it.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum UndoAction {
+    CreateOrder {
+        order_id: u32,
+    },
+    CompleteOrder {
+        order_id: u32,
+    },
+    CancelOrder {
+        order_id: u32,
+    },
+    UpdatePriority {
+        order_id: u32,
+        previous: PriorityTier,
+    },
+    ReportProduction {
+        items: Vec<Item>,
+    },
+    /// Undoing restores only the fields `applied` changed from `previous`.
+    SetFlavorConfig {
+        flavor: Flavor,
+        previous: FlavorConfig,
+        applied: FlavorConfig,
+    },
+    FinishBatch {
+        batch_id: u32,
+    },
+    /// The pieces are with the customer, so undoing refuses this rather than reaching past it.
+    HandOverItems {
+        order_id: u32,
+    },
+}
+
+impl UndoAction {
+    /// Order the action was taken on, if any.
+    pub fn order_id(&self) -> Option<u32> {
+        match self {
+            UndoAction::CreateOrder { order_id }
+            | UndoAction::CompleteOrder { order_id }
+            | UndoAction::CancelOrder { order_id }
+            | UndoAction::UpdatePriority { order_id, .. }
+            | UndoAction::HandOverItems { order_id } => Some(*order_id),
+            UndoAction::ReportProduction { .. }
+            | UndoAction::SetFlavorConfig { .. }
+            | UndoAction::FinishBatch { .. } => None,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            UndoAction::CreateOrder { order_id } => format!("注文 {order_id} の作成"),
+            UndoAction::CompleteOrder { order_id } => format!("注文 {order_id} の完了"),
+            UndoAction::CancelOrder { order_id } => format!("注文 {order_id} のキャンセル"),
+            UndoAction::UpdatePriority { order_id, .. } => {
+                format!("注文 {order_id} の優先度変更")
+            }
+            UndoAction::ReportProduction { items } => format!(
+                "生産報告 ({})",
+                items
+                    .iter()
+                    .map(|item| format!("{} x{}", item.flavor, item.quantity))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            UndoAction::SetFlavorConfig { flavor, .. } => format!("{flavor} の設定変更"),
+            UndoAction::FinishBatch { batch_id } => format!("バッチ {batch_id} の焼き上がり"),
+            UndoAction::HandOverItems { order_id } => format!("注文 {order_id} の受け渡し"),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoEntry {
+    pub id: u32,
+    pub action: UndoAction,
+    pub actor: Actor,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Recent staff actions. Once full, the oldest fall off.
+#[derive(Debug)]
+pub struct UndoStack {
+    entries: VecDeque<UndoEntry>,
+    capacity: usize,
+    last_id: u32,
+}
+
+impl UndoStack {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            last_id: 0,
+        }
+    }
+
+    pub fn push(&mut self, action: UndoAction, actor: Actor, now: DateTime<Utc>) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.last_id += 1;
+        self.entries.push_back(UndoEntry {
+            id: self.last_id,
+            action,
+            actor,
+            recorded_at: now,
+        });
+    }
+
+    /// Newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &UndoEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// The entry with `id`, or the newest one without it.
+    pub fn get(&self, id: Option<u32>) -> Option<&UndoEntry> {
+        match id {
+            Some(id) => self.entries.iter().find(|entry| entry.id == id),
+            None => self.entries.back(),
+        }
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        self.entries.retain(|entry| entry.id != id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::domain::order_event::Actor;
+
+    use super::{UndoAction, UndoStack};
+
+    #[test]
+    fn stack_keeps_the_newest_entries() {
+        let mut stack = UndoStack::new(2);
+        for order_id in 1..=3 {
+            stack.push(
+                UndoAction::CompleteOrder { order_id },
+                Actor::Staff,
+                Utc::now(),
+            );
+        }
+
+        let ids: Vec<_> = stack.entries().map(|entry| entry.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+        assert_eq!(stack.get(None).map(|entry| entry.id), Some(3));
+        assert!(stack.get(Some(1)).is_none());
+
+        stack.remove(3);
+        assert_eq!(stack.get(None).map(|entry| entry.id), Some(2));
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::domain::availability::store_time;
+use crate::domain::snapshot::{Flavor, Order, OrderSystemSnapshot};
+use crate::domain::wait_time;
+
+// Fewer ready orders than this in the window say more about luck than about the grill.
+const MIN_SAMPLE_ORDERS: usize = 3;
+
+/// Estimate shown to customers; the other one is still reported for comparison.
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WaitTimeEstimator {
+    /// Batch arithmetic over stock, the grill and the plates.
+    #[default]
+    BatchModel,
+    /// Average of the batch model and recent throughput, when there is enough history.
+    Blended,
+}
+
+impl WaitTimeEstimator {
+    pub fn select(self, batch_model: Option<i64>, history: Option<i64>) -> Option<i64> {
+        match (self, batch_model, history) {
+            (Self::Blended, Some(batch_model), Some(history)) => {
+                Some((batch_model + history + 1) / 2)
+            }
+            _ => batch_model,
+        }
+    }
+}
+
+/// Expected wait with the range it is likely to fall in.
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WaitEstimate {
+    pub minutes: i64,
+    pub min_minutes: i64,
+    pub max_minutes: i64,
+    pub expected_ready_at: DateTime<Utc>,
+    pub earliest_ready_at: DateTime<Utc>,
+    pub latest_ready_at: DateTime<Utc>,
+}
+
+impl WaitEstimate {
+    /// Spans both estimators when they are known, otherwise a margin around the one estimate.
+    pub fn new(
+        estimator: WaitTimeEstimator,
+        batch_model: Option<i64>,
+        history: Option<i64>,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let minutes = estimator.select(batch_model, history)?;
+        let (min_minutes, max_minutes) = match (batch_model, history) {
+            (Some(batch_model), Some(history)) => (
+                batch_model.min(history).min(minutes),
+                batch_model.max(history).max(minutes),
+            ),
+            _ => {
+                let margin = if minutes > 0 { (minutes / 5).max(1) } else { 0 };
+                ((minutes - margin).max(0), minutes + margin)
+            }
+        };
+        Some(Self {
+            minutes,
+            min_minutes,
+            max_minutes,
+            expected_ready_at: now + Duration::minutes(minutes),
+            earliest_ready_at: now + Duration::minutes(min_minutes),
+            latest_ready_at: now + Duration::minutes(max_minutes),
+        })
+    }
+
+    /// e.g. `約15分 (12〜18分, 10:45頃)`
+    pub fn describe(&self) -> String {
+        if self.max_minutes == 0 {
+            return "すぐに提供できます".to_owned();
+        }
+        let range = if self.min_minutes == self.max_minutes {
+            String::new()
+        } else {
+            format!("{}〜{}分, ", self.min_minutes, self.max_minutes)
+        };
+        format!(
+            "約{}分 ({}{}頃)",
+            self.minutes,
+            range,
+            store_time(self.expected_ready_at).format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for WaitTimeEstimator {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "batch" => Ok(Self::BatchModel),
+            "blended" => Ok(Self::Blended),
+            other => Err(format!("unknown wait time estimator `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTimeSettings {
+    pub estimator: WaitTimeEstimator,
+    /// How far back throughput is observed.
+    pub history_window_minutes: i64,
+}
+
+impl Default for WaitTimeSettings {
+    fn default() -> Self {
+        Self {
+            estimator: WaitTimeEstimator::default(),
+            history_window_minutes: 60,
+        }
+    }
+}
+
+/// Pieces per flavor that actually became ready over a rolling window.
+/// Slow batches, staff shortages and rushes all show up in the observed pace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThroughputHistory {
+    pieces_per_minute: HashMap<Flavor, f64>,
+}
+
+impl ThroughputHistory {
+    pub fn observe(snapshot: &OrderSystemSnapshot, now: DateTime<Utc>, window: Duration) -> Self {
+        let window_start = now - window;
+        let recent = snapshot
+            .orders
+            .iter()
+            .filter(|order| {
+                order
+                    .ready_at
+                    .is_some_and(|ready_at| window_start <= ready_at && ready_at <= now)
+            })
+            .collect::<Vec<_>>();
+        // Right after opening the window reaches back before the first order.
+        let observed_from = recent
+            .iter()
+            .map(|order| order.ordered_at)
+            .min()
+            .map_or(window_start, |first| first.max(window_start));
+        let observed_minutes = (now - observed_from).num_minutes().max(1) as f64;
+
+        let mut samples = HashMap::<&Flavor, (usize, usize)>::new();
+        for item in recent.iter().flat_map(|order| &order.items) {
+            let (orders, pieces) = samples.entry(&item.flavor).or_default();
+            *orders += 1;
+            *pieces += item.quantity;
+        }
+
+        Self {
+            pieces_per_minute: samples
+                .into_iter()
+                .filter(|&(_, (orders, _))| orders >= MIN_SAMPLE_ORDERS)
+                .map(|(flavor, (_, pieces))| (flavor.clone(), pieces as f64 / observed_minutes))
+                .collect(),
+        }
+    }
+
+    /// Minutes until `pieces` more pieces are ready at the recent pace, or `None` without
+    /// enough history for the flavor.
+    pub fn minutes_for(&self, flavor: &Flavor, pieces: usize) -> Option<i64> {
+        if pieces == 0 {
+            return Some(0);
+        }
+        let rate = self.pieces_per_minute.get(flavor)?;
+        Some((pieces as f64 / rate).ceil() as i64)
+    }
+}
+
+pub fn estimate_order_wait_minutes(
+    snapshot: &OrderSystemSnapshot,
+    order: &Order,
+    history: &ThroughputHistory,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    if !wait_time::is_queued(order, now) {
+        return None;
+    }
+    let aging = snapshot.priority_aging;
+    let is_ahead = |other: &Order| {
+        wait_time::order_priority_key(other, aging, now)
+            <= wait_time::order_priority_key(order, aging, now)
+    };
+    let orders_ahead = snapshot
+        .orders
+        .iter()
+        .filter(|other| wait_time::is_queued(other, now) && is_ahead(other))
+        .collect();
+    let held = wait_time::held_pre_orders(snapshot, now).filter(|other| is_ahead(other));
+
+    wait_time::with_reserved_production(orders_ahead, held, aging, now, |orders_ahead| {
+        let demand = queued_demand(orders_ahead.iter().copied());
+        order
+            .outstanding()
+            .map(|(flavor, _)| {
+                let demanded = demand.get(flavor).copied().unwrap_or(0);
+                history.minutes_for(flavor, demanded.saturating_sub(snapshot.stock_of(flavor)))
+            })
+            .max()
+            .unwrap_or(Some(0))
+    })
+}
+
+/// Wait time for one more piece of each flavor on sale at the recent pace, in menu order.
+pub fn estimate_current_wait_times(
+    snapshot: &OrderSystemSnapshot,
+    history: &ThroughputHistory,
+    now: DateTime<Utc>,
+) -> Vec<(Flavor, Option<i64>)> {
+    let queued_orders = snapshot
+        .orders
+        .iter()
+        .filter(|order| wait_time::is_queued(order, now))
+        .collect::<Vec<_>>();
+
+    snapshot
+        .active_flavors()
+        .map(|entry| {
+            let wait_minutes = wait_time::with_reserved_production(
+                queued_orders.clone(),
+                wait_time::held_pre_orders(snapshot, now),
+                snapshot.priority_aging,
+                now,
+                |queued_orders| {
+                    let demand = queued_demand(queued_orders.iter().copied());
+                    let demanded = demand.get(&entry.id).copied().unwrap_or(0) + 1;
+                    history.minutes_for(
+                        &entry.id,
+                        demanded.saturating_sub(snapshot.stock_of(&entry.id)),
+                    )
+                },
+            );
+            (entry.id.clone(), wait_minutes)
+        })
+        .collect()
+}
+
+fn queued_demand<'a>(orders: impl Iterator<Item = &'a Order>) -> HashMap<&'a Flavor, usize> {
+    let mut demand = HashMap::new();
+    for (flavor, quantity) in orders.flat_map(|order| order.outstanding()) {
+        *demand.entry(flavor).or_insert(0) += quantity;
+    }
+    demand
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{Order, OrderStatus};
+    use crate::test_fixtures::{snapshot_with_orders, waiting_order};
+
+    use super::{ThroughputHistory, WaitEstimate, WaitTimeEstimator, estimate_order_wait_minutes};
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 3, 0, 0).unwrap()
+    }
+
+    fn order(id: u32, quantity: usize, ordered_minutes_ago: i64) -> Order {
+        Order {
+            ordered_at: now() - Duration::minutes(ordered_minutes_ago),
+            ..waiting_order(id, quantity)
+        }
+    }
+
+    fn completed_order(id: u32, quantity: usize, ready_minutes_ago: i64) -> Order {
+        Order {
+            status: OrderStatus::Completed,
+            ready_at: Some(now() - Duration::minutes(ready_minutes_ago)),
+            ..order(id, quantity, ready_minutes_ago + 10)
+        }
+    }
+
+    #[test]
+    fn waiting_order_is_estimated_from_recent_throughput() {
+        // 30 pieces became ready over the last hour: half a piece per minute.
+        let snapshot = snapshot_with_orders(vec![
+            completed_order(1, 10, 50),
+            completed_order(2, 10, 30),
+            completed_order(3, 10, 10),
+            completed_order(4, 10, 90),
+            order(5, 4, 5),
+            order(6, 2, 1),
+        ]);
+        let history = ThroughputHistory::observe(&snapshot, now(), Duration::minutes(60));
+
+        assert_eq!(
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[5], &history, now()),
+            Some(12)
+        );
+    }
+
+    #[test]
+    fn too_little_history_falls_back_to_batch_model() {
+        let snapshot = snapshot_with_orders(vec![completed_order(1, 10, 10), order(2, 4, 5)]);
+        let history = ThroughputHistory::observe(&snapshot, now(), Duration::minutes(60));
+
+        let from_history =
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[1], &history, now());
+
+        assert_eq!(from_history, None);
+        assert_eq!(
+            WaitTimeEstimator::Blended.select(Some(15), from_history),
+            Some(15)
+        );
+        assert_eq!(
+            WaitTimeEstimator::Blended.select(Some(15), Some(24)),
+            Some(20)
+        );
+    }
+
+    #[test]
+    fn estimate_range_spans_both_estimators() {
+        let estimate = WaitEstimate::new(WaitTimeEstimator::BatchModel, Some(15), Some(24), now())
+            .expect("batch model estimate");
+
+        assert_eq!(
+            (estimate.minutes, estimate.min_minutes, estimate.max_minutes),
+            (15, 15, 24)
+        );
+        // 12:15 JST
+        assert_eq!(estimate.describe(), "約15分 (15〜24分, 12:15頃)");
+
+        let estimate = WaitEstimate::new(WaitTimeEstimator::Blended, Some(15), None, now())
+            .expect("batch model estimate");
+        assert_eq!((estimate.min_minutes, estimate.max_minutes), (12, 18));
+    }
+}
@@ -1,146 +1,338 @@
-use enum_map::EnumMap;
-use strum::IntoEnumIterator;
+use std::collections::HashMap;
 
-use crate::domain::snapshot::{Flavor, Order, OrderStatus, OrderSystemSnapshot};
+use chrono::{DateTime, Duration, Utc};
 
-pub fn estimate_order_wait_minutes(snapshot: &OrderSystemSnapshot, order: &Order) -> Option<i64> {
-    if order.status != OrderStatus::Waiting {
+use crate::domain::snapshot::{
+    Flavor, Order, OrderStatus, OrderSystemSnapshot, PriorityAging, PriorityTier,
+};
+use crate::domain::station::{self, PlateSchedule};
+
+pub fn estimate_order_wait_minutes(
+    snapshot: &OrderSystemSnapshot,
+    order: &Order,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    if !is_queued(order, now) {
         return None;
     }
 
-    order
-        .items
+    let aging = snapshot.priority_aging;
+    let is_ahead = |other: &Order| {
+        order_priority_key(other, aging, now) <= order_priority_key(order, aging, now)
+    };
+    let orders_ahead = snapshot
+        .orders
         .iter()
-        .map(|item_in_order| {
-            let flavor = item_in_order.flavor;
-            let total_demand = snapshot
-                .orders
-                .iter()
-                .filter(|other| {
-                    other.status == OrderStatus::Waiting
-                        && order_priority_key(other) <= order_priority_key(order)
-                })
-                .flat_map(|other| &other.items)
-                .filter(|item| item.flavor == flavor)
-                .map(|item| item.quantity)
-                .sum::<usize>();
-
-            estimate_flavor_wait_minutes(snapshot, flavor, total_demand)
-        })
-        .max()
-        .unwrap_or(Some(0))
+        .filter(|other| is_queued(other, now) && is_ahead(other))
+        .collect();
+    let held = held_pre_orders(snapshot, now).filter(|other| is_ahead(other));
+
+    with_reserved_production(orders_ahead, held, aging, now, |orders_ahead| {
+        let ready_in = estimate_production_minutes(
+            snapshot,
+            orders_ahead.iter().flat_map(|other| other.outstanding()),
+            now,
+        );
+        order
+            .outstanding()
+            .map(|(flavor, _)| ready_in.get(flavor).copied().flatten())
+            .max()
+            .unwrap_or(Some(0))
+    })
 }
 
-pub fn estimate_current_wait_times(snapshot: &OrderSystemSnapshot) -> EnumMap<Flavor, Option<i64>> {
-    let mut wait_times = EnumMap::from_fn(|_| None);
+/// Wait time for one more piece of each flavor on sale, in menu order.
+pub fn estimate_current_wait_times(
+    snapshot: &OrderSystemSnapshot,
+    now: DateTime<Utc>,
+) -> Vec<(Flavor, Option<i64>)> {
+    let queued_orders = snapshot
+        .orders
+        .iter()
+        .filter(|order| is_queued(order, now))
+        .collect::<Vec<_>>();
 
-    for flavor in Flavor::iter() {
-        let demand = snapshot
-            .orders
-            .iter()
-            .filter(|order| {
-                order.status == OrderStatus::Waiting || order.status == OrderStatus::Cooking
-            })
-            .flat_map(|order| &order.items)
-            .filter(|item| item.flavor == flavor)
-            .map(|item| item.quantity)
-            .sum::<usize>()
-            + 1;
+    let mut wait_times = Vec::new();
 
-        wait_times[flavor] = estimate_flavor_wait_minutes(snapshot, flavor, demand);
+    for flavor in snapshot.active_flavors().map(|entry| &entry.id) {
+        let wait_minutes = with_reserved_production(
+            queued_orders.clone(),
+            held_pre_orders(snapshot, now),
+            snapshot.priority_aging,
+            now,
+            |queued_orders| {
+                let demand = queued_orders
+                    .iter()
+                    .flat_map(|order| order.outstanding())
+                    .chain([(flavor, 1)]);
+                estimate_production_minutes(snapshot, demand, now)
+                    .get(flavor)
+                    .copied()
+                    .flatten()
+            },
+        );
+        wait_times.push((flavor.clone(), wait_minutes));
     }
 
     wait_times
 }
 
-fn order_priority_key(order: &Order) -> (bool, chrono::DateTime<chrono::Utc>, u32) {
-    (!order.is_priority, order.ordered_at, order.id)
+// Cooking orders have claimed pieces of the batches on the grill. Pre-orders join the queue
+// at their lead time before pickup.
+pub(crate) fn is_queued(order: &Order, now: DateTime<Utc>) -> bool {
+    (order.status == OrderStatus::Waiting && !order.is_held(now))
+        || order.status == OrderStatus::Cooking
 }
 
-fn estimate_flavor_wait_minutes(
+pub(crate) fn held_pre_orders(
     snapshot: &OrderSystemSnapshot,
-    flavor: Flavor,
-    demand: usize,
+    now: DateTime<Utc>,
+) -> impl Iterator<Item = &Order> {
+    snapshot
+        .orders
+        .iter()
+        .filter(move |order| order.is_held(now))
+}
+
+/// Pre-orders released before the estimate runs out are served first, so they are counted
+/// ahead until the estimate settles. `estimate` gets the orders ahead in priority order.
+pub(crate) fn with_reserved_production<'a>(
+    mut orders_ahead: Vec<&'a Order>,
+    held: impl Iterator<Item = &'a Order>,
+    aging: PriorityAging,
+    now: DateTime<Utc>,
+    estimate: impl Fn(&[&'a Order]) -> Option<i64>,
 ) -> Option<i64> {
-    let stock = snapshot.unallocated_stock[flavor];
-    if demand <= stock {
-        return Some(0);
+    let mut held = held.collect::<Vec<_>>();
+    held.sort_by_key(|order| order.release_at);
+    loop {
+        orders_ahead.sort_by_key(|order| order_priority_key(order, aging, now));
+        let minutes = estimate(&orders_ahead)?;
+        let ready_at = now + Duration::minutes(minutes);
+        let released = held
+            .iter()
+            .take_while(|order| {
+                order
+                    .release_at
+                    .is_some_and(|release_at| release_at <= ready_at)
+            })
+            .count();
+        if released == 0 {
+            return Some(minutes);
+        }
+        orders_ahead.extend(held.drain(..released));
     }
+}
 
-    let needed_from_production = demand.saturating_sub(stock);
-    let config = snapshot.flavor_configs[flavor];
-    if config.quantity_per_batch == 0 {
-        return None;
+/// Place in the queue at `now`: tier after promotions for waiting, then arrival.
+pub(crate) fn order_priority_key(
+    order: &Order,
+    aging: PriorityAging,
+    now: DateTime<Utc>,
+) -> (PriorityTier, DateTime<Utc>, u32) {
+    (
+        order.effective_priority(aging, now),
+        order.ordered_at,
+        order.id,
+    )
+}
+
+/// Minutes until each flavor in `demand` is covered, in the order the pieces are demanded.
+/// Stock comes first, then the batches on the grill, then new batches on parallel plates.
+/// `None` means the flavor cannot be grilled.
+fn estimate_production_minutes<'a>(
+    snapshot: &OrderSystemSnapshot,
+    demand: impl IntoIterator<Item = (&'a Flavor, usize)>,
+    now: DateTime<Utc>,
+) -> HashMap<Flavor, Option<i64>> {
+    let mut schedule = PlateSchedule::new(snapshot, now);
+    let mut on_the_grill = HashMap::<&Flavor, Vec<(usize, i64)>>::new();
+    for batch in snapshot.active_batches() {
+        on_the_grill.entry(&batch.flavor).or_default().push((
+            batch.quantity,
+            station::remaining_cooking_minutes(snapshot, batch, now),
+        ));
     }
+    // Soonest done last, so that `pop` takes it first.
+    for batches in on_the_grill.values_mut() {
+        batches.sort_by_key(|&(_, remaining)| std::cmp::Reverse(remaining));
+    }
+
+    let mut demanded = HashMap::<&Flavor, usize>::new();
+    let mut covered = HashMap::<&Flavor, usize>::new();
+    let mut ready_in = HashMap::<Flavor, Option<i64>>::new();
 
-    let batches_needed = needed_from_production.div_ceil(config.quantity_per_batch as usize);
-    Some(batches_needed as i64 * config.cooking_time_minutes as i64)
+    for (flavor, quantity) in demand {
+        let demanded = demanded.entry(flavor).or_insert(0);
+        *demanded += quantity;
+        let covered = covered
+            .entry(flavor)
+            .or_insert_with(|| snapshot.stock_of(flavor));
+        let ready_in = ready_in.entry(flavor.clone()).or_insert(Some(0));
+
+        while *covered < *demanded {
+            let Some((quantity, remaining)) = on_the_grill.get_mut(flavor).and_then(Vec::pop)
+            else {
+                break;
+            };
+            *covered += quantity;
+            *ready_in = ready_in.map(|ready_in| ready_in.max(remaining));
+        }
+
+        let Some(batches) =
+            station::batches_needed(snapshot, flavor, demanded.saturating_sub(*covered))
+        else {
+            *ready_in = None;
+            continue;
+        };
+        for _ in 0..batches {
+            match schedule.grill_batch(flavor) {
+                Some(done) => *ready_in = ready_in.map(|ready_in| ready_in.max(done)),
+                None => *ready_in = None,
+            }
+        }
+        if let Some(config) = snapshot.flavor_configs.get(flavor) {
+            *covered += batches * config.quantity_per_batch as usize;
+        }
+    }
+
+    ready_in
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::{TimeZone, Utc};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
 
-    use crate::domain::snapshot::{Flavor, Item, Order, OrderStatus, OrderSystemSnapshot};
+    use crate::domain::snapshot::{Batch, Order, OrderStatus, PriorityTier, Station};
+    use crate::test_fixtures::{snapshot_with_orders, tsubuan, waiting_order};
 
     use super::{estimate_current_wait_times, estimate_order_wait_minutes};
 
-    fn waiting_order(id: u32, quantity: usize) -> Order {
-        Order {
-            id,
-            items: vec![Item {
-                flavor: Flavor::Tsubuan,
-                quantity,
-            }],
-            status: OrderStatus::Waiting,
-            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, id).unwrap(),
-            ready_at: None,
-            completed_at: None,
-            notify: Default::default(),
-            is_priority: false,
-        }
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap()
     }
 
     fn priority_waiting_order(id: u32, quantity: usize) -> Order {
         Order {
-            is_priority: true,
+            priority: PriorityTier::Vip,
             ..waiting_order(id, quantity)
         }
     }
 
     #[test]
     fn current_wait_time_is_zero_when_stock_covers_next_order() {
-        let mut snapshot = OrderSystemSnapshot::default();
-        snapshot.unallocated_stock[Flavor::Tsubuan] = 1;
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
 
-        let wait_times = estimate_current_wait_times(&snapshot);
+        let wait_times = estimate_current_wait_times(&snapshot, now());
 
-        assert_eq!(wait_times[Flavor::Tsubuan], Some(0));
+        assert_eq!(wait_times, vec![(tsubuan(), Some(0))]);
     }
 
     #[test]
     fn waiting_order_uses_largest_flavor_wait_time() {
-        let snapshot = OrderSystemSnapshot {
-            orders: vec![waiting_order(1, 10)],
-            ..Default::default()
-        };
+        let snapshot = snapshot_with_orders(vec![waiting_order(1, 10)]);
 
         assert_eq!(
-            estimate_order_wait_minutes(&snapshot, &snapshot.orders[0]),
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[0], now()),
             Some(30)
         );
     }
 
     #[test]
     fn priority_order_wait_time_ignores_overtaken_normal_orders() {
-        let snapshot = OrderSystemSnapshot {
-            orders: vec![waiting_order(1, 9), priority_waiting_order(2, 1)],
-            ..Default::default()
-        };
+        let snapshot =
+            snapshot_with_orders(vec![waiting_order(1, 9), priority_waiting_order(2, 1)]);
 
         assert_eq!(
-            estimate_order_wait_minutes(&snapshot, &snapshot.orders[1]),
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[1], now()),
             Some(15)
         );
     }
+
+    #[test]
+    fn current_wait_times_skip_retired_flavors() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        snapshot.menu[0].is_active = false;
+
+        assert!(estimate_current_wait_times(&snapshot, now()).is_empty());
+    }
+
+    #[test]
+    fn parallel_plates_grill_batches_at_the_same_time() {
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 18)]);
+        snapshot.stations.push(Station {
+            id: 1,
+            name: "鉄板A".to_owned(),
+            plate_count: 2,
+        });
+        snapshot
+            .flavor_configs
+            .get_mut(&tsubuan())
+            .expect("config exists")
+            .station_id = Some(1);
+
+        assert_eq!(
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[0], now()),
+            Some(15)
+        );
+        assert_eq!(
+            estimate_current_wait_times(&snapshot, now()),
+            vec![(tsubuan(), Some(30))]
+        );
+    }
+
+    #[test]
+    fn batch_on_the_grill_covers_waiting_order_with_its_remaining_time() {
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 3)]);
+        snapshot.batches.push(Batch {
+            id: 1,
+            flavor: tsubuan(),
+            quantity: 9,
+            started_at: now() - Duration::minutes(12),
+            finished_at: None,
+        });
+
+        assert_eq!(
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[0], now()),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn cooking_order_is_estimated_until_its_batch_comes_off() {
+        let mut cooking = waiting_order(1, 3);
+        cooking.status = OrderStatus::Cooking;
+        let mut snapshot = snapshot_with_orders(vec![cooking]);
+        snapshot.batches.push(Batch {
+            id: 1,
+            flavor: tsubuan(),
+            quantity: 9,
+            started_at: now() - Duration::minutes(5),
+            finished_at: None,
+        });
+
+        assert_eq!(
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[0], now()),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn pre_order_released_before_walk_in_is_served_goes_first() {
+        let mut pre_order = waiting_order(1, 9);
+        pre_order.pickup_at = Some(now() + Duration::minutes(40));
+        pre_order.release_at = Some(now() + Duration::minutes(10));
+        let snapshot = snapshot_with_orders(vec![pre_order, waiting_order(2, 1)]);
+
+        // Alone the walk-in would wait for one batch, but the pre-order joins after 10 minutes.
+        assert_eq!(
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[1], now()),
+            Some(30)
+        );
+        assert_eq!(
+            estimate_order_wait_minutes(&snapshot, &snapshot.orders[0], now()),
+            None
+        );
+    }
 }
@@ -6,4 +6,6 @@ pub mod domain;
 pub mod line;
 pub mod port;
 pub mod storage;
+#[cfg(test)]
+mod test_fixtures;
 mod usecase;
@@ -3,9 +3,19 @@ use bot_sdk_line::messaging_api_line::models::{
     TemplateMessage, TextMessageV2, template::Template,
 };
 
+use chrono::{DateTime, Utc};
+
 use crate::{
     app::AppRegistry,
-    domain::snapshot::{Notify, OrderStatus},
+    domain::{
+        availability::{store_date, store_time},
+        business_day,
+        order_event::Actor,
+        order_number::DisplayOrderNumber,
+        pre_order::{self, PickupSlot},
+        snapshot::{Item, MenuFlavor, Notify, OrderStatus, OrderSystemSnapshot},
+    },
+    usecase::order::{NewOrder, OrderError},
 };
 use tracing::error;
 // ========== 公開API: イベントハンドラー ==========
@@ -19,6 +29,8 @@ pub async fn handle_command(
 ) {
     if let Some(order_id_str) = command.strip_prefix("!adding_notification:") {
         handle_adding_notification(registry, reply_token, order_id_str.trim(), user_id).await;
+    } else if let Some(args) = command.strip_prefix("!preorder:") {
+        handle_pre_order(registry, reply_token, args.trim(), user_id).await;
     } else {
         send_reply(
             registry,
@@ -32,7 +44,14 @@ pub async fn handle_command(
 }
 
 /// 通常のテキストメッセージを処理
-pub async fn handle_text_message(registry: &AppRegistry, reply_token: String, _text: &str) {
+pub async fn handle_text_message(registry: &AppRegistry, reply_token: String, text: &str) {
+    // 呼び出し番号が送られてきたら注文状況を返す
+    if let Some(number) = DisplayOrderNumber::parse_input(text)
+        && number.as_str().chars().any(|c| c.is_ascii_digit())
+    {
+        handle_check_order_by_number(registry, reply_token, &number).await;
+        return;
+    }
     send_reply(
         registry,
         reply_token,
@@ -86,6 +105,18 @@ pub async fn handle_postback(
         return;
     }
 
+    // 予約できる時間帯の表示
+    if postback_data == "action=show_pickup_slots" {
+        handle_show_pickup_slots(registry, reply_token).await;
+        return;
+    }
+
+    // メニュー表示
+    if postback_data == "action=show_menu" {
+        handle_show_menu(registry, reply_token).await;
+        return;
+    }
+
     // その他の定型アクション
     let reply_text = get_static_reply_text(postback_data);
     send_reply(registry, reply_token, vec![create_text_message(reply_text)]).await;
@@ -117,6 +148,23 @@ async fn handle_check_order_status(registry: &AppRegistry, reply_token: String,
     }
 }
 
+/// 呼び出し番号から注文状況を確認
+async fn handle_check_order_by_number(
+    registry: &AppRegistry,
+    reply_token: String,
+    number: &DisplayOrderNumber,
+) {
+    let reply_text = match registry.get_order_details_by_display_number(number).await {
+        Ok(Some(details)) => format_order_details(&details),
+        Ok(None) => format!("❌ 呼び出し番号 #{} の注文が見つかりません。", number),
+        Err(error) => {
+            error!(?error, %number, "failed to load line order details by number");
+            "❌ エラー：注文情報を取得できませんでした。".to_string()
+        }
+    };
+    send_reply(registry, reply_token, vec![create_text_message(reply_text)]).await;
+}
+
 /// 通知登録確認を処理
 async fn handle_notification_confirm(
     registry: &AppRegistry,
@@ -136,11 +184,11 @@ async fn handle_notification_confirm(
         return;
     };
 
-    let payload = Notify::Line { user_id };
-
-    let result = registry.add_notification(order_id, payload).await;
+    let result = acting_registry(registry, &user_id)
+        .add_notification(order_id, Notify::Line { user_id })
+        .await;
 
-    if matches!(result, Ok(Some(_))) {
+    if matches!(result, Ok(Ok(_))) {
         let buttons_template = create_notification_success_template(order_id);
         send_reply(
             registry,
@@ -192,10 +240,11 @@ async fn handle_notification_cancel(
         return;
     };
 
+    let acting = acting_registry(registry, &user_id);
     let payload = Notify::Line { user_id };
 
-    match registry.cancel_notification(order_id, &payload).await {
-        Ok(Some(_)) => {
+    match acting.cancel_notification(order_id, &payload).await {
+        Ok(Ok(_)) => {
             send_reply(
                 registry,
                 reply_token,
@@ -206,7 +255,7 @@ async fn handle_notification_cancel(
             )
             .await;
         }
-        Ok(None) => {
+        Ok(Err(_)) => {
             send_reply(
                 registry,
                 reply_token,
@@ -270,7 +319,7 @@ async fn handle_adding_notification(
         Ok(Some(details))
             if !matches!(
                 details.status,
-                OrderStatus::Completed | OrderStatus::Cancelled
+                OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Expired
             ) =>
         {
             let confirm_template = create_notification_confirm_template(&details);
@@ -315,6 +364,70 @@ async fn handle_adding_notification(
     }
 }
 
+/// 予約注文を処理（例: !preorder: 14:00 つぶあん:20,カスタード:10）
+async fn handle_pre_order(
+    registry: &AppRegistry,
+    reply_token: String,
+    args: &str,
+    user_id: Option<String>,
+) {
+    let Some(user_id) = user_id else {
+        send_reply(
+            registry,
+            reply_token,
+            vec![create_text_message(
+                "❌ ユーザー情報の取得に失敗しました。".to_string(),
+            )],
+        )
+        .await;
+        return;
+    };
+
+    let reply_text = match registry.snapshot().await {
+        Ok(snapshot) => match parse_pre_order(&snapshot, args) {
+            Ok((pickup_at, items)) => {
+                let acting = acting_registry(registry, &user_id);
+                let new_order = NewOrder {
+                    pickup_at: Some(pickup_at),
+                    notify: [Notify::Line { user_id }].into(),
+                    ..NewOrder::new(items)
+                };
+                match acting.create_order(new_order).await {
+                    Ok(Ok(order)) => format!(
+                        "✅ 予約を受け付けました！\n\n【呼び出し番号】\n#{}\n\n【受け取り時刻】\n{}〜\n\n【合計】\n{}円\n\n準備ができたらメッセージでお知らせします。",
+                        order.display_number,
+                        store_time(order.pickup_at.unwrap_or(pickup_at)).format("%H:%M"),
+                        order.total_price
+                    ),
+                    Ok(Err(error)) => format_pre_order_error(&error),
+                    Err(error) => {
+                        error!(?error, "failed to save line pre-order");
+                        "❌ エラー：予約を保存できませんでした。".to_string()
+                    }
+                }
+            }
+            Err(message) => message,
+        },
+        Err(error) => {
+            error!(?error, "failed to load line menu for pre-order");
+            "❌ エラー：メニューを取得できませんでした。".to_string()
+        }
+    };
+    send_reply(registry, reply_token, vec![create_text_message(reply_text)]).await;
+}
+
+/// 予約できる時間帯を表示
+async fn handle_show_pickup_slots(registry: &AppRegistry, reply_token: String) {
+    let reply_text = match registry.get_pickup_slots().await {
+        Ok(slots) => format_pickup_slots(&slots),
+        Err(error) => {
+            error!(?error, "failed to load line pickup slots");
+            "❌ エラー：予約枠を取得できませんでした。".to_string()
+        }
+    };
+    send_reply(registry, reply_token, vec![create_text_message(reply_text)]).await;
+}
+
 /// 待ち時間を表示
 async fn handle_show_waittime(registry: &AppRegistry, reply_token: String) {
     let reply_text = match registry.get_current_wait_times().await {
@@ -327,6 +440,25 @@ async fn handle_show_waittime(registry: &AppRegistry, reply_token: String) {
     send_reply(registry, reply_token, vec![create_text_message(reply_text)]).await;
 }
 
+/// メニューを表示
+async fn handle_show_menu(registry: &AppRegistry, reply_token: String) {
+    let reply_text = match registry.snapshot().await {
+        Ok(snapshot) => format_menu(snapshot.active_flavors()),
+        Err(error) => {
+            error!(?error, "failed to load line menu");
+            "❌ エラー：メニューを取得できませんでした。".to_string()
+        }
+    };
+    send_reply(registry, reply_token, vec![create_text_message(reply_text)]).await;
+}
+
+// Mutations are recorded as made by the LINE user who sent the message.
+fn acting_registry(registry: &AppRegistry, user_id: &str) -> AppRegistry {
+    registry.as_actor(Actor::Line {
+        user_id: user_id.to_owned(),
+    })
+}
+
 // ========== ヘルパー関数：メッセージフォーマット ==========
 
 /// OrderDetailsResponse をユーザー向けにフォーマット
@@ -337,24 +469,34 @@ fn format_order_details(details: &crate::api::model::OrderDetailsResponse) -> St
         OrderStatus::Ready => "✅ 受け取り準備完了",
         OrderStatus::Completed => "🎉 完了",
         OrderStatus::Cancelled => "❌ キャンセル",
+        OrderStatus::Expired => "⌛ 受け取り期限切れ",
     };
 
     let items_str = details
         .items
         .iter()
-        .map(|item| format!("  ・{} x{}", item.flavor, item.quantity))
+        .map(|item| {
+            if item.handed_over_quantity > 0 {
+                format!(
+                    "  ・{} x{} (受け渡し済み {})",
+                    item.display_name, item.quantity, item.handed_over_quantity
+                )
+            } else {
+                format!("  ・{} x{}", item.display_name, item.quantity)
+            }
+        })
         .collect::<Vec<_>>()
         .join("\n");
 
     let ordered_at_str = details.ordered_at.format("%Y年%m月%d日 %H:%M").to_string();
 
     let wait_time_str = details
-        .estimated_wait_minutes
-        .map_or("N/A".to_string(), |m| format!("{} 分", m));
+        .estimate
+        .map_or("N/A".to_string(), |estimate| estimate.describe());
 
     format!(
-        "📦 注文 #{}\n\n【現在の状態】\n{}\n\n【予想待ち時間】\n{}\n\n【商品】\n{}\n\n【注文時刻】\n{}",
-        details.id, status_text, wait_time_str, items_str, ordered_at_str
+        "📦 注文 #{} (注文ID: {})\n\n【現在の状態】\n{}\n\n【予想待ち時間】\n{}\n\n【商品】\n{}\n\n【注文時刻】\n{}",
+        details.display_number, details.id, status_text, wait_time_str, items_str, ordered_at_str
     )
 }
 
@@ -362,28 +504,127 @@ fn format_order_details(details: &crate::api::model::OrderDetailsResponse) -> St
 fn format_wait_times(wait_times: &crate::api::model::WaitTimeResponse) -> String {
     let mut lines = vec!["⏱️ 現在の待ち時間".to_string(), "".to_string()];
 
-    for (flavor, time) in wait_times.wait_times.iter() {
-        let time_str = time.map_or("提供なし".to_string(), |t| {
-            if t == 0 {
-                "すぐに提供できます".to_string()
-            } else {
-                format!("約{}分", t)
-            }
-        });
-        lines.push(format!("【{}】\n{}", flavor, time_str));
+    for wait_time in &wait_times.flavors {
+        let mut time_str = if wait_time.is_sold_out {
+            "🙇 売り切れ".to_string()
+        } else {
+            wait_time
+                .estimate
+                .map_or("提供なし".to_string(), |estimate| estimate.describe())
+        };
+        if !wait_time.is_sold_out
+            && let Some(remaining) = wait_time.remaining_quantity
+        {
+            time_str.push_str(&format!("（残り{}個）", remaining));
+        }
+        lines.push(format!("【{}】\n{}", wait_time.display_name, time_str));
     }
 
     lines.join("\n")
 }
 
+/// メニューをフォーマット
+fn format_menu<'a>(flavors: impl Iterator<Item = &'a MenuFlavor>) -> String {
+    let mut lines = vec!["🐟メニュー☆彡".to_string()];
+    lines.extend(
+        flavors.map(|flavor| format!("- {} ({}円)", flavor.display_name, flavor.unit_price)),
+    );
+    lines.join("\n")
+}
+
+/// 予約できる時間帯をフォーマット
+fn format_pickup_slots(slots: &[PickupSlot]) -> String {
+    let lines = slots
+        .iter()
+        .filter(|slot| slot.remaining > 0)
+        .map(|slot| {
+            format!(
+                "・{}〜{}（残り{}個）",
+                store_time(slot.starts_at).format("%H:%M"),
+                store_time(slot.ends_at).format("%H:%M"),
+                slot.remaining
+            )
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return "🙇 現在予約できる時間帯はありません。".to_string();
+    }
+    format!(
+        "🕒 予約できる受け取り時間帯\n\n{}\n\n予約は次の形式で送ってください↓\n例:'!preorder: 14:00 つぶあん:20,カスタード:10'",
+        lines.join("\n")
+    )
+}
+
+/// 予約注文の引数を解析（受け取り時刻と商品）
+fn parse_pre_order(
+    snapshot: &OrderSystemSnapshot,
+    args: &str,
+) -> Result<(DateTime<Utc>, Vec<Item>), String> {
+    let usage = "❌ 予約の形式が不正です。\n例:'!preorder: 14:00 つぶあん:20,カスタード:10'";
+    let (time, items) = args.split_once(char::is_whitespace).ok_or(usage)?;
+    let date =
+        business_day::open_day(snapshot).map_or_else(|| store_date(Utc::now()), |day| day.date);
+    let pickup_at = pre_order::parse_pickup_time(time, date)
+        .ok_or_else(|| format!("❌ 受け取り時刻が不正です: {}\n例: 14:00", time))?;
+    let items = items
+        .split([',', '、'])
+        .map(|item| {
+            let (name, quantity) = item.trim().split_once([':', '：']).ok_or(usage)?;
+            let flavor = snapshot
+                .menu
+                .iter()
+                .find(|entry| {
+                    entry.display_name == name.trim()
+                        || entry.id.as_str().eq_ignore_ascii_case(name.trim())
+                })
+                .map(|entry| entry.id.clone())
+                .ok_or_else(|| format!("❌ メニューにない商品です: {}", name.trim()))?;
+            let quantity = quantity
+                .trim()
+                .parse()
+                .map_err(|_| format!("❌ 不正な数量です: {}", quantity.trim()))?;
+            Ok(Item { flavor, quantity })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok((pickup_at, items))
+}
+
+/// 予約注文のエラーをユーザー向けにフォーマット
+fn format_pre_order_error(error: &OrderError) -> String {
+    match error {
+        OrderError::PickupTooSoon { earliest } => format!(
+            "❌ 予約は{}からの枠で受け付けています。",
+            store_time(*earliest).format("%H:%M")
+        ),
+        OrderError::PickupOutsideBusinessDay => {
+            "❌ 予約は本日の営業時間内の枠のみ受け付けています。".to_string()
+        }
+        OrderError::PickupSlotFull {
+            starts_at,
+            remaining,
+        } => format!(
+            "🙇 {}の枠は残り{}個までしか予約できません。",
+            store_time(*starts_at).format("%H:%M"),
+            remaining
+        ),
+        OrderError::BusinessDayNotOpen => "🙇 現在は営業時間外です。".to_string(),
+        OrderError::ExceedsRemaining { remaining, .. } => {
+            format!("🙇 本日の残りは{}個のため予約できません。", remaining)
+        }
+        OrderError::NotOnSale(_) => "🙇 現在販売していない商品が含まれています。".to_string(),
+        _ => "❌ 予約できませんでした。内容を確認してもう一度お試しください。".to_string(),
+    }
+}
+
 /// 静的な返信テキストを取得
 fn get_static_reply_text(postback_data: &str) -> String {
     match postback_data {
         "action=register_notification" => {
             "注文番号を半角数字で続いて入力↓\n例:'!adding_notification: 123'".into()
         }
-        "action=show_menu" => {
-            "🐟メニュー☆彡\n- つぶあん (200円)\n- カスタード (200円)\n- 栗きんとん (200円)".into()
+        "action=preorder" => {
+            "受け取り時刻と商品を続いて入力↓\n例:'!preorder: 14:00 つぶあん:20,カスタード:10'"
+                .into()
         }
         _ => format!("不明な操作です: {}", postback_data),
     }
@@ -447,7 +688,7 @@ fn create_notification_confirm_template(
     let items_str = details
         .items
         .iter()
-        .map(|item| format!("・{} x{}", item.flavor, item.quantity))
+        .map(|item| format!("・{} x{}", item.display_name, item.quantity))
         .collect::<Vec<_>>()
         .join("\n");
 
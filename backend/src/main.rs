@@ -40,12 +40,32 @@ async fn main() -> anyhow::Result<()> {
                     config.line_channel_access_token,
                     config.line_channel_secret,
                     config.staff_api_token,
+                    config.discord_staff_channel_id,
                     ctx.clone(),
                     repository,
-                );
+                )
+                .with_wait_time_settings(config.wait_time)
+                .with_stock_settings(config.stock)
+                .with_display_number_settings(config.display_numbers)
+                .with_pre_order_settings(config.pre_orders)
+                .with_order_limits(config.order_limits)
+                .with_no_show_settings(config.no_shows)
+                .with_priority_aging(config.priority_aging)
+                .with_allocation_policy(config.allocation_policy);
                 let ret = registry.initialize().await;
                 info!(?ret, "initialized registry");
 
+                tokio::spawn(
+                    registry
+                        .clone()
+                        .run_batch_timers(config.batch_alert_escalation_minutes),
+                );
+                tokio::spawn(registry.clone().run_pre_order_release());
+                tokio::spawn(registry.clone().run_no_show_policy());
+                if config.stock.auto_discard {
+                    tokio::spawn(registry.clone().run_stock_expiry());
+                }
+
                 let app = routes(registry.clone()).layer(cors());
 
                 info!(addr = %config.bind_addr, "listening");
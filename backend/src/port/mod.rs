@@ -2,3 +2,4 @@ pub mod line_reply;
 pub mod notification_log;
 pub mod notifier;
 pub mod order_repository;
+pub mod staff_alert;
@@ -1,9 +1,21 @@
 use async_trait::async_trait;
 
+use crate::domain::order_event::{EventFilter, OrderEvent};
 use crate::domain::snapshot::OrderSystemSnapshot;
+use crate::domain::snapshot_change::SnapshotChange;
 
 #[async_trait]
 pub trait OrderRepository: Send + Sync {
+    /// Everything stored, including the finished orders of past days.
     async fn load_snapshot(&self) -> anyhow::Result<OrderSystemSnapshot>;
-    async fn replace_snapshot(&self, snapshot: &OrderSystemSnapshot) -> anyhow::Result<()>;
+    /// Writes the changes together with the events recording them in one transaction.
+    async fn apply_changes(
+        &self,
+        changes: &[SnapshotChange],
+        events: &[OrderEvent],
+    ) -> anyhow::Result<()>;
+    /// Events of one order, oldest first.
+    async fn order_history(&self, order_id: u32) -> anyhow::Result<Vec<OrderEvent>>;
+    /// Events the filter picks across all orders, production and stock, oldest first.
+    async fn events(&self, filter: EventFilter) -> anyhow::Result<Vec<OrderEvent>>;
 }
@@ -0,0 +1,6 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait StaffAlertSender: Send + Sync {
+    async fn alert_staff(&self, message: String) -> anyhow::Result<()>;
+}
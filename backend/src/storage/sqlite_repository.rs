@@ -1,14 +1,19 @@
 use std::collections::{HashMap, HashSet};
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
-use strum::IntoEnumIterator;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
 
+use crate::domain::business_day::{BusinessDay, StockDisposition};
 use crate::domain::notification::NotificationDeliveryLog;
+use crate::domain::order_event::{Actor, EventFilter, EventReason, OrderEvent, OrderEventKind};
+use crate::domain::order_number::DisplayOrderNumber;
 use crate::domain::snapshot::{
-    Flavor, FlavorConfig, Item, Notify, Order, OrderStatus, OrderSystemSnapshot,
+    Batch, Flavor, FlavorConfig, Item, MenuFlavor, Notify, Order, OrderStatus, OrderSystemSnapshot,
+    PaymentMethod, PricingRule, PricingRuleKind, PriorityTier, Station, StockAdjustment, StockLot,
+    StockReason,
 };
+use crate::domain::snapshot_change::SnapshotChange;
 use crate::port::notification_log::NotificationLog;
 use crate::port::order_repository::OrderRepository;
 
@@ -23,183 +28,492 @@ impl SqliteRepository {
     }
 
     async fn load_snapshot(&self) -> anyhow::Result<OrderSystemSnapshot> {
-        let mut snapshot = OrderSystemSnapshot::default();
-
-        for row in sqlx::query!("SELECT flavor, unallocated_quantity FROM stock")
+        let menu = sqlx::query!(
+            "SELECT id, display_name, sort_order, is_active, unit_price FROM flavors ORDER BY sort_order, id",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(MenuFlavor {
+                id: Flavor::from_db_str(&required_column(row.id, "id")?)?,
+                display_name: row.display_name,
+                sort_order: row.sort_order,
+                is_active: row.is_active != 0,
+                unit_price: row.unit_price as u32,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        let pricing_rules = sqlx::query!(
+            "SELECT code, name, kind, bundle_quantity, bundle_price, discount_percent, discount_amount, is_active FROM pricing_rules ORDER BY code",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let kind = match row.kind.as_str() {
+                "bundle" => PricingRuleKind::Bundle {
+                    quantity: required_integer(row.bundle_quantity, "bundle_quantity")? as u32,
+                    price: required_integer(row.bundle_price, "bundle_price")? as u32,
+                },
+                "percentage_discount" => PricingRuleKind::PercentageDiscount {
+                    percent: required_integer(row.discount_percent, "discount_percent")? as u32,
+                },
+                "fixed_discount" => PricingRuleKind::FixedDiscount {
+                    amount: required_integer(row.discount_amount, "discount_amount")? as u32,
+                },
+                kind => anyhow::bail!("invalid pricing rule kind: {kind}"),
+            };
+            Ok(PricingRule {
+                code: required_column(row.code, "code")?,
+                name: row.name,
+                kind,
+                is_active: row.is_active != 0,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        let stations = sqlx::query!("SELECT id, name, plate_count FROM stations ORDER BY id")
             .fetch_all(&self.pool)
             .await?
-        {
-            let flavor = Flavor::from_db_str(&required_column(row.flavor, "flavor")?)?;
-            snapshot.unallocated_stock[flavor] = row.unallocated_quantity as usize;
-        }
-
-        for row in sqlx::query!(
-            "SELECT flavor, cooking_time_minutes, quantity_per_batch FROM flavor_configs",
+            .into_iter()
+            .map(|row| Station {
+                id: row.id as u32,
+                name: row.name,
+                plate_count: row.plate_count as u32,
+            })
+            .collect();
+        let batches = sqlx::query!(
+            "SELECT id, flavor, quantity, started_at, finished_at FROM batches ORDER BY id",
         )
         .fetch_all(&self.pool)
         .await?
-        {
-            let flavor = Flavor::from_db_str(&required_column(row.flavor, "flavor")?)?;
-            snapshot.flavor_configs[flavor] = FlavorConfig {
-                cooking_time_minutes: row.cooking_time_minutes as u32,
-                quantity_per_batch: row.quantity_per_batch as u32,
-            };
-        }
+        .into_iter()
+        .map(|row| {
+            Ok(Batch {
+                id: row.id as u32,
+                flavor: Flavor::from_db_str(&row.flavor)?,
+                quantity: row.quantity as usize,
+                started_at: parse_datetime(row.started_at)?,
+                finished_at: parse_optional_datetime(row.finished_at)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        let business_days = sqlx::query!(
+            "SELECT id, business_date, opened_at, closed_at, stock_disposition FROM business_days ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(BusinessDay {
+                id: row.id as u32,
+                date: NaiveDate::parse_from_str(&row.business_date, "%Y-%m-%d")?,
+                opened_at: parse_datetime(row.opened_at)?,
+                closed_at: parse_optional_datetime(row.closed_at)?,
+                stock_disposition: row
+                    .stock_disposition
+                    .as_deref()
+                    .map(StockDisposition::from_db_str)
+                    .transpose()?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut snapshot = OrderSystemSnapshot {
+            menu,
+            pricing_rules,
+            stations,
+            batches,
+            business_days,
+            ..Default::default()
+        };
 
-        let mut items_by_order_id = HashMap::<u32, Vec<Item>>::new();
-        for row in sqlx::query!("SELECT order_id, flavor, quantity FROM order_items")
+        for row in sqlx::query!("SELECT flavor, unallocated_quantity FROM stock")
             .fetch_all(&self.pool)
             .await?
         {
-            let order_id = row.order_id as u32;
-            let flavor = Flavor::from_db_str(&row.flavor)?;
-            let quantity = row.quantity as usize;
-            items_by_order_id
-                .entry(order_id)
-                .or_default()
-                .push(Item { flavor, quantity });
+            let flavor = Flavor::from_db_str(&required_column(row.flavor, "flavor")?)?;
+            snapshot
+                .unallocated_stock
+                .insert(flavor, row.unallocated_quantity as usize);
         }
 
-        let mut notifications_by_order_id = HashMap::<u32, HashSet<Notify>>::new();
-        for row in sqlx::query!(
-            "SELECT order_id, kind, discord_channel_id, discord_user_id, line_user_id FROM notifications",
+        snapshot.stock_lots = sqlx::query!(
+            "SELECT flavor, quantity, produced_at FROM stock_lots ORDER BY flavor, produced_at, id",
         )
         .fetch_all(&self.pool)
         .await?
-        {
-            let order_id = row.order_id as u32;
-            let notify = match row.kind.as_str() {
-                "discord" => Notify::Discord {
-                    channel_id: required_column(row.discord_channel_id, "discord_channel_id")?
-                        .parse()?,
-                    user_id: required_column(row.discord_user_id, "discord_user_id")?.parse()?,
-                },
-                "line" => Notify::Line {
-                    user_id: required_column(row.line_user_id, "line_user_id")?,
-                },
-                kind => anyhow::bail!("invalid notification kind: {kind}"),
-            };
-            notifications_by_order_id
-                .entry(order_id)
-                .or_default()
-                .insert(notify);
-        }
-
-        snapshot.orders = sqlx::query!(
-            "SELECT id, status, ordered_at, ready_at, completed_at, is_priority FROM orders ORDER BY id",
+        .into_iter()
+        .map(|row| {
+            Ok(StockLot {
+                flavor: Flavor::from_db_str(&row.flavor)?,
+                quantity: row.quantity as usize,
+                produced_at: parse_datetime(row.produced_at)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        snapshot.stock_adjustments = sqlx::query!(
+            "SELECT id, flavor, delta, reason, note, recorded_at FROM stock_adjustments ORDER BY id",
         )
         .fetch_all(&self.pool)
         .await?
         .into_iter()
         .map(|row| {
-            let id = row.id as u32;
-            Ok(Order {
-                id,
-                items: items_by_order_id.remove(&id).unwrap_or_default(),
-                status: OrderStatus::from_db_str(&row.status)?,
-                ordered_at: parse_datetime(row.ordered_at)?,
-                ready_at: parse_optional_datetime(row.ready_at)?,
-                completed_at: parse_optional_datetime(row.completed_at)?,
-                notify: notifications_by_order_id.remove(&id).unwrap_or_default(),
-                is_priority: row.is_priority != 0,
+            Ok(StockAdjustment {
+                id: row.id as u32,
+                flavor: Flavor::from_db_str(&row.flavor)?,
+                delta: row.delta,
+                reason: StockReason::from_db_str(&row.reason)?,
+                note: row.note,
+                recorded_at: parse_datetime(row.recorded_at)?,
             })
         })
         .collect::<anyhow::Result<Vec<_>>>()?;
 
+        for row in sqlx::query!(
+            "SELECT flavor, cooking_time_minutes, quantity_per_batch, daily_limit, is_sold_out, station_id FROM flavor_configs",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        {
+            let flavor = Flavor::from_db_str(&required_column(row.flavor, "flavor")?)?;
+            snapshot.flavor_configs.insert(
+                flavor,
+                FlavorConfig {
+                    cooking_time_minutes: row.cooking_time_minutes as u32,
+                    quantity_per_batch: row.quantity_per_batch as u32,
+                    daily_limit: row.daily_limit.map(|limit| limit as u32),
+                    is_sold_out: row.is_sold_out != 0,
+                    station_id: row.station_id.map(|id| id as u32),
+                },
+            );
+        }
+
+        let orders = sqlx::query_as!(
+            OrderRow,
+            "SELECT id, status, ordered_at, ready_at, completed_at, priority, total_price, pricing_rule, discount_amount, payment_method, refunded_at, display_number, business_day_id, pickup_at, release_at, no_show_at, expired_at FROM orders ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let items = sqlx::query_as!(
+            OrderItemRow,
+            "SELECT order_id, flavor, quantity, handed_over_quantity FROM order_items",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let notifications = sqlx::query_as!(
+            NotificationRow,
+            "SELECT order_id, kind, discord_channel_id, discord_user_id, line_user_id FROM notifications",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        snapshot.orders = orders_from_rows(orders, items, notifications)?;
         Ok(snapshot)
     }
 
-    async fn replace_snapshot(&self, snapshot: &OrderSystemSnapshot) -> anyhow::Result<()> {
+    async fn apply_changes(
+        &self,
+        changes: &[SnapshotChange],
+        events: &[OrderEvent],
+    ) -> anyhow::Result<()> {
         let mut tx = self.pool.begin().await?;
 
-        sqlx::query!("DELETE FROM notifications")
-            .execute(&mut *tx)
-            .await?;
-        sqlx::query!("DELETE FROM order_items")
-            .execute(&mut *tx)
-            .await?;
-        sqlx::query!("DELETE FROM orders").execute(&mut *tx).await?;
-        sqlx::query!("DELETE FROM stock").execute(&mut *tx).await?;
-        sqlx::query!("DELETE FROM flavor_configs")
-            .execute(&mut *tx)
-            .await?;
-
-        for flavor in Flavor::iter() {
-            sqlx::query!(
-                "INSERT INTO stock (flavor, unallocated_quantity) VALUES (?, ?)",
-                flavor.as_db_str(),
-                snapshot.unallocated_stock[flavor] as i64,
-            )
-            .execute(&mut *tx)
-            .await?;
-
-            let config = snapshot.flavor_configs[flavor];
-            sqlx::query!(
-                "INSERT INTO flavor_configs (flavor, cooking_time_minutes, quantity_per_batch) VALUES (?, ?, ?)",
-                flavor.as_db_str(),
-                config.cooking_time_minutes as i64,
-                config.quantity_per_batch as i64,
-            )
-            .execute(&mut *tx)
-            .await?;
-        }
-
-        for order in &snapshot.orders {
-            sqlx::query!(
-                "INSERT INTO orders (id, status, ordered_at, ready_at, completed_at, is_priority) VALUES (?, ?, ?, ?, ?, ?)",
-                order.id as i64,
-                order.status.as_db_str(),
-                format_datetime(order.ordered_at),
-                order.ready_at.map(format_datetime),
-                order.completed_at.map(format_datetime),
-                i64::from(order.is_priority),
-            )
-            .execute(&mut *tx)
-            .await?;
-
-            for item in &order.items {
-                sqlx::query!(
-                    "INSERT INTO order_items (order_id, flavor, quantity) VALUES (?, ?, ?)",
-                    order.id as i64,
-                    item.flavor.as_db_str(),
-                    item.quantity as i64,
-                )
-                .execute(&mut *tx)
-                .await?;
-            }
-
-            for notify in &order.notify {
-                match notify {
-                    Notify::Discord {
-                        channel_id,
-                        user_id,
-                    } => {
+        for change in changes {
+            match change {
+                SnapshotChange::UpsertFlavor(entry) => {
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO flavors (id, display_name, sort_order, is_active, unit_price) VALUES (?, ?, ?, ?, ?)
+                        ON CONFLICT (id) DO UPDATE SET display_name = excluded.display_name, sort_order = excluded.sort_order, is_active = excluded.is_active, unit_price = excluded.unit_price
+                        "#,
+                        entry.id.as_db_str(),
+                        entry.display_name,
+                        entry.sort_order,
+                        i64::from(entry.is_active),
+                        i64::from(entry.unit_price),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::UpsertPricingRule(rule) => {
+                    let (bundle_quantity, bundle_price, discount_percent, discount_amount) =
+                        match rule.kind {
+                            PricingRuleKind::Bundle { quantity, price } => (
+                                Some(i64::from(quantity)),
+                                Some(i64::from(price)),
+                                None,
+                                None,
+                            ),
+                            PricingRuleKind::PercentageDiscount { percent } => {
+                                (None, None, Some(i64::from(percent)), None)
+                            }
+                            PricingRuleKind::FixedDiscount { amount } => {
+                                (None, None, None, Some(i64::from(amount)))
+                            }
+                        };
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO pricing_rules (code, name, kind, bundle_quantity, bundle_price, discount_percent, discount_amount, is_active) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                        ON CONFLICT (code) DO UPDATE SET name = excluded.name, kind = excluded.kind, bundle_quantity = excluded.bundle_quantity, bundle_price = excluded.bundle_price, discount_percent = excluded.discount_percent, discount_amount = excluded.discount_amount, is_active = excluded.is_active
+                        "#,
+                        rule.code,
+                        rule.name,
+                        rule.kind.as_db_str(),
+                        bundle_quantity,
+                        bundle_price,
+                        discount_percent,
+                        discount_amount,
+                        i64::from(rule.is_active),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::UpsertStation(station) => {
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO stations (id, name, plate_count) VALUES (?, ?, ?)
+                        ON CONFLICT (id) DO UPDATE SET name = excluded.name, plate_count = excluded.plate_count
+                        "#,
+                        i64::from(station.id),
+                        station.name,
+                        i64::from(station.plate_count),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::UpsertFlavorConfig { flavor, config } => {
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO flavor_configs (flavor, cooking_time_minutes, quantity_per_batch, daily_limit, is_sold_out, station_id) VALUES (?, ?, ?, ?, ?, ?)
+                        ON CONFLICT (flavor) DO UPDATE SET cooking_time_minutes = excluded.cooking_time_minutes, quantity_per_batch = excluded.quantity_per_batch, daily_limit = excluded.daily_limit, is_sold_out = excluded.is_sold_out, station_id = excluded.station_id
+                        "#,
+                        flavor.as_db_str(),
+                        config.cooking_time_minutes as i64,
+                        config.quantity_per_batch as i64,
+                        config.daily_limit.map(i64::from),
+                        i64::from(config.is_sold_out),
+                        config.station_id.map(i64::from),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::UpsertBusinessDay(day) => {
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO business_days (id, business_date, opened_at, closed_at, stock_disposition) VALUES (?, ?, ?, ?, ?)
+                        ON CONFLICT (id) DO UPDATE SET business_date = excluded.business_date, opened_at = excluded.opened_at, closed_at = excluded.closed_at, stock_disposition = excluded.stock_disposition
+                        "#,
+                        i64::from(day.id),
+                        day.date.format("%Y-%m-%d").to_string(),
+                        format_datetime(day.opened_at),
+                        day.closed_at.map(format_datetime),
+                        day.stock_disposition.map(StockDisposition::as_db_str),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::UpsertBatch(batch) => {
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO batches (id, flavor, quantity, started_at, finished_at) VALUES (?, ?, ?, ?, ?)
+                        ON CONFLICT (id) DO UPDATE SET flavor = excluded.flavor, quantity = excluded.quantity, started_at = excluded.started_at, finished_at = excluded.finished_at
+                        "#,
+                        i64::from(batch.id),
+                        batch.flavor.as_db_str(),
+                        batch.quantity as i64,
+                        format_datetime(batch.started_at),
+                        batch.finished_at.map(format_datetime),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::SetStock { flavor, quantity } => {
+                    let quantity = *quantity as i64;
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO stock (flavor, unallocated_quantity) VALUES (?, ?)
+                        ON CONFLICT (flavor) DO UPDATE SET unallocated_quantity = excluded.unallocated_quantity
+                        "#,
+                        flavor.as_db_str(),
+                        quantity,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::ReplaceStockLots { flavor, lots } => {
+                    sqlx::query!(
+                        "DELETE FROM stock_lots WHERE flavor = ?",
+                        flavor.as_db_str()
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    for lot in lots {
                         sqlx::query!(
-                            "INSERT INTO notifications (order_id, kind, discord_channel_id, discord_user_id) VALUES (?, 'discord', ?, ?)",
-                            order.id as i64,
-                            channel_id.to_string(),
-                            user_id.to_string(),
+                            "INSERT INTO stock_lots (flavor, quantity, produced_at) VALUES (?, ?, ?)",
+                            lot.flavor.as_db_str(),
+                            lot.quantity as i64,
+                            format_datetime(lot.produced_at),
                         )
                         .execute(&mut *tx)
                         .await?;
                     }
-                    Notify::Line { user_id } => {
-                        sqlx::query!(
-                            "INSERT INTO notifications (order_id, kind, line_user_id) VALUES (?, 'line', ?)",
-                            order.id as i64,
-                            user_id,
-                        )
+                }
+                SnapshotChange::InsertStockAdjustment(adjustment) => {
+                    sqlx::query!(
+                        "INSERT INTO stock_adjustments (id, flavor, delta, reason, note, recorded_at) VALUES (?, ?, ?, ?, ?, ?)",
+                        i64::from(adjustment.id),
+                        adjustment.flavor.as_db_str(),
+                        adjustment.delta,
+                        adjustment.reason.as_db_str(),
+                        adjustment.note,
+                        format_datetime(adjustment.recorded_at),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::InsertOrder(order) => {
+                    sqlx::query!(
+                        "INSERT INTO orders (id, status, ordered_at, ready_at, completed_at, priority, total_price, pricing_rule, discount_amount, payment_method, refunded_at, display_number, business_day_id, pickup_at, release_at, no_show_at, expired_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                        order.id as i64,
+                        order.status.as_db_str(),
+                        format_datetime(order.ordered_at),
+                        order.ready_at.map(format_datetime),
+                        order.completed_at.map(format_datetime),
+                        order.priority.as_db_str(),
+                        i64::from(order.total_price),
+                        order.pricing_rule,
+                        i64::from(order.discount_amount),
+                        order.payment_method.map(PaymentMethod::as_db_str),
+                        order.refunded_at.map(format_datetime),
+                        order.display_number.as_str(),
+                        order.business_day_id.map(i64::from),
+                        order.pickup_at.map(format_datetime),
+                        order.release_at.map(format_datetime),
+                        order.no_show_at.map(format_datetime),
+                        order.expired_at.map(format_datetime),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    insert_order_items(&mut tx, order.id, &order.items, &order.handed_over).await?;
+                    insert_notifications(&mut tx, order.id, &order.notify).await?;
+                }
+                SnapshotChange::UpdateOrderStatus {
+                    id,
+                    status,
+                    ready_at,
+                    completed_at,
+                    no_show_at,
+                    expired_at,
+                } => {
+                    sqlx::query!(
+                        "UPDATE orders SET status = ?, ready_at = ?, completed_at = ?, no_show_at = ?, expired_at = ? WHERE id = ?",
+                        status.as_db_str(),
+                        ready_at.map(format_datetime),
+                        completed_at.map(format_datetime),
+                        no_show_at.map(format_datetime),
+                        expired_at.map(format_datetime),
+                        i64::from(*id),
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::UpdateOrder(order) => {
+                    sqlx::query!(
+                        "UPDATE orders SET status = ?, ordered_at = ?, ready_at = ?, completed_at = ?, priority = ?, total_price = ?, pricing_rule = ?, discount_amount = ?, payment_method = ?, refunded_at = ?, display_number = ?, business_day_id = ?, pickup_at = ?, release_at = ?, no_show_at = ?, expired_at = ? WHERE id = ?",
+                        order.status.as_db_str(),
+                        format_datetime(order.ordered_at),
+                        order.ready_at.map(format_datetime),
+                        order.completed_at.map(format_datetime),
+                        order.priority.as_db_str(),
+                        i64::from(order.total_price),
+                        order.pricing_rule,
+                        i64::from(order.discount_amount),
+                        order.payment_method.map(PaymentMethod::as_db_str),
+                        order.refunded_at.map(format_datetime),
+                        order.display_number.as_str(),
+                        order.business_day_id.map(i64::from),
+                        order.pickup_at.map(format_datetime),
+                        order.release_at.map(format_datetime),
+                        order.no_show_at.map(format_datetime),
+                        order.expired_at.map(format_datetime),
+                        order.id as i64,
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                SnapshotChange::SetOrderItems {
+                    order_id,
+                    items,
+                    handed_over,
+                } => {
+                    let id = i64::from(*order_id);
+                    sqlx::query!("DELETE FROM order_items WHERE order_id = ?", id)
                         .execute(&mut *tx)
                         .await?;
-                    }
+                    insert_order_items(&mut tx, *order_id, items, handed_over).await?;
+                }
+                SnapshotChange::SetOrderNotifications { order_id, notify } => {
+                    let id = i64::from(*order_id);
+                    sqlx::query!("DELETE FROM notifications WHERE order_id = ?", id)
+                        .execute(&mut *tx)
+                        .await?;
+                    insert_notifications(&mut tx, *order_id, notify).await?;
                 }
             }
         }
+        for event in events {
+            sqlx::query!(
+                "INSERT INTO order_events (order_id, kind, actor_kind, actor_id, flavor, before_value, after_value, reason, recorded_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                event.order_id.map(i64::from),
+                event.kind.as_db_str(),
+                event.actor.as_db_str(),
+                event.actor.db_id(),
+                event.flavor.as_ref().map(Flavor::as_db_str),
+                event.before,
+                event.after,
+                event.reason.map(EventReason::as_db_str),
+                format_datetime(event.recorded_at),
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
 
         tx.commit().await?;
         Ok(())
     }
 
+    async fn order_history(&self, order_id: u32) -> anyhow::Result<Vec<OrderEvent>> {
+        let order_id = i64::from(order_id);
+        sqlx::query_as!(
+            OrderEventRow,
+            "SELECT order_id, kind, actor_kind, actor_id, flavor, before_value, after_value, reason, recorded_at FROM order_events WHERE order_id = ? ORDER BY id",
+            order_id,
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(event_from_row)
+        .collect()
+    }
+
+    // Timestamps are stored as RFC 3339 in UTC, which sorts as text.
+    async fn events(&self, filter: EventFilter) -> anyhow::Result<Vec<OrderEvent>> {
+        let from = filter.from.map(format_datetime);
+        let to = filter.to.map(format_datetime);
+        let kind = filter.kind.map(OrderEventKind::as_db_str);
+        sqlx::query_as!(
+            OrderEventRow,
+            "SELECT order_id, kind, actor_kind, actor_id, flavor, before_value, after_value, reason, recorded_at FROM order_events WHERE (?1 IS NULL OR recorded_at >= ?1) AND (?2 IS NULL OR recorded_at < ?2) AND (?3 IS NULL OR kind = ?3) ORDER BY id",
+            from,
+            to,
+            kind,
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(event_from_row)
+        .collect()
+    }
+
     async fn record_notification_delivery(
         &self,
         log: &NotificationDeliveryLog,
@@ -255,8 +569,20 @@ impl OrderRepository for SqliteRepository {
         SqliteRepository::load_snapshot(self).await
     }
 
-    async fn replace_snapshot(&self, snapshot: &OrderSystemSnapshot) -> anyhow::Result<()> {
-        SqliteRepository::replace_snapshot(self, snapshot).await
+    async fn apply_changes(
+        &self,
+        changes: &[SnapshotChange],
+        events: &[OrderEvent],
+    ) -> anyhow::Result<()> {
+        SqliteRepository::apply_changes(self, changes, events).await
+    }
+
+    async fn order_history(&self, order_id: u32) -> anyhow::Result<Vec<OrderEvent>> {
+        SqliteRepository::order_history(self, order_id).await
+    }
+
+    async fn events(&self, filter: EventFilter) -> anyhow::Result<Vec<OrderEvent>> {
+        SqliteRepository::events(self, filter).await
     }
 }
 
@@ -270,6 +596,208 @@ impl NotificationLog for SqliteRepository {
     }
 }
 
+struct OrderRow {
+    id: i64,
+    status: String,
+    ordered_at: String,
+    ready_at: Option<String>,
+    completed_at: Option<String>,
+    priority: String,
+    total_price: i64,
+    pricing_rule: Option<String>,
+    discount_amount: i64,
+    payment_method: Option<String>,
+    refunded_at: Option<String>,
+    display_number: String,
+    business_day_id: Option<i64>,
+    pickup_at: Option<String>,
+    release_at: Option<String>,
+    no_show_at: Option<String>,
+    expired_at: Option<String>,
+}
+
+struct OrderItemRow {
+    order_id: i64,
+    flavor: String,
+    quantity: i64,
+    handed_over_quantity: i64,
+}
+
+struct NotificationRow {
+    order_id: i64,
+    kind: String,
+    discord_channel_id: Option<String>,
+    discord_user_id: Option<String>,
+    line_user_id: Option<String>,
+}
+
+struct OrderEventRow {
+    order_id: Option<i64>,
+    kind: String,
+    actor_kind: String,
+    actor_id: Option<String>,
+    flavor: Option<String>,
+    before_value: Option<String>,
+    after_value: Option<String>,
+    reason: Option<String>,
+    recorded_at: String,
+}
+
+fn event_from_row(row: OrderEventRow) -> anyhow::Result<OrderEvent> {
+    Ok(OrderEvent {
+        order_id: row.order_id.map(|id| id as u32),
+        kind: OrderEventKind::from_db_str(&row.kind)?,
+        actor: Actor::from_db(&row.actor_kind, row.actor_id)?,
+        flavor: row.flavor.as_deref().map(Flavor::from_db_str).transpose()?,
+        before: row.before_value,
+        after: row.after_value,
+        reason: row
+            .reason
+            .as_deref()
+            .map(EventReason::from_db_str)
+            .transpose()?,
+        recorded_at: parse_datetime(row.recorded_at)?,
+    })
+}
+
+fn orders_from_rows(
+    orders: Vec<OrderRow>,
+    items: Vec<OrderItemRow>,
+    notifications: Vec<NotificationRow>,
+) -> anyhow::Result<Vec<Order>> {
+    let mut items_by_order_id = HashMap::<u32, Vec<Item>>::new();
+    let mut handed_over_by_order_id = HashMap::<u32, Vec<Item>>::new();
+    for row in items {
+        let order_id = row.order_id as u32;
+        let flavor = Flavor::from_db_str(&row.flavor)?;
+        if row.handed_over_quantity > 0 {
+            handed_over_by_order_id
+                .entry(order_id)
+                .or_default()
+                .push(Item {
+                    flavor: flavor.clone(),
+                    quantity: row.handed_over_quantity as usize,
+                });
+        }
+        let quantity = row.quantity as usize;
+        items_by_order_id
+            .entry(order_id)
+            .or_default()
+            .push(Item { flavor, quantity });
+    }
+
+    let mut notifications_by_order_id = HashMap::<u32, HashSet<Notify>>::new();
+    for row in notifications {
+        let order_id = row.order_id as u32;
+        let notify = match row.kind.as_str() {
+            "discord" => Notify::Discord {
+                channel_id: required_column(row.discord_channel_id, "discord_channel_id")?
+                    .parse()?,
+                user_id: required_column(row.discord_user_id, "discord_user_id")?.parse()?,
+            },
+            "line" => Notify::Line {
+                user_id: required_column(row.line_user_id, "line_user_id")?,
+            },
+            kind => anyhow::bail!("invalid notification kind: {kind}"),
+        };
+        notifications_by_order_id
+            .entry(order_id)
+            .or_default()
+            .insert(notify);
+    }
+
+    orders
+        .into_iter()
+        .map(|row| {
+            let id = row.id as u32;
+            Ok(Order {
+                id,
+                items: items_by_order_id.remove(&id).unwrap_or_default(),
+                status: OrderStatus::from_db_str(&row.status)?,
+                ordered_at: parse_datetime(row.ordered_at)?,
+                ready_at: parse_optional_datetime(row.ready_at)?,
+                completed_at: parse_optional_datetime(row.completed_at)?,
+                notify: notifications_by_order_id.remove(&id).unwrap_or_default(),
+                priority: PriorityTier::from_db_str(&row.priority)?,
+                total_price: row.total_price as u32,
+                pricing_rule: row.pricing_rule,
+                discount_amount: row.discount_amount as u32,
+                payment_method: row
+                    .payment_method
+                    .as_deref()
+                    .map(PaymentMethod::from_db_str)
+                    .transpose()?,
+                refunded_at: parse_optional_datetime(row.refunded_at)?,
+                handed_over: handed_over_by_order_id.remove(&id).unwrap_or_default(),
+                display_number: DisplayOrderNumber::new(row.display_number),
+                business_day_id: row.business_day_id.map(|id| id as u32),
+                pickup_at: parse_optional_datetime(row.pickup_at)?,
+                release_at: parse_optional_datetime(row.release_at)?,
+                no_show_at: parse_optional_datetime(row.no_show_at)?,
+                expired_at: parse_optional_datetime(row.expired_at)?,
+            })
+        })
+        .collect()
+}
+
+async fn insert_order_items(
+    tx: &mut SqliteConnection,
+    order_id: u32,
+    items: &[Item],
+    handed_over: &[Item],
+) -> anyhow::Result<()> {
+    for item in items {
+        let handed_over_quantity = handed_over
+            .iter()
+            .find(|handed| handed.flavor == item.flavor)
+            .map_or(0, |handed| handed.quantity);
+        sqlx::query!(
+            "INSERT INTO order_items (order_id, flavor, quantity, handed_over_quantity) VALUES (?, ?, ?, ?)",
+            order_id as i64,
+            item.flavor.as_db_str(),
+            item.quantity as i64,
+            handed_over_quantity as i64,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn insert_notifications(
+    tx: &mut SqliteConnection,
+    order_id: u32,
+    notify: &HashSet<Notify>,
+) -> anyhow::Result<()> {
+    for notify in notify {
+        match notify {
+            Notify::Discord {
+                channel_id,
+                user_id,
+            } => {
+                sqlx::query!(
+                    "INSERT INTO notifications (order_id, kind, discord_channel_id, discord_user_id) VALUES (?, 'discord', ?, ?)",
+                    order_id as i64,
+                    channel_id.to_string(),
+                    user_id.to_string(),
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            Notify::Line { user_id } => {
+                sqlx::query!(
+                    "INSERT INTO notifications (order_id, kind, line_user_id) VALUES (?, 'line', ?)",
+                    order_id as i64,
+                    user_id,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn parse_datetime(value: String) -> anyhow::Result<DateTime<Utc>> {
     Ok(DateTime::parse_from_rfc3339(&value)?.with_timezone(&Utc))
 }
@@ -282,17 +810,28 @@ fn required_column(value: Option<String>, name: &str) -> anyhow::Result<String>
     value.ok_or_else(|| anyhow::anyhow!("{name} must not be null"))
 }
 
+fn required_integer(value: Option<i64>, name: &str) -> anyhow::Result<i64> {
+    value.ok_or_else(|| anyhow::anyhow!("{name} must not be null"))
+}
+
 fn format_datetime(value: DateTime<Utc>) -> String {
     value.to_rfc3339()
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
     use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
 
+    use crate::domain::business_day::{BusinessDay, StockDisposition};
     use crate::domain::notification::{NotificationDeliveryLog, NotificationDeliveryStatus};
-    use crate::domain::snapshot::{Notify, Order, OrderStatus, OrderSystemSnapshot};
+    use crate::domain::order_event::{Actor, EventFilter, OrderEvent, OrderEventKind};
+    use crate::domain::order_number::DisplayOrderNumber;
+    use crate::domain::snapshot::{
+        Flavor, Notify, Order, OrderStatus, OrderSystemSnapshot, PricingRule, PricingRuleKind,
+        Station, StockAdjustment, StockLot, StockReason,
+    };
+    use crate::domain::snapshot_change::{SnapshotChange, changes_between};
 
     use super::SqliteRepository;
 
@@ -303,10 +842,21 @@ mod tests {
             .await?;
         sqlx::migrate!("./migrations").run(&pool).await?;
         let repository = SqliteRepository::new(pool.clone());
-        repository.replace_snapshot(&snapshot_with_order()).await?;
+        save(&repository, &snapshot_with_order()).await?;
         Ok((pool, repository))
     }
 
+    // Writes `snapshot` over what is stored, the way mutations do.
+    async fn save(
+        repository: &SqliteRepository,
+        snapshot: &OrderSystemSnapshot,
+    ) -> anyhow::Result<()> {
+        let stored = repository.load_snapshot().await?;
+        repository
+            .apply_changes(&changes_between(&stored, snapshot), &[])
+            .await
+    }
+
     fn snapshot_with_order() -> OrderSystemSnapshot {
         OrderSystemSnapshot {
             orders: vec![Order {
@@ -317,7 +867,19 @@ mod tests {
                 ready_at: Some(Utc::now()),
                 completed_at: None,
                 notify: Default::default(),
-                is_priority: false,
+                priority: Default::default(),
+                total_price: 0,
+                pricing_rule: None,
+                discount_amount: 0,
+                payment_method: None,
+                refunded_at: None,
+                handed_over: Vec::new(),
+                display_number: DisplayOrderNumber::new("01"),
+                business_day_id: None,
+                pickup_at: None,
+                release_at: None,
+                no_show_at: None,
+                expired_at: None,
             }],
             ..Default::default()
         }
@@ -366,6 +928,249 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn load_snapshot_reads_seeded_menu_catalog() -> anyhow::Result<()> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        let repository = SqliteRepository::new(pool);
+
+        let snapshot = repository.load_snapshot().await?;
+
+        let ids = snapshot
+            .menu
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["tsubuan", "custard", "kurikinton"]);
+        let kurikinton = Flavor::new("kurikinton");
+        assert_eq!(snapshot.flavor_configs[&kurikinton].quantity_per_batch, 2);
+        // Prices are left for staff to set through the menu API.
+        assert!(snapshot.menu.iter().all(|entry| entry.unit_price == 0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn saved_snapshot_round_trips_pricing_rules() -> anyhow::Result<()> {
+        let (_pool, repository) = repository().await?;
+        let mut snapshot = repository.load_snapshot().await?;
+        snapshot.pricing_rules.push(PricingRule {
+            code: "STAFF".to_owned(),
+            name: "まかない".to_owned(),
+            kind: PricingRuleKind::PercentageDiscount { percent: 50 },
+            is_active: true,
+        });
+        snapshot.orders[0].pricing_rule = Some("STAFF".to_owned());
+        snapshot.orders[0].discount_amount = 100;
+
+        save(&repository, &snapshot).await?;
+        let loaded = repository.load_snapshot().await?;
+
+        assert_eq!(loaded.pricing_rules, snapshot.pricing_rules);
+        assert_eq!(loaded.orders[0].pricing_rule.as_deref(), Some("STAFF"));
+        assert_eq!(loaded.orders[0].discount_amount, 100);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn saved_snapshot_round_trips_stations() -> anyhow::Result<()> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        let repository = SqliteRepository::new(pool);
+        let mut snapshot = repository.load_snapshot().await?;
+        let tsubuan = Flavor::new("tsubuan");
+        snapshot.stations.push(Station {
+            id: 1,
+            name: "鉄板A".to_owned(),
+            plate_count: 3,
+        });
+        snapshot
+            .flavor_configs
+            .get_mut(&tsubuan)
+            .expect("seeded flavor")
+            .station_id = Some(1);
+
+        save(&repository, &snapshot).await?;
+        let loaded = repository.load_snapshot().await?;
+
+        assert_eq!(loaded.stations, snapshot.stations);
+        assert_eq!(loaded.flavor_configs[&tsubuan].station_id, Some(1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn saved_snapshot_round_trips_stock_lots_and_adjustments() -> anyhow::Result<()> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        let repository = SqliteRepository::new(pool);
+        let mut snapshot = repository.load_snapshot().await?;
+        let tsubuan = Flavor::new("tsubuan");
+        let produced_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        snapshot.unallocated_stock.insert(tsubuan.clone(), 3);
+        snapshot.stock_lots.push(StockLot {
+            flavor: tsubuan.clone(),
+            quantity: 3,
+            produced_at,
+        });
+        snapshot.stock_adjustments.push(StockAdjustment {
+            id: 1,
+            flavor: tsubuan,
+            delta: -2,
+            reason: StockReason::Burnt,
+            note: Some("焦げ".to_owned()),
+            recorded_at: produced_at,
+        });
+
+        save(&repository, &snapshot).await?;
+        let loaded = repository.load_snapshot().await?;
+
+        assert_eq!(loaded.stock_lots, snapshot.stock_lots);
+        assert_eq!(loaded.stock_adjustments, snapshot.stock_adjustments);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn saved_snapshot_round_trips_business_days() -> anyhow::Result<()> {
+        let (_pool, repository) = repository().await?;
+        let mut snapshot = repository.load_snapshot().await?;
+        let opened_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        snapshot.business_days.push(BusinessDay {
+            id: 1,
+            date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            opened_at,
+            closed_at: Some(opened_at + Duration::hours(8)),
+            stock_disposition: Some(StockDisposition::WriteOff),
+        });
+        snapshot.orders[0].business_day_id = Some(1);
+
+        save(&repository, &snapshot).await?;
+        let loaded = repository.load_snapshot().await?;
+
+        assert_eq!(loaded.business_days, snapshot.business_days);
+        assert_eq!(loaded.orders[0].business_day_id, Some(1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn saved_snapshot_round_trips_pickup_slots() -> anyhow::Result<()> {
+        let (_pool, repository) = repository().await?;
+        let mut snapshot = repository.load_snapshot().await?;
+        let pickup_at = Utc.with_ymd_and_hms(2026, 1, 1, 5, 0, 0).unwrap();
+        snapshot.orders[0].pickup_at = Some(pickup_at);
+        snapshot.orders[0].release_at = Some(pickup_at - Duration::minutes(30));
+
+        save(&repository, &snapshot).await?;
+        let loaded = repository.load_snapshot().await?;
+
+        assert_eq!(loaded.orders[0].pickup_at, Some(pickup_at));
+        assert_eq!(loaded.orders[0].release_at, snapshot.orders[0].release_at);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn status_change_updates_only_its_order() -> anyhow::Result<()> {
+        let (_pool, repository) = repository().await?;
+        let mut snapshot = repository.load_snapshot().await?;
+        let ready_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        snapshot.orders[0].status = OrderStatus::Completed;
+        let waiting = Order {
+            status: OrderStatus::Waiting,
+            ready_at: None,
+            ..snapshot.orders[0].clone()
+        };
+        snapshot.orders.extend([
+            Order {
+                id: 2,
+                ..waiting.clone()
+            },
+            Order { id: 3, ..waiting },
+        ]);
+        save(&repository, &snapshot).await?;
+
+        repository
+            .apply_changes(
+                &[SnapshotChange::UpdateOrderStatus {
+                    id: 3,
+                    status: OrderStatus::Ready,
+                    ready_at: Some(ready_at),
+                    completed_at: None,
+                    no_show_at: None,
+                    expired_at: None,
+                }],
+                &[],
+            )
+            .await?;
+        let loaded = repository.load_snapshot().await?;
+
+        assert_eq!(loaded.orders[2].status, OrderStatus::Ready);
+        assert_eq!(loaded.orders[2].ready_at, Some(ready_at));
+        assert_eq!(loaded.orders[..2], snapshot.orders[..2]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn order_history_is_append_only() -> anyhow::Result<()> {
+        let (pool, repository) = repository().await?;
+        let recorded_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        let events = [
+            OrderEvent {
+                order_id: Some(1),
+                kind: OrderEventKind::StatusChanged,
+                actor: Actor::Line {
+                    user_id: "line-user".to_owned(),
+                },
+                flavor: None,
+                before: Some("ready".to_owned()),
+                after: Some("completed".to_owned()),
+                reason: None,
+                recorded_at,
+            },
+            OrderEvent::cancelled_at_closing(1, OrderStatus::Waiting, &Actor::Staff, recorded_at),
+            OrderEvent::stock_change(
+                OrderEventKind::StockAdjusted,
+                &Flavor::new("tsubuan"),
+                3,
+                2,
+                &Actor::Discord { user_id: 42 },
+                recorded_at,
+            ),
+        ];
+        repository.apply_changes(&[], &events).await?;
+
+        assert_eq!(repository.order_history(1).await?, events[..2]);
+        let stock_events = EventFilter {
+            kind: Some(OrderEventKind::StockAdjusted),
+            ..Default::default()
+        };
+        assert_eq!(repository.events(stock_events).await?, events[2..]);
+        let later = EventFilter {
+            from: Some(recorded_at + Duration::seconds(1)),
+            ..Default::default()
+        };
+        assert!(repository.events(later).await?.is_empty());
+        assert!(
+            sqlx::query("DELETE FROM order_events")
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+        assert!(
+            sqlx::query("UPDATE order_events SET after_value = 'cancelled'")
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
     async fn latest_notification_delivery(
         pool: &SqlitePool,
     ) -> anyhow::Result<(String, Option<String>)> {
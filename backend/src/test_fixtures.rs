@@ -0,0 +1,80 @@
+//! Menu and orders shared by the unit tests.
+
+use chrono::{Duration, TimeZone, Utc};
+
+use crate::domain::order_number::DisplayOrderNumber;
+use crate::domain::snapshot::{
+    Flavor, FlavorConfig, Item, MenuFlavor, Order, OrderStatus, OrderSystemSnapshot,
+};
+
+pub fn tsubuan() -> Flavor {
+    Flavor::new("tsubuan")
+}
+
+/// Active flavor at 200 yen, named after its id.
+pub fn menu_flavor(id: &str, sort_order: i64) -> MenuFlavor {
+    MenuFlavor {
+        id: Flavor::new(id),
+        display_name: id.to_owned(),
+        sort_order,
+        is_active: true,
+        unit_price: 200,
+    }
+}
+
+/// Nine pieces per 15-minute batch, without a station or a daily limit.
+pub fn flavor_config() -> FlavorConfig {
+    FlavorConfig {
+        cooking_time_minutes: 15,
+        quantity_per_batch: 9,
+        daily_limit: None,
+        is_sold_out: false,
+        station_id: None,
+    }
+}
+
+/// Snapshot selling つぶあん only.
+pub fn snapshot_with_orders(orders: Vec<Order>) -> OrderSystemSnapshot {
+    let mut snapshot = OrderSystemSnapshot {
+        orders,
+        ..Default::default()
+    };
+    snapshot.add_flavor(
+        MenuFlavor {
+            display_name: "つぶあん".to_owned(),
+            ..menu_flavor("tsubuan", 1)
+        },
+        flavor_config(),
+    );
+    snapshot
+}
+
+/// Waiting order for `quantity` つぶあん, taken `id` seconds past 2026-01-01 00:00 UTC.
+pub fn waiting_order(id: u32, quantity: usize) -> Order {
+    Order {
+        id,
+        items: vec![Item {
+            flavor: tsubuan(),
+            quantity,
+        }],
+        status: OrderStatus::Waiting,
+        ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+            + Duration::seconds(id.into()),
+        ready_at: None,
+        completed_at: None,
+        notify: Default::default(),
+        priority: Default::default(),
+        total_price: 0,
+        pricing_rule: None,
+        discount_amount: 0,
+        payment_method: None,
+        refunded_at: None,
+        handed_over: Vec::new(),
+        display_number: DisplayOrderNumber::new(format!("{id:02}")),
+        business_day_id: None,
+        pickup_at: None,
+        release_at: None,
+        no_show_at: None,
+        expired_at: None,
+    }
+}
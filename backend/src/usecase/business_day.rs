@@ -0,0 +1,237 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::domain::availability::store_date;
+use crate::domain::business_day::{self, BusinessDay, BusinessDaySummary, StockDisposition};
+use crate::domain::order_status::{self, OrderTransition, PendingNotification};
+use crate::domain::snapshot::{Item, OrderStatus, OrderSystemSnapshot, StockReason};
+use crate::domain::stock;
+use crate::usecase::stock::discard_stock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusinessDayError {
+    /// The day opened on this date has not been closed yet.
+    AlreadyOpen(NaiveDate),
+    NotOpen,
+}
+
+pub fn open_business_day(
+    snapshot: &mut OrderSystemSnapshot,
+    now: DateTime<Utc>,
+) -> Result<BusinessDay, BusinessDayError> {
+    if let Some(day) = business_day::open_day(snapshot) {
+        return Err(BusinessDayError::AlreadyOpen(day.date));
+    }
+    let id = snapshot
+        .business_days
+        .iter()
+        .map(|day| day.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let day = BusinessDay {
+        id,
+        date: store_date(now),
+        opened_at: now,
+        closed_at: None,
+        stock_disposition: None,
+    };
+    snapshot.business_days.push(day.clone());
+    Ok(day)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosedBusinessDay {
+    pub summary: BusinessDaySummary,
+    /// Orders cancelled at closing, with the status they were in.
+    pub cancelled: Vec<(u32, OrderStatus)>,
+    /// Tells the customers of the cancelled orders.
+    pub notifications: Vec<PendingNotification>,
+}
+
+// Orders still in progress are cancelled so nothing is left on the display the next morning;
+// pieces set aside for them go back to stock before it is carried over or written off.
+pub fn close_business_day(
+    snapshot: &mut OrderSystemSnapshot,
+    stock_disposition: StockDisposition,
+    now: DateTime<Utc>,
+) -> Result<ClosedBusinessDay, BusinessDayError> {
+    let day = business_day::open_day(snapshot).ok_or(BusinessDayError::NotOpen)?;
+    let (id, date) = (day.id, day.date);
+
+    let mut cancelled = Vec::new();
+    let mut notifications = Vec::new();
+    let mut set_aside = Vec::<(Item, DateTime<Utc>)>::new();
+    for order in snapshot.orders.iter_mut().filter(|order| {
+        matches!(
+            order.status,
+            OrderStatus::Waiting | OrderStatus::Cooking | OrderStatus::Ready
+        )
+    }) {
+        let from = order_status::apply_transition(order, OrderTransition::Cancel, now)
+            .expect("orders in progress can be cancelled");
+        cancelled.push((order.id, from));
+        notifications.extend(order.notify.iter().cloned().map(|notify| PendingNotification {
+            order_id: order.id,
+            notify,
+            message: format!(
+                "申し訳ありません。本日の営業を終了したため、#{}番 のご注文はキャンセルとなりました。返金についてはスタッフまでお問い合わせください。",
+                order.display_number
+            ),
+        }));
+        if from == OrderStatus::Ready {
+            let ready_at = order.ready_at.unwrap_or(now);
+            set_aside.extend(order.outstanding().map(|(flavor, quantity)| {
+                (
+                    Item {
+                        flavor: flavor.clone(),
+                        quantity,
+                    },
+                    ready_at,
+                )
+            }));
+        }
+    }
+    for (item, ready_at) in set_aside {
+        stock::add_stock(snapshot, &item.flavor, item.quantity, ready_at);
+    }
+
+    if stock_disposition == StockDisposition::WriteOff {
+        let flavors = snapshot
+            .menu
+            .iter()
+            .map(|entry| entry.id.clone())
+            .collect::<Vec<_>>();
+        for flavor in flavors {
+            let quantity = snapshot.stock_of(&flavor);
+            if quantity > 0 {
+                discard_stock(
+                    snapshot,
+                    flavor,
+                    quantity,
+                    StockReason::Stale,
+                    Some("閉店時に廃棄".to_owned()),
+                    now,
+                )
+                .expect("stock of a menu flavor can be discarded");
+            }
+        }
+    }
+
+    let day = snapshot
+        .business_days
+        .iter_mut()
+        .find(|day| day.id == id)
+        .expect("open day must exist");
+    day.closed_at = Some(now);
+    day.stock_disposition = Some(stock_disposition);
+    Ok(ClosedBusinessDay {
+        summary: business_day::summarize(snapshot, date),
+        cancelled,
+        notifications,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::domain::availability::store_date;
+    use crate::domain::business_day::{self, StockDisposition};
+    use crate::domain::snapshot::{Notify, Order, OrderStatus};
+    use crate::domain::stock;
+    use crate::test_fixtures::{snapshot_with_orders, tsubuan, waiting_order};
+
+    use super::{BusinessDayError, close_business_day, open_business_day};
+
+    fn order(id: u32, status: OrderStatus, business_day_id: u32) -> Order {
+        Order {
+            status,
+            notify: [Notify::Line {
+                user_id: format!("user-{id}"),
+            }]
+            .into(),
+            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, id).unwrap(),
+            ready_at: (status == OrderStatus::Ready)
+                .then(|| Utc.with_ymd_and_hms(2026, 1, 1, 1, 30, 0).unwrap()),
+            total_price: 400,
+            business_day_id: Some(business_day_id),
+            ..waiting_order(id, 2)
+        }
+    }
+
+    #[test]
+    fn closing_cancels_leftovers_and_writes_off_stock() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        // 2026-01-01 10:00 JST
+        let opened_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        let day = open_business_day(&mut snapshot, opened_at).expect("open");
+        stock::add_stock(&mut snapshot, &tsubuan(), 1, opened_at);
+        snapshot.orders = vec![
+            order(1, OrderStatus::Completed, day.id),
+            order(2, OrderStatus::Ready, day.id),
+        ];
+        assert_eq!(
+            open_business_day(&mut snapshot, opened_at).err(),
+            Some(BusinessDayError::AlreadyOpen(day.date))
+        );
+
+        let closed = close_business_day(
+            &mut snapshot,
+            StockDisposition::WriteOff,
+            opened_at + Duration::hours(8),
+        )
+        .expect("close");
+        let summary = closed.summary;
+
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Cancelled);
+        assert_eq!(closed.cancelled, vec![(2, OrderStatus::Ready)]);
+        let told: Vec<_> = closed
+            .notifications
+            .iter()
+            .map(|notification| notification.order_id)
+            .collect();
+        assert_eq!(told, vec![2]);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+        assert_eq!(summary.completed_count, 1);
+        assert_eq!(summary.sold[0].quantity, 2);
+        assert_eq!(summary.revenue.cancelled_count, 1);
+        // The piece left over plus the two set aside for the cancelled order.
+        assert_eq!(summary.waste.total_quantity, 3);
+        assert!(business_day::is_locked(&snapshot, &snapshot.orders[0]));
+        assert_eq!(
+            close_business_day(
+                &mut snapshot,
+                StockDisposition::CarryOver,
+                opened_at + Duration::hours(9)
+            )
+            .err(),
+            Some(BusinessDayError::NotOpen)
+        );
+    }
+
+    #[test]
+    fn day_opened_after_midnight_keeps_reporting_under_its_date() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        // 2026-01-01 23:00 JST
+        let opened_at = Utc.with_ymd_and_hms(2026, 1, 1, 14, 0, 0).unwrap();
+        let day = open_business_day(&mut snapshot, opened_at).expect("open");
+        let after_midnight = opened_at + Duration::hours(2);
+        close_business_day(
+            &mut snapshot,
+            StockDisposition::CarryOver,
+            after_midnight + Duration::hours(1),
+        )
+        .expect("close");
+
+        assert_eq!(
+            business_day::business_date_at(&snapshot, after_midnight),
+            day.date
+        );
+        assert_ne!(store_date(after_midnight), day.date);
+        // Outside business hours the store date applies.
+        assert_eq!(
+            business_day::business_date_at(&snapshot, after_midnight + Duration::hours(2)),
+            store_date(after_midnight)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::domain::order_status::{self, StatusUpdate};
+use crate::domain::snapshot::{Flavor, FlavorConfig, MenuFlavor, OrderSystemSnapshot};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddFlavorError {
+    InvalidId,
+    AlreadyExists,
+}
+
+pub fn add_flavor(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: MenuFlavor,
+    config: FlavorConfig,
+) -> Result<MenuFlavor, AddFlavorError> {
+    if !Flavor::is_valid_id(flavor.id.as_str()) {
+        return Err(AddFlavorError::InvalidId);
+    }
+    if snapshot.menu_flavor(&flavor.id).is_some() {
+        return Err(AddFlavorError::AlreadyExists);
+    }
+
+    snapshot.add_flavor(flavor.clone(), config);
+    Ok(flavor)
+}
+
+// Retired flavors stay in the catalog so that existing orders and stock keep their names.
+pub fn set_flavor_active(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: &Flavor,
+    is_active: bool,
+) -> Option<(MenuFlavor, StatusUpdate)> {
+    let entry = snapshot.menu.iter_mut().find(|entry| &entry.id == flavor)?;
+    entry.is_active = is_active;
+    let result = entry.clone();
+
+    Some((result, order_status::update_order_statuses(snapshot)))
+}
+
+pub fn set_flavor_price(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: &Flavor,
+    unit_price: u32,
+) -> Option<MenuFlavor> {
+    let entry = snapshot.menu.iter_mut().find(|entry| &entry.id == flavor)?;
+    entry.unit_price = unit_price;
+    Some(entry.clone())
+}
+
+pub fn set_flavor_sold_out(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: &Flavor,
+    is_sold_out: bool,
+) -> Option<FlavorConfig> {
+    let config = snapshot.flavor_configs.get_mut(flavor)?;
+    config.is_sold_out = is_sold_out;
+    Some(*config)
+}
+
+pub fn set_flavor_daily_limit(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: &Flavor,
+    daily_limit: Option<u32>,
+) -> Option<FlavorConfig> {
+    let config = snapshot.flavor_configs.get_mut(flavor)?;
+    config.daily_limit = daily_limit;
+    Some(*config)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::snapshot::{Flavor, OrderSystemSnapshot};
+    use crate::test_fixtures::{flavor_config, menu_flavor};
+
+    use super::{AddFlavorError, add_flavor, set_flavor_active};
+
+    #[test]
+    fn add_flavor_keeps_menu_sorted() {
+        let mut snapshot = OrderSystemSnapshot::default();
+
+        add_flavor(&mut snapshot, menu_flavor("custard", 2), flavor_config())
+            .expect("valid flavor");
+        add_flavor(&mut snapshot, menu_flavor("tsubuan", 1), flavor_config())
+            .expect("valid flavor");
+
+        let ids = snapshot
+            .menu
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["tsubuan", "custard"]);
+        assert_eq!(snapshot.stock_of(&Flavor::new("custard")), 0);
+    }
+
+    #[test]
+    fn add_flavor_rejects_duplicate_and_invalid_ids() {
+        let mut snapshot = OrderSystemSnapshot::default();
+        add_flavor(&mut snapshot, menu_flavor("tsubuan", 1), flavor_config())
+            .expect("valid flavor");
+
+        assert_eq!(
+            add_flavor(&mut snapshot, menu_flavor("tsubuan", 2), flavor_config()),
+            Err(AddFlavorError::AlreadyExists)
+        );
+        assert_eq!(
+            add_flavor(
+                &mut snapshot,
+                menu_flavor("Matcha Latte", 3),
+                flavor_config()
+            ),
+            Err(AddFlavorError::InvalidId)
+        );
+    }
+
+    #[test]
+    fn retiring_unknown_flavor_returns_none() {
+        let mut snapshot = OrderSystemSnapshot::default();
+
+        assert!(set_flavor_active(&mut snapshot, &Flavor::new("matcha"), false).is_none());
+    }
+}
@@ -1,2 +1,8 @@
+pub mod business_day;
+pub mod menu;
 pub mod order;
+pub mod pricing;
 pub mod production;
+pub mod station;
+pub mod stock;
+pub mod undo;
@@ -1,38 +1,342 @@
-use chrono::Utc;
+use std::collections::HashSet;
+use std::fmt;
 
-use crate::domain::order_status::{self, StatusUpdate};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::domain::availability::{self, OrderLimits, store_date};
+use crate::domain::business_day;
+use crate::domain::no_show::{self, NoShowNotice, NoShowSettings, NoShowStep};
+use crate::domain::order_number::{self, DisplayNumberSettings, DisplayOrderNumber};
+use crate::domain::order_status::{self, OrderTransition, PendingNotification, StatusUpdate};
+use crate::domain::pre_order::{self, PreOrderSettings};
 use crate::domain::snapshot::{
-    Flavor, FlavorConfig, Item, Notify, Order, OrderStatus, OrderSystemSnapshot,
+    Flavor, FlavorConfig, Item, Notify, Order, OrderStatus, OrderSystemSnapshot, PaymentMethod,
+    PriorityTier,
 };
+use crate::domain::stock;
+use crate::usecase::business_day::open_business_day;
+use crate::usecase::pricing::{self, PricingError};
+
+/// Order as requested by staff or a customer.
+#[derive(Debug, Clone, Default)]
+pub struct NewOrder {
+    pub items: Vec<Item>,
+    pub priority: PriorityTier,
+    pub payment_method: Option<PaymentMethod>,
+    /// Code of a bundle or coupon to apply.
+    pub pricing_rule: Option<String>,
+    /// Requested pickup time, which makes the order a pre-order.
+    pub pickup_at: Option<DateTime<Utc>>,
+    /// Where to tell the customer about the order, for orders they place themselves.
+    pub notify: HashSet<Notify>,
+}
+
+impl NewOrder {
+    pub fn new(items: Vec<Item>) -> Self {
+        Self {
+            items,
+            ..Default::default()
+        }
+    }
+}
 
 pub struct OrderMutation<T> {
     pub result: T,
     pub status_update: StatusUpdate,
 }
 
-pub fn create_order(
-    snapshot: &mut OrderSystemSnapshot,
-    items: Vec<Item>,
-    is_priority: bool,
-) -> OrderMutation<Order> {
-    let new_id = snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    // Malformed orders
+    EmptyItems,
+    ZeroQuantity(Flavor),
+    DuplicateFlavor(Flavor),
+    TooManyPieces {
+        flavor: Flavor,
+        max: usize,
+    },
+    TotalTooLarge,
+    // References to the catalog
+    NotOnSale(Flavor),
+    UnknownPricingRule(String),
+    PricingRuleNotApplicable(String),
+    // Conflicts with the current state
+    ExceedsRemaining {
+        flavor: Flavor,
+        remaining: usize,
+    },
+    NotFound(u32),
+    NotCompleted(u32),
+    AlreadyRefunded(u32),
+    /// Items can only be changed or handed over before the order is completed or cancelled.
+    NotEditable {
+        id: u32,
+        status: OrderStatus,
+    },
+    BelowHandedOver {
+        flavor: Flavor,
+        handed_over: usize,
+    },
+    ExceedsOutstanding {
+        flavor: Flavor,
+        outstanding: usize,
+    },
+    NotInStock {
+        flavor: Flavor,
+        available: usize,
+    },
+    NothingToHandOver(u32),
+    /// Every piece went to the customer, so there is nothing to queue again.
+    FullyHandedOver(u32),
+    /// The pickup slot starts too soon to cook the pre-order in time.
+    PickupTooSoon {
+        earliest: DateTime<Utc>,
+    },
+    /// Pre-orders are only taken for the current business day.
+    PickupOutsideBusinessDay,
+    PickupSlotFull {
+        starts_at: DateTime<Utc>,
+        remaining: usize,
+    },
+    /// Orders are only taken while a business day is open.
+    BusinessDayNotOpen,
+    /// The order belongs to a business day that was already closed.
+    BusinessDayClosed(u32),
+    /// Every display number of the pool is held by an active order.
+    DisplayNumbersExhausted,
+    InvalidTransition {
+        id: u32,
+        from: OrderStatus,
+        transition: OrderTransition,
+    },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderError::EmptyItems => write!(f, "order has no items"),
+            OrderError::ZeroQuantity(flavor) => write!(f, "quantity of {flavor} must be positive"),
+            OrderError::DuplicateFlavor(flavor) => write!(f, "{flavor} appears more than once"),
+            OrderError::TooManyPieces { flavor, max } => {
+                write!(f, "at most {max} pieces of {flavor} can be ordered at once")
+            }
+            OrderError::TotalTooLarge => write!(f, "order total is too large"),
+            OrderError::NotOnSale(flavor) => write!(f, "{flavor} is not on sale"),
+            OrderError::UnknownPricingRule(code) => {
+                write!(f, "pricing rule {code} is not available")
+            }
+            OrderError::PricingRuleNotApplicable(code) => {
+                write!(f, "pricing rule {code} does not apply to the order")
+            }
+            OrderError::ExceedsRemaining { flavor, remaining } => {
+                write!(f, "only {remaining} pieces of {flavor} are left today")
+            }
+            OrderError::NotFound(id) => write!(f, "order {id} not found"),
+            OrderError::NotCompleted(id) => write!(f, "order {id} has not been handed over"),
+            OrderError::AlreadyRefunded(id) => write!(f, "order {id} is already refunded"),
+            OrderError::NotEditable { id, status } => write!(
+                f,
+                "cannot change or hand over items of order {id} while it is {}",
+                status.as_db_str()
+            ),
+            OrderError::BelowHandedOver {
+                flavor,
+                handed_over,
+            } => write!(
+                f,
+                "{handed_over} pieces of {flavor} were already handed over"
+            ),
+            OrderError::ExceedsOutstanding {
+                flavor,
+                outstanding,
+            } => write!(
+                f,
+                "only {outstanding} pieces of {flavor} are still to be handed over"
+            ),
+            OrderError::NotInStock { flavor, available } => {
+                write!(f, "only {available} pieces of {flavor} are in stock")
+            }
+            OrderError::PickupTooSoon { earliest } => {
+                write!(f, "the earliest pickup slot starts at {earliest}")
+            }
+            OrderError::PickupOutsideBusinessDay => {
+                write!(f, "pickup must be within the current business day")
+            }
+            OrderError::PickupSlotFull {
+                starts_at,
+                remaining,
+            } => write!(
+                f,
+                "only {remaining} pieces can still be reserved for the slot at {starts_at}"
+            ),
+            OrderError::BusinessDayNotOpen => write!(f, "no business day is open"),
+            OrderError::BusinessDayClosed(id) => {
+                write!(f, "the business day of order {id} is already closed")
+            }
+            OrderError::DisplayNumbersExhausted => {
+                write!(f, "every display number is in use by an active order")
+            }
+            OrderError::NothingToHandOver(id) => {
+                write!(
+                    f,
+                    "none of the outstanding pieces of order {id} are in stock"
+                )
+            }
+            OrderError::FullyHandedOver(id) => {
+                write!(f, "every piece of order {id} was handed over")
+            }
+            OrderError::InvalidTransition {
+                id,
+                from,
+                transition,
+            } => write!(
+                f,
+                "cannot {} order {id} while it is {}",
+                transition.as_str(),
+                from.as_db_str()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+impl From<PricingError> for OrderError {
+    fn from(error: PricingError) -> Self {
+        match error {
+            PricingError::UnknownRule(code) => OrderError::UnknownPricingRule(code),
+            PricingError::NotApplicable(code) => OrderError::PricingRuleNotApplicable(code),
+            PricingError::TotalTooLarge => OrderError::TotalTooLarge,
+        }
+    }
+}
+
+pub fn validate_items(items: &[Item]) -> Result<(), OrderError> {
+    if items.is_empty() {
+        return Err(OrderError::EmptyItems);
+    }
+    let mut seen = HashSet::new();
+    for item in items {
+        if item.quantity == 0 {
+            return Err(OrderError::ZeroQuantity(item.flavor.clone()));
+        }
+        if !seen.insert(&item.flavor) {
+            return Err(OrderError::DuplicateFlavor(item.flavor.clone()));
+        }
+    }
+    Ok(())
+}
+
+// Keeps the price and wait time math of an order small.
+fn check_item_quantities(items: &[Item], limits: OrderLimits) -> Result<(), OrderError> {
+    match items
+        .iter()
+        .find(|item| item.quantity > limits.max_item_quantity)
+    {
+        Some(item) => Err(OrderError::TooManyPieces {
+            flavor: item.flavor.clone(),
+            max: limits.max_item_quantity,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn find_order_mut(snapshot: &mut OrderSystemSnapshot, id: u32) -> Result<&mut Order, OrderError> {
+    let order = snapshot
         .orders
         .iter()
-        .map(|order| order.id)
-        .max()
-        .unwrap_or(0)
-        + 1;
+        .find(|order| order.id == id)
+        .ok_or(OrderError::NotFound(id))?;
+    if business_day::is_locked(snapshot, order) {
+        return Err(OrderError::BusinessDayClosed(id));
+    }
+    Ok(snapshot
+        .orders
+        .iter_mut()
+        .find(|order| order.id == id)
+        .expect("found order must exist"))
+}
+
+pub fn create_order(
+    snapshot: &mut OrderSystemSnapshot,
+    new_order: NewOrder,
+    display_numbers: &DisplayNumberSettings,
+    pre_orders: PreOrderSettings,
+    limits: OrderLimits,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let now = Utc::now();
+    let NewOrder {
+        items,
+        priority,
+        payment_method,
+        pricing_rule,
+        pickup_at,
+        notify,
+    } = new_order;
+    // A store that has never opened a business day, such as one just upgraded to them, opens
+    // its first with its first order. From then on staff open each day themselves.
+    let open_day = business_day::open_day(snapshot).map(|day| (day.id, day.date));
+    if open_day.is_none() && !snapshot.business_days.is_empty() {
+        return Err(OrderError::BusinessDayNotOpen);
+    }
+    let business_date = open_day.map_or_else(|| store_date(now), |(_, date)| date);
+    validate_items(&items)?;
+    check_item_quantities(&items, limits)?;
+    if let Some(item) = items.iter().find(|item| !snapshot.is_on_sale(&item.flavor)) {
+        return Err(OrderError::NotOnSale(item.flavor.clone()));
+    }
+    for item in &items {
+        if let Some(remaining) = availability::remaining_quantity(snapshot, &item.flavor, now)
+            && item.quantity > remaining
+        {
+            return Err(OrderError::ExceedsRemaining {
+                flavor: item.flavor.clone(),
+                remaining,
+            });
+        }
+    }
+
+    let new_id = snapshot.next_order_id();
+    let pickup_at = pickup_at
+        .map(|pickup_at| {
+            reserve_pickup_slot(snapshot, &items, pickup_at, business_date, pre_orders, now)
+        })
+        .transpose()?;
+    let price = pricing::price_order(snapshot, &items, pricing_rule.as_deref())?;
+    let display_number =
+        allocate_display_number(snapshot, priority.is_priority(), display_numbers)?;
+    let business_day_id = match open_day {
+        Some((id, _)) => id,
+        None => {
+            open_business_day(snapshot, now)
+                .expect("no business day is open")
+                .id
+        }
+    };
     let new_order = Order {
         id: new_id,
         items,
         status: OrderStatus::Waiting,
-        ordered_at: Utc::now(),
+        ordered_at: now,
         ready_at: None,
         completed_at: None,
-        notify: Default::default(),
-        is_priority,
+        notify,
+        priority,
+        total_price: price.total_price,
+        pricing_rule: price.pricing_rule,
+        discount_amount: price.discount_amount,
+        payment_method,
+        refunded_at: None,
+        handed_over: Vec::new(),
+        display_number,
+        business_day_id: Some(business_day_id),
+        pickup_at,
+        release_at: pickup_at.map(|pickup_at| pre_orders.release_at(pickup_at)),
+        no_show_at: None,
+        expired_at: None,
     };
     snapshot.orders.push(new_order);
+    snapshot.last_order_id = new_id;
 
     let status_update = order_status::update_order_statuses(snapshot);
     let result = snapshot
@@ -42,80 +346,474 @@ pub fn create_order(
         .expect("created order must exist")
         .clone();
 
-    OrderMutation {
+    Ok(OrderMutation {
         result,
         status_update,
+    })
+}
+
+// Returns the start of the slot the requested time falls in.
+fn reserve_pickup_slot(
+    snapshot: &OrderSystemSnapshot,
+    items: &[Item],
+    pickup_at: DateTime<Utc>,
+    business_date: NaiveDate,
+    pre_orders: PreOrderSettings,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, OrderError> {
+    let starts_at = pre_orders.slot_start(pickup_at);
+    let earliest = pre_orders.earliest_slot(now);
+    if starts_at < earliest {
+        return Err(OrderError::PickupTooSoon { earliest });
+    }
+    if availability::store_date(starts_at) != business_date {
+        return Err(OrderError::PickupOutsideBusinessDay);
     }
+    let reserved = pre_order::reserved_in_slot(snapshot, starts_at);
+    check_slot_capacity(items, starts_at, reserved, pre_orders)?;
+    Ok(starts_at)
 }
 
-pub fn complete_order(snapshot: &mut OrderSystemSnapshot, id: u32) -> Option<OrderMutation<Order>> {
-    let order = snapshot.orders.iter_mut().find(|order| order.id == id)?;
-    let previous_status = order.status;
-    order.status = OrderStatus::Completed;
-    order.completed_at = Some(Utc::now());
+fn check_slot_capacity(
+    items: &[Item],
+    starts_at: DateTime<Utc>,
+    reserved: usize,
+    pre_orders: PreOrderSettings,
+) -> Result<(), OrderError> {
+    let remaining = pre_orders.slot_capacity.saturating_sub(reserved);
+    if items.iter().map(|item| item.quantity).sum::<usize>() > remaining {
+        return Err(OrderError::PickupSlotFull {
+            starts_at,
+            remaining,
+        });
+    }
+    Ok(())
+}
+
+pub fn complete_order(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
+    transition(order, OrderTransition::Complete)?;
     let result = order.clone();
 
-    let status_update = if matches!(
-        previous_status,
-        OrderStatus::Waiting | OrderStatus::Cooking | OrderStatus::Ready
-    ) {
-        order_status::update_order_statuses(snapshot)
-    } else {
-        empty_status_update()
-    };
+    Ok(OrderMutation {
+        result,
+        status_update: order_status::update_order_statuses(snapshot),
+    })
+}
 
-    Some(OrderMutation {
+pub fn cancel_order(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
+    let previous_status = transition(order, OrderTransition::Cancel)?;
+    let result = order.clone();
+
+    // Pieces handed over earlier stay with the customer.
+    if previous_status == OrderStatus::Ready {
+        let ready_at = result.ready_at.unwrap_or_else(Utc::now);
+        return_to_stock(snapshot, &outstanding_items(&result), ready_at);
+    }
+
+    Ok(OrderMutation {
         result,
-        status_update,
+        status_update: order_status::update_order_statuses(snapshot),
     })
 }
 
-pub fn cancel_order(snapshot: &mut OrderSystemSnapshot, id: u32) -> Option<OrderMutation<Order>> {
-    let order_index = snapshot.orders.iter().position(|order| order.id == id)?;
-    let previous_status = snapshot.orders[order_index].status;
-    let items_to_return =
-        (previous_status == OrderStatus::Ready).then(|| snapshot.orders[order_index].items.clone());
+// The customer changed their mind. The order keeps its place in the queue and its notification
+// registrations; pieces already set aside go back to stock and allocation starts over.
+pub fn update_order_items(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+    items: Vec<Item>,
+    pre_orders: PreOrderSettings,
+    limits: OrderLimits,
+) -> Result<OrderMutation<Order>, OrderError> {
+    validate_items(&items)?;
+    check_item_quantities(&items, limits)?;
+    let now = Utc::now();
+    let order = snapshot
+        .orders
+        .iter()
+        .find(|order| order.id == id)
+        .ok_or(OrderError::NotFound(id))?;
+    if !matches!(
+        order.status,
+        OrderStatus::Waiting | OrderStatus::Cooking | OrderStatus::Ready
+    ) {
+        return Err(OrderError::NotEditable {
+            id,
+            status: order.status,
+        });
+    }
+    let already_ordered = |flavor: &Flavor| {
+        order
+            .items
+            .iter()
+            .find(|item| &item.flavor == flavor)
+            .map_or(0, |item| item.quantity)
+    };
+    for handed_over in &order.handed_over {
+        let kept = items
+            .iter()
+            .find(|item| item.flavor == handed_over.flavor)
+            .map_or(0, |item| item.quantity);
+        if kept < handed_over.quantity {
+            return Err(OrderError::BelowHandedOver {
+                flavor: handed_over.flavor.clone(),
+                handed_over: handed_over.quantity,
+            });
+        }
+    }
+    for item in &items {
+        let already = already_ordered(&item.flavor);
+        // Flavors taken off sale since ordering may be kept, just not added or increased.
+        if item.quantity > already && !snapshot.is_on_sale(&item.flavor) {
+            return Err(OrderError::NotOnSale(item.flavor.clone()));
+        }
+        // The order's own pieces already count against today's limit.
+        if let Some(remaining) = availability::remaining_quantity(snapshot, &item.flavor, now)
+            && item.quantity > already + remaining
+        {
+            return Err(OrderError::ExceedsRemaining {
+                flavor: item.flavor.clone(),
+                remaining: already + remaining,
+            });
+        }
+    }
+    // The slot was checked with the order's old pieces in it, which the new ones replace.
+    if let Some(starts_at) = order.pickup_at {
+        let own = order.items.iter().map(|item| item.quantity).sum::<usize>();
+        let reserved = pre_order::reserved_in_slot(snapshot, starts_at).saturating_sub(own);
+        check_slot_capacity(&items, starts_at, reserved, pre_orders)?;
+    }
+    let price = pricing::price_order(snapshot, &items, order.pricing_rule.as_deref())?;
 
-    snapshot.orders[order_index].status = OrderStatus::Cancelled;
-    let result = snapshot.orders[order_index].clone();
+    let order = find_order_mut(snapshot, id)?;
+    let set_aside = outstanding_items(order);
+    order.items = items;
+    order.total_price = price.total_price;
+    order.discount_amount = price.discount_amount;
+    order.pricing_rule = price.pricing_rule;
+    let ready_at = order.ready_at.take();
+    order.no_show_at = None;
+    if order.status == OrderStatus::Ready {
+        order.status = OrderStatus::Waiting;
+        return_to_stock(snapshot, &set_aside, ready_at.unwrap_or(now));
+    }
+    reallocated(snapshot, id)
+}
 
-    let stock_was_changed = if let Some(items) = items_to_return {
-        for item in items {
-            snapshot.unallocated_stock[item.flavor] += item.quantity;
+// Splits off the part of a large order that is already available. The handed-over pieces leave
+// stock; the outstanding part keeps the order's place in the queue, display number and
+// notification targets. Without `items`, every outstanding piece the queue leaves for the order
+// is handed over.
+pub fn hand_over_items(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+    items: Option<Vec<Item>>,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let order = snapshot
+        .orders
+        .iter()
+        .find(|order| order.id == id)
+        .ok_or(OrderError::NotFound(id))?;
+    if !matches!(
+        order.status,
+        OrderStatus::Waiting | OrderStatus::Cooking | OrderStatus::Ready
+    ) {
+        return Err(OrderError::NotEditable {
+            id,
+            status: order.status,
+        });
+    }
+    // Pieces of a ready order are already set aside for it. Others only take what the orders
+    // queued ahead of them leave.
+    let left = order_status::stock_left_for(snapshot, id);
+    let available = |flavor: &Flavor, outstanding: usize| {
+        if order.status == OrderStatus::Ready {
+            outstanding
+        } else {
+            left.get(flavor).copied().unwrap_or(0)
         }
-        true
-    } else {
-        false
     };
+    let outstanding = order
+        .outstanding()
+        .map(|(flavor, quantity)| (flavor.clone(), quantity))
+        .collect::<Vec<_>>();
+    let outstanding_of = |flavor: &Flavor| {
+        outstanding
+            .iter()
+            .find(|(outstanding, _)| outstanding == flavor)
+            .map_or(0, |(_, quantity)| *quantity)
+    };
+
+    let items = match items {
+        Some(items) => {
+            validate_items(&items)?;
+            for item in &items {
+                let outstanding = outstanding_of(&item.flavor);
+                if item.quantity > outstanding {
+                    return Err(OrderError::ExceedsOutstanding {
+                        flavor: item.flavor.clone(),
+                        outstanding,
+                    });
+                }
+                let available = available(&item.flavor, outstanding);
+                if item.quantity > available {
+                    return Err(OrderError::NotInStock {
+                        flavor: item.flavor.clone(),
+                        available,
+                    });
+                }
+            }
+            items
+        }
+        None => outstanding
+            .iter()
+            .map(|(flavor, quantity)| Item {
+                flavor: flavor.clone(),
+                quantity: (*quantity).min(available(flavor, *quantity)),
+            })
+            .filter(|item| item.quantity > 0)
+            .collect(),
+    };
+    if items.is_empty() {
+        return Err(OrderError::NothingToHandOver(id));
+    }
+
+    if order.status != OrderStatus::Ready {
+        for item in &items {
+            let stock = snapshot.stock_of(&item.flavor);
+            snapshot
+                .unallocated_stock
+                .insert(item.flavor.clone(), stock - item.quantity);
+        }
+    }
+    let order = find_order_mut(snapshot, id)?;
+    for item in items {
+        match order
+            .handed_over
+            .iter_mut()
+            .find(|handed_over| handed_over.flavor == item.flavor)
+        {
+            Some(handed_over) => handed_over.quantity += item.quantity,
+            None => order.handed_over.push(item),
+        }
+    }
+    if order.outstanding().next().is_none() {
+        transition(order, OrderTransition::Complete)?;
+    }
+    reallocated(snapshot, id)
+}
+
+// Undo for a mistaken completion: the pieces are still on the counter, so they go back to stock
+// and the order queues again at its original place. Pieces handed over before stay with the
+// customer.
+pub fn reopen_order(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+    display_numbers: &DisplayNumberSettings,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
+    if order.refunded_at.is_some() {
+        return Err(OrderError::AlreadyRefunded(id));
+    }
+    if order.status == OrderStatus::Completed && order.outstanding().next().is_none() {
+        return Err(OrderError::FullyHandedOver(id));
+    }
+    // Orders completed before becoming ready had nothing set aside.
+    let ready_at = order.ready_at;
+    transition(order, OrderTransition::Reopen)?;
+    let outstanding = outstanding_items(order);
+
+    if let Some(ready_at) = ready_at {
+        return_to_stock(snapshot, &outstanding, ready_at);
+    }
+    reclaim_display_number(snapshot, id, display_numbers)?;
+    reallocated(snapshot, id)
+}
+
+// Undo for a mistaken cancellation, or for an expired order whose customer turned up after all.
+// Any stock was returned when the order was cancelled or expired, so it simply queues again at its
+// original place.
+pub fn restore_order(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+    display_numbers: &DisplayNumberSettings,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
+    transition(order, OrderTransition::Restore)?;
 
-    let status_update = if stock_was_changed
-        || matches!(previous_status, OrderStatus::Waiting | OrderStatus::Cooking)
+    reclaim_display_number(snapshot, id, display_numbers)?;
+    reallocated(snapshot, id)
+}
+
+// Reminds customers of ready orders they have not come for and gives up on the ones that kept
+// pieces from others for too long. Steps are recorded on the order, so each is taken once.
+pub fn chase_no_shows(
+    snapshot: &mut OrderSystemSnapshot,
+    settings: NoShowSettings,
+    now: DateTime<Utc>,
+) -> OrderMutation<Vec<NoShowNotice>> {
+    let mut notices = Vec::new();
+    let mut notifications = Vec::new();
+    for (id, step) in no_show::due_no_show_steps(snapshot, settings, now) {
+        let Some(order) = snapshot.orders.iter_mut().find(|order| order.id == id) else {
+            continue;
+        };
+        let ready_at = order.ready_at.unwrap_or(now);
+        notices.push(NoShowNotice::new(order, step, ready_at, now));
+        match step {
+            NoShowStep::Reminded => {
+                order.no_show_at = Some(now);
+                notifications.extend(order.notify.iter().cloned().map(|notify| {
+                    PendingNotification {
+                        order_id: id,
+                        notify,
+                        message: format!(
+                            "#{}番 のご注文をお渡しできます。お早めに受け取りにお越しください。",
+                            order.display_number
+                        ),
+                    }
+                }));
+            }
+            NoShowStep::Expired => {
+                order_status::apply_transition(order, OrderTransition::Expire, now)
+                    .expect("due orders are ready");
+                let items = outstanding_items(order);
+                return_to_stock(snapshot, &items, ready_at);
+            }
+        }
+    }
+
+    let mut status_update = if notices
+        .iter()
+        .any(|notice| notice.step == NoShowStep::Expired)
     {
         order_status::update_order_statuses(snapshot)
     } else {
         empty_status_update()
     };
+    notifications.append(&mut status_update.notifications);
+    status_update.notifications = notifications;
+    OrderMutation {
+        result: notices,
+        status_update,
+    }
+}
+
+fn transition(order: &mut Order, transition: OrderTransition) -> Result<OrderStatus, OrderError> {
+    order_status::apply_transition(order, transition, Utc::now()).map_err(|error| {
+        OrderError::InvalidTransition {
+            id: order.id,
+            from: error.from,
+            transition: error.transition,
+        }
+    })
+}
+
+// The pieces were made no later than the order became ready.
+fn return_to_stock(snapshot: &mut OrderSystemSnapshot, items: &[Item], produced_at: DateTime<Utc>) {
+    for item in items {
+        stock::add_stock(snapshot, &item.flavor, item.quantity, produced_at);
+    }
+}
 
-    Some(OrderMutation {
+// The number of an order that comes back may have been given to another order in the meantime.
+fn reclaim_display_number(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+    display_numbers: &DisplayNumberSettings,
+) -> Result<(), OrderError> {
+    let order = snapshot
+        .orders
+        .iter()
+        .find(|order| order.id == id)
+        .ok_or(OrderError::NotFound(id))?;
+    let taken = snapshot.orders.iter().any(|other| {
+        other.id != id
+            && order_number::is_holding_number(other)
+            && other.display_number == order.display_number
+    });
+    if taken {
+        let display_number =
+            allocate_display_number(snapshot, order.priority.is_priority(), display_numbers)?;
+        find_order_mut(snapshot, id)?.display_number = display_number;
+    }
+    Ok(())
+}
+
+// Numbering starts over each day; earlier days only matter for numbers still held.
+fn allocate_display_number(
+    snapshot: &OrderSystemSnapshot,
+    is_priority: bool,
+    display_numbers: &DisplayNumberSettings,
+) -> Result<DisplayOrderNumber, OrderError> {
+    let business_day_id = business_day::open_day(snapshot).map(|day| day.id);
+    display_numbers
+        .pool_for(is_priority)
+        .allocate(snapshot.orders.iter().filter(|order| {
+            (business_day_id.is_some() && order.business_day_id == business_day_id)
+                || order_number::is_holding_number(order)
+        }))
+        .ok_or(OrderError::DisplayNumbersExhausted)
+}
+
+fn outstanding_items(order: &Order) -> Vec<Item> {
+    order
+        .outstanding()
+        .map(|(flavor, quantity)| Item {
+            flavor: flavor.clone(),
+            quantity,
+        })
+        .collect()
+}
+
+fn reallocated(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let status_update = order_status::update_order_statuses(snapshot);
+    let result = find_order_mut(snapshot, id)?.clone();
+
+    Ok(OrderMutation {
         result,
         status_update,
     })
 }
 
+// Cancelled orders are settled at cancellation; refunds are for orders already handed over.
+pub fn refund_order(snapshot: &mut OrderSystemSnapshot, id: u32) -> Result<Order, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
+    if order.status != OrderStatus::Completed {
+        return Err(OrderError::NotCompleted(id));
+    }
+    if order.refunded_at.is_some() {
+        return Err(OrderError::AlreadyRefunded(id));
+    }
+    order.refunded_at = Some(Utc::now());
+    Ok(order.clone())
+}
+
 pub fn update_order_priority(
     snapshot: &mut OrderSystemSnapshot,
     id: u32,
-    is_priority: bool,
-) -> Option<OrderMutation<Order>> {
-    let order = snapshot.orders.iter_mut().find(|order| order.id == id)?;
-    if order.is_priority == is_priority {
-        return Some(OrderMutation {
+    priority: PriorityTier,
+) -> Result<OrderMutation<Order>, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
+    if order.priority == priority {
+        return Ok(OrderMutation {
             result: order.clone(),
             status_update: empty_status_update(),
         });
     }
 
-    order.is_priority = is_priority;
+    order.priority = priority;
     let status_update = order_status::update_order_statuses(snapshot);
     let result = snapshot
         .orders
@@ -124,7 +822,7 @@ pub fn update_order_priority(
         .expect("updated order must exist")
         .clone();
 
-    Some(OrderMutation {
+    Ok(OrderMutation {
         result,
         status_update,
     })
@@ -134,29 +832,33 @@ pub fn add_notification(
     snapshot: &mut OrderSystemSnapshot,
     id: u32,
     notify: Notify,
-) -> Option<Order> {
-    let order = snapshot.orders.iter_mut().find(|order| order.id == id)?;
+) -> Result<Order, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
     order.notify.insert(notify);
-    Some(order.clone())
+    Ok(order.clone())
 }
 
 pub fn cancel_notification(
     snapshot: &mut OrderSystemSnapshot,
     id: u32,
     notify: &Notify,
-) -> Option<Order> {
-    let order = snapshot.orders.iter_mut().find(|order| order.id == id)?;
+) -> Result<Order, OrderError> {
+    let order = find_order_mut(snapshot, id)?;
     order.notify.remove(notify);
-    Some(order.clone())
+    Ok(order.clone())
 }
 
 pub fn set_flavor_config(
     snapshot: &mut OrderSystemSnapshot,
     flavor: Flavor,
     config: FlavorConfig,
-) -> StatusUpdate {
-    snapshot.flavor_configs[flavor] = config;
-    order_status::update_order_statuses(snapshot)
+) -> Option<StatusUpdate> {
+    snapshot.menu_flavor(&flavor)?;
+    if let Some(station_id) = config.station_id {
+        snapshot.station(station_id)?;
+    }
+    snapshot.flavor_configs.insert(flavor, config);
+    Some(order_status::update_order_statuses(snapshot))
 }
 
 fn empty_status_update() -> StatusUpdate {
@@ -170,76 +872,643 @@ fn empty_status_update() -> StatusUpdate {
 mod tests {
     use chrono::{Duration, TimeZone, Utc};
 
-    use crate::domain::snapshot::{Flavor, Item, Order, OrderStatus, OrderSystemSnapshot};
+    use crate::domain::snapshot::{
+        Flavor, Item, Notify, Order, OrderStatus, OrderSystemSnapshot, PaymentMethod, PriorityTier,
+    };
 
-    use super::{cancel_order, complete_order, create_order, update_order_priority};
+    use super::{
+        NewOrder, OrderError, cancel_order, chase_no_shows, complete_order, create_order,
+        hand_over_items, refund_order, reopen_order, restore_order, update_order_items,
+        update_order_priority,
+    };
+    use crate::domain::allocation::AllocationPolicyKind;
+    use crate::domain::availability::{OrderLimits, store_date};
+    use crate::domain::no_show::{NoShowSettings, NoShowStep};
+    use crate::domain::order_number::DisplayOrderNumber;
+    use crate::domain::order_status::{OrderTransition, update_order_statuses};
+    use crate::domain::pre_order::PreOrderSettings;
+    use crate::test_fixtures::{self, tsubuan, waiting_order};
+    use crate::usecase::business_day::open_business_day;
 
     fn item() -> Item {
         Item {
-            flavor: Flavor::Tsubuan,
+            flavor: tsubuan(),
             quantity: 1,
         }
     }
 
-    fn waiting_order(id: u32) -> Order {
-        Order {
-            id,
-            items: vec![item()],
-            status: OrderStatus::Waiting,
-            ordered_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
-                + Duration::seconds(id.into()),
-            ready_at: None,
-            completed_at: None,
-            notify: Default::default(),
-            is_priority: false,
-        }
+    fn snapshot_with_orders(orders: Vec<Order>) -> OrderSystemSnapshot {
+        let mut snapshot = test_fixtures::snapshot_with_orders(orders);
+        open_business_day(&mut snapshot, Utc::now() - Duration::hours(1)).expect("open");
+        snapshot
     }
 
     #[test]
     fn create_order_allocates_next_internal_id() {
-        let mut snapshot = OrderSystemSnapshot {
-            orders: vec![waiting_order(41)],
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(41, 1)]);
+
+        let mutation = create_order(
+            &mut snapshot,
+            NewOrder::new(vec![item()]),
+            &Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .expect("flavor is on sale");
+
+        assert_eq!(mutation.result.id, 42);
+    }
+
+    #[test]
+    fn create_order_records_total_and_payment_method() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        let items = vec![Item {
+            flavor: tsubuan(),
+            quantity: 3,
+        }];
+
+        let mutation = create_order(
+            &mut snapshot,
+            NewOrder {
+                payment_method: Some(PaymentMethod::QrCode),
+                ..NewOrder::new(items)
+            },
+            &Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .expect("flavor is on sale");
+
+        // Later price changes must not affect the recorded total.
+        snapshot.menu[0].unit_price = 250;
+        assert_eq!(snapshot.orders[0].total_price, 600);
+        assert_eq!(mutation.result.payment_method, Some(PaymentMethod::QrCode));
+    }
+
+    #[test]
+    fn first_order_opens_the_first_business_day_only() {
+        let mut snapshot = test_fixtures::snapshot_with_orders(Vec::new());
+
+        let mutation = create_order(
+            &mut snapshot,
+            NewOrder::new(vec![item()]),
+            &Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .expect("the first business day opens");
+        assert_eq!(snapshot.business_days.len(), 1);
+        assert_eq!(
+            mutation.result.business_day_id,
+            Some(snapshot.business_days[0].id)
+        );
+
+        snapshot.business_days[0].closed_at = Some(Utc::now());
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(vec![item()]),
+                &Default::default(),
+                Default::default(),
+                Default::default(),
+            )
+            .map(|mutation| mutation.result.id),
+            Err(OrderError::BusinessDayNotOpen)
+        );
+    }
+
+    #[test]
+    fn create_order_rejects_malformed_items() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        let zero = Item {
+            flavor: tsubuan(),
+            quantity: 0,
+        };
+
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(Vec::new()),
+                &Default::default(),
+                Default::default(),
+                Default::default()
+            )
+            .err(),
+            Some(OrderError::EmptyItems)
+        );
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(vec![zero]),
+                &Default::default(),
+                Default::default(),
+                Default::default()
+            )
+            .err(),
+            Some(OrderError::ZeroQuantity(tsubuan()))
+        );
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(vec![item(), item()]),
+                &Default::default(),
+                Default::default(),
+                Default::default()
+            )
+            .err(),
+            Some(OrderError::DuplicateFlavor(tsubuan()))
+        );
+        let too_many = Item {
+            flavor: tsubuan(),
+            quantity: 51,
+        };
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(vec![too_many]),
+                &Default::default(),
+                Default::default(),
+                OrderLimits {
+                    max_item_quantity: 50
+                }
+            )
+            .err(),
+            Some(OrderError::TooManyPieces {
+                flavor: tsubuan(),
+                max: 50
+            })
+        );
+        assert!(snapshot.orders.is_empty());
+    }
+
+    #[test]
+    fn create_order_rejects_retired_flavor() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        snapshot.menu[0].is_active = false;
+
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(vec![item()]),
+                &Default::default(),
+                Default::default(),
+                Default::default()
+            )
+            .err(),
+            Some(OrderError::NotOnSale(tsubuan()))
+        );
+        assert!(snapshot.orders.is_empty());
+    }
+
+    #[test]
+    fn create_order_rejects_quantity_over_daily_limit() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        snapshot
+            .flavor_configs
+            .get_mut(&tsubuan())
+            .expect("config exists")
+            .daily_limit = Some(3);
+        let two = Item {
+            flavor: tsubuan(),
+            quantity: 2,
+        };
+        create_order(
+            &mut snapshot,
+            NewOrder::new(vec![two.clone()]),
+            &Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .expect("within daily limit");
+
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                NewOrder::new(vec![two]),
+                &Default::default(),
+                Default::default(),
+                Default::default()
+            )
+            .err(),
+            Some(OrderError::ExceedsRemaining {
+                flavor: tsubuan(),
+                remaining: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn pre_order_is_held_and_limited_by_its_slot() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        snapshot.unallocated_stock.insert(tsubuan(), 5);
+        let settings = PreOrderSettings {
+            slot_capacity: 4,
             ..Default::default()
         };
+        let pickup_at = settings.earliest_slot(Utc::now()) + Duration::minutes(45);
+        snapshot.business_days[0].date = store_date(pickup_at);
+        let pre_order = |quantity, pickup_at| NewOrder {
+            pickup_at: Some(pickup_at),
+            ..NewOrder::new(vec![Item {
+                flavor: tsubuan(),
+                quantity,
+            }])
+        };
 
-        let mutation = create_order(&mut snapshot, vec![item()], false);
+        let mutation = create_order(
+            &mut snapshot,
+            pre_order(3, pickup_at),
+            &Default::default(),
+            settings,
+            Default::default(),
+        )
+        .expect("slot has room");
 
-        assert_eq!(mutation.result.id, 42);
+        let starts_at = settings.slot_start(pickup_at);
+        assert_eq!(mutation.result.pickup_at, Some(starts_at));
+        assert_eq!(mutation.result.status, OrderStatus::Waiting);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 5);
+        assert_eq!(
+            create_order(
+                &mut snapshot,
+                pre_order(2, pickup_at),
+                &Default::default(),
+                settings,
+                Default::default()
+            )
+            .err(),
+            Some(OrderError::PickupSlotFull {
+                starts_at,
+                remaining: 1
+            })
+        );
+        assert!(matches!(
+            create_order(
+                &mut snapshot,
+                pre_order(1, Utc::now()),
+                &Default::default(),
+                settings,
+                Default::default()
+            ),
+            Err(OrderError::PickupTooSoon { .. })
+        ));
+        // Editing the pre-order frees its own pieces but not room taken by others.
+        let id = mutation.result.id;
+        let items = |quantity| {
+            vec![Item {
+                flavor: tsubuan(),
+                quantity,
+            }]
+        };
+        update_order_items(&mut snapshot, id, items(4), settings, Default::default())
+            .expect("slot has room");
+        assert_eq!(
+            update_order_items(&mut snapshot, id, items(5), settings, Default::default()).err(),
+            Some(OrderError::PickupSlotFull {
+                starts_at,
+                remaining: 4
+            })
+        );
     }
 
     #[test]
     fn cancelling_ready_order_returns_stock() {
-        let mut ready = waiting_order(1);
+        let mut ready = waiting_order(1, 1);
         ready.status = OrderStatus::Ready;
-        let mut snapshot = OrderSystemSnapshot {
-            orders: vec![ready],
-            ..Default::default()
-        };
+        let mut snapshot = snapshot_with_orders(vec![ready]);
 
         let mutation = cancel_order(&mut snapshot, 1).expect("order exists");
 
         assert_eq!(mutation.result.status, OrderStatus::Cancelled);
-        assert_eq!(snapshot.unallocated_stock[Flavor::Tsubuan], 1);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 1);
+    }
+
+    #[test]
+    fn refund_is_only_allowed_once_for_completed_orders() {
+        let mut completed = waiting_order(2, 1);
+        completed.status = OrderStatus::Completed;
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 1), completed]);
+
+        assert_eq!(
+            refund_order(&mut snapshot, 1).err(),
+            Some(OrderError::NotCompleted(1))
+        );
+        assert!(refund_order(&mut snapshot, 2).is_ok());
+        assert_eq!(
+            refund_order(&mut snapshot, 2).err(),
+            Some(OrderError::AlreadyRefunded(2))
+        );
     }
 
     #[test]
     fn completing_unknown_order_returns_none() {
         let mut snapshot = OrderSystemSnapshot::default();
 
-        assert!(complete_order(&mut snapshot, 1).is_none());
+        assert!(matches!(
+            complete_order(&mut snapshot, 1),
+            Err(OrderError::NotFound(1))
+        ));
     }
 
     #[test]
     fn priority_update_recalculates_ready_order() {
-        let mut snapshot = OrderSystemSnapshot {
-            orders: vec![waiting_order(1), waiting_order(2)],
-            ..Default::default()
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 1), waiting_order(2, 1)]);
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
+
+        let mutation =
+            update_order_priority(&mut snapshot, 2, PriorityTier::Staff).expect("order exists");
+
+        assert_eq!(mutation.result.priority, PriorityTier::Staff);
+        assert_eq!(mutation.status_update.newly_ready_orders, vec![2]);
+    }
+
+    #[test]
+    fn finished_orders_reject_complete_and_cancel() {
+        let mut cancelled = waiting_order(1, 1);
+        cancelled.status = OrderStatus::Cancelled;
+        let mut completed = waiting_order(2, 1);
+        completed.status = OrderStatus::Completed;
+        let mut snapshot = snapshot_with_orders(vec![cancelled, completed]);
+
+        assert_eq!(
+            complete_order(&mut snapshot, 1).err(),
+            Some(OrderError::InvalidTransition {
+                id: 1,
+                from: OrderStatus::Cancelled,
+                transition: OrderTransition::Complete,
+            })
+        );
+        assert!(matches!(
+            cancel_order(&mut snapshot, 2),
+            Err(OrderError::InvalidTransition { id: 2, .. })
+        ));
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Completed);
+    }
+
+    #[test]
+    fn reopening_completed_order_returns_its_pieces_to_it() {
+        let mut completed = waiting_order(1, 1);
+        completed.status = OrderStatus::Ready;
+        completed.ready_at = Some(completed.ordered_at);
+        let mut snapshot = snapshot_with_orders(vec![completed, waiting_order(2, 1)]);
+        complete_order(&mut snapshot, 1).expect("ready order can be completed");
+
+        let mutation = reopen_order(&mut snapshot, 1, &Default::default())
+            .expect("completed order can be reopened");
+
+        // The older order wins the returned piece back; the newer one keeps waiting.
+        assert_eq!(mutation.result.status, OrderStatus::Ready);
+        assert_eq!(mutation.result.completed_at, None);
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Waiting);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+    }
+
+    #[test]
+    fn reopening_partly_handed_over_order_returns_only_the_rest() {
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 4), waiting_order(2, 2)]);
+        snapshot.unallocated_stock.insert(tsubuan(), 4);
+        update_order_statuses(&mut snapshot);
+        let handed_over = vec![Item {
+            flavor: tsubuan(),
+            quantity: 3,
+        }];
+        hand_over_items(&mut snapshot, 1, Some(handed_over)).expect("pieces are set aside");
+        complete_order(&mut snapshot, 1).expect("ready order can be completed");
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+
+        let mutation = reopen_order(&mut snapshot, 1, &Default::default())
+            .expect("completed order can be reopened");
+
+        assert_eq!(mutation.result.status, OrderStatus::Ready);
+        assert_eq!(mutation.result.handed_over_quantity(&tsubuan()), 3);
+        assert_eq!(
+            mutation.result.outstanding().collect::<Vec<_>>(),
+            vec![(&tsubuan(), 1)]
+        );
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Waiting);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+    }
+
+    #[test]
+    fn reopening_fully_handed_over_order_is_rejected() {
+        let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 2)]);
+        snapshot.unallocated_stock.insert(tsubuan(), 2);
+        update_order_statuses(&mut snapshot);
+        let mutation = hand_over_items(&mut snapshot, 1, None).expect("pieces are set aside");
+        assert_eq!(mutation.result.status, OrderStatus::Completed);
+
+        assert_eq!(
+            reopen_order(&mut snapshot, 1, &Default::default()).err(),
+            Some(OrderError::FullyHandedOver(1))
+        );
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Completed);
+    }
+
+    #[test]
+    fn restored_order_whose_number_was_reused_is_numbered_like_a_new_order() {
+        let mut cancelled = waiting_order(1, 1);
+        cancelled.status = OrderStatus::Cancelled;
+        let mut reused = waiting_order(2, 1);
+        reused.display_number = cancelled.display_number.clone();
+        // Completed before today's business day opened.
+        let mut earlier = waiting_order(3, 1);
+        earlier.status = OrderStatus::Completed;
+        earlier.display_number = DisplayOrderNumber::new("09");
+        let mut snapshot = snapshot_with_orders(vec![cancelled, reused, earlier]);
+        let new_order = create_order(
+            &mut snapshot.clone(),
+            NewOrder::new(vec![item()]),
+            &Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .expect("flavor is on sale");
+
+        let mutation = restore_order(&mut snapshot, 1, &Default::default())
+            .expect("cancelled order can be restored");
+
+        assert_eq!(
+            mutation.result.display_number,
+            new_order.result.display_number
+        );
+    }
+
+    #[test]
+    fn restoring_cancelled_order_queues_it_again() {
+        let mut ready = waiting_order(1, 1);
+        ready.status = OrderStatus::Ready;
+        let mut snapshot = snapshot_with_orders(vec![ready, waiting_order(2, 1)]);
+        cancel_order(&mut snapshot, 1).expect("ready order can be cancelled");
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Ready);
+
+        let mutation = restore_order(&mut snapshot, 1, &Default::default())
+            .expect("cancelled order can be restored");
+
+        assert_eq!(mutation.result.status, OrderStatus::Waiting);
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Ready);
+        assert!(restore_order(&mut snapshot, 1, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn uncollected_order_is_reminded_then_expires_and_its_stock_goes_to_the_next() {
+        let now = Utc::now();
+        let mut uncollected = waiting_order(1, 1);
+        uncollected.status = OrderStatus::Ready;
+        uncollected.ready_at = Some(now - Duration::minutes(40));
+        uncollected.notify.insert(Notify::Line {
+            user_id: "U1".to_owned(),
+        });
+        let mut snapshot = snapshot_with_orders(vec![uncollected, waiting_order(2, 1)]);
+        let settings = NoShowSettings {
+            reminder_minutes: 10,
+            expire_minutes: Some(30),
         };
-        snapshot.unallocated_stock[Flavor::Tsubuan] = 1;
 
-        let mutation = update_order_priority(&mut snapshot, 2, true).expect("order exists");
+        let mutation = chase_no_shows(&mut snapshot, settings, now);
 
-        assert!(mutation.result.is_priority);
+        let steps = mutation
+            .result
+            .iter()
+            .map(|notice| (notice.order_id, notice.step))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec![(1, NoShowStep::Reminded), (1, NoShowStep::Expired)]
+        );
+        assert_eq!(mutation.status_update.notifications.len(), 1);
         assert_eq!(mutation.status_update.newly_ready_orders, vec![2]);
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Expired);
+        assert_eq!(snapshot.orders[0].no_show_at, Some(now));
+        assert_eq!(snapshot.orders[0].expired_at, Some(now));
+        assert!(
+            chase_no_shows(&mut snapshot, settings, now)
+                .result
+                .is_empty()
+        );
+
+        // The customer turned up after all.
+        let mutation = restore_order(&mut snapshot, 1, &Default::default())
+            .expect("expired order can be restored");
+        assert_eq!(mutation.result.status, OrderStatus::Waiting);
+        assert_eq!(mutation.result.no_show_at, None);
+    }
+
+    #[test]
+    fn editing_ready_order_returns_stock_and_keeps_queue_position() {
+        let mut ready = waiting_order(1, 1);
+        ready.status = OrderStatus::Ready;
+        ready.ready_at = Some(Utc.with_ymd_and_hms(2026, 1, 1, 0, 5, 0).unwrap());
+        ready.notify.insert(Notify::Discord {
+            channel_id: 1,
+            user_id: 1,
+        });
+        let mut snapshot = snapshot_with_orders(vec![ready, waiting_order(2, 1)]);
+        let ordered_at = snapshot.orders[0].ordered_at;
+        let three = Item {
+            flavor: tsubuan(),
+            quantity: 3,
+        };
+
+        let mutation = update_order_items(
+            &mut snapshot,
+            1,
+            vec![three.clone()],
+            Default::default(),
+            Default::default(),
+        )
+        .expect("order is editable");
+
+        assert_eq!(mutation.result.status, OrderStatus::Waiting);
+        assert_eq!(mutation.result.ordered_at, ordered_at);
+        assert_eq!(mutation.result.total_price, 600);
+        assert_eq!(mutation.result.notify.len(), 1);
+        // The returned piece goes to the next order in line.
+        assert_eq!(snapshot.orders[1].status, OrderStatus::Ready);
+
+        complete_order(&mut snapshot, 1).expect("order exists");
+        assert_eq!(
+            update_order_items(
+                &mut snapshot,
+                1,
+                vec![three],
+                Default::default(),
+                Default::default(),
+            )
+            .err(),
+            Some(OrderError::NotEditable {
+                id: 1,
+                status: OrderStatus::Completed,
+            })
+        );
+    }
+
+    #[test]
+    fn handing_over_available_flavors_keeps_the_rest_queued() {
+        let mut large = waiting_order(1, 1);
+        large.items = vec![
+            Item {
+                flavor: tsubuan(),
+                quantity: 4,
+            },
+            Item {
+                flavor: Flavor::new("custard"),
+                quantity: 6,
+            },
+        ];
+        let mut snapshot = snapshot_with_orders(vec![large]);
+        snapshot.unallocated_stock.insert(tsubuan(), 5);
+
+        let mutation = hand_over_items(&mut snapshot, 1, None).expect("tsubuan is in stock");
+
+        assert_eq!(mutation.result.status, OrderStatus::Waiting);
+        assert!(mutation.result.is_partially_handed_over());
+        assert_eq!(snapshot.stock_of(&tsubuan()), 1);
+        assert_eq!(
+            hand_over_items(&mut snapshot, 1, None).err(),
+            Some(OrderError::NothingToHandOver(1))
+        );
+
+        // The rest arrives and goes to the order like any other.
+        snapshot.unallocated_stock.insert(Flavor::new("custard"), 6);
+        let status_update = update_order_statuses(&mut snapshot);
+        assert_eq!(status_update.newly_ready_orders, vec![1]);
+        assert_eq!(snapshot.stock_of(&Flavor::new("custard")), 0);
+
+        // Cancelling then returns only what was set aside; the customer keeps the tsubuan.
+        let mutation = cancel_order(&mut snapshot, 1).expect("order exists");
+        assert_eq!(mutation.result.handed_over.len(), 1);
+        assert_eq!(snapshot.stock_of(&Flavor::new("custard")), 6);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 1);
+    }
+    #[test]
+    fn handing_over_does_not_take_pieces_kept_for_orders_ahead() {
+        let mut large = waiting_order(2, 2);
+        large.items.push(Item {
+            flavor: Flavor::new("custard"),
+            quantity: 6,
+        });
+        let hand_over_under = |policy| {
+            let mut snapshot = snapshot_with_orders(vec![waiting_order(1, 3), large.clone()]);
+            snapshot.allocation_policy = policy;
+            snapshot.unallocated_stock.insert(tsubuan(), 2);
+            hand_over_items(&mut snapshot, 2, None).map(|mutation| mutation.result.handed_over)
+        };
+
+        // Order 1 cannot be filled yet, so greedy allocation lets order 2 past it.
+        assert_eq!(
+            hand_over_under(AllocationPolicyKind::Greedy),
+            Ok(vec![Item {
+                flavor: tsubuan(),
+                quantity: 2,
+            }])
+        );
+        assert_eq!(
+            hand_over_under(AllocationPolicyKind::StrictFifo),
+            Err(OrderError::NothingToHandOver(2))
+        );
+        assert_eq!(
+            hand_over_under(AllocationPolicyKind::ReserveHeadOfLine),
+            Err(OrderError::NothingToHandOver(2))
+        );
     }
 }
@@ -0,0 +1,245 @@
+use std::cmp::Reverse;
+
+use crate::domain::snapshot::{Item, OrderSystemSnapshot, PricingRule, PricingRuleKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PricingError {
+    /// No active rule with the code.
+    UnknownRule(String),
+    /// The order does not meet the rule, e.g. too few pieces for a bundle.
+    NotApplicable(String),
+    /// The total does not fit in the yen amounts orders keep.
+    TotalTooLarge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddPricingRuleError {
+    InvalidCode,
+    InvalidRule,
+    AlreadyExists,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderPrice {
+    pub total_price: u32,
+    pub discount_amount: u32,
+    /// Code of the applied rule, normalized to the catalog spelling.
+    pub pricing_rule: Option<String>,
+}
+
+pub fn price_order(
+    snapshot: &OrderSystemSnapshot,
+    items: &[Item],
+    pricing_rule: Option<&str>,
+) -> Result<OrderPrice, PricingError> {
+    let subtotal = snapshot.items_total(items);
+    let Some(code) = pricing_rule else {
+        return Ok(OrderPrice {
+            total_price: yen(subtotal)?,
+            discount_amount: 0,
+            pricing_rule: None,
+        });
+    };
+    let rule = snapshot
+        .pricing_rule(code)
+        .filter(|rule| rule.is_active)
+        .ok_or_else(|| PricingError::UnknownRule(code.to_owned()))?;
+
+    let discount_amount = match rule.kind {
+        PricingRuleKind::Bundle { quantity, price } => {
+            bundle_discount(snapshot, items, quantity, price)
+                .ok_or_else(|| PricingError::NotApplicable(rule.code.clone()))?
+        }
+        PricingRuleKind::PercentageDiscount { percent } => subtotal * u64::from(percent) / 100,
+        PricingRuleKind::FixedDiscount { amount } => u64::from(amount).min(subtotal),
+    };
+
+    Ok(OrderPrice {
+        total_price: yen(subtotal - discount_amount)?,
+        discount_amount: yen(discount_amount)?,
+        pricing_rule: Some(rule.code.clone()),
+    })
+}
+
+// The most expensive pieces go into bundles so the customer always gets the best price.
+fn bundle_discount(
+    snapshot: &OrderSystemSnapshot,
+    items: &[Item],
+    quantity: u32,
+    price: u32,
+) -> Option<u64> {
+    let mut pieces = items
+        .iter()
+        .map(|item| (snapshot.unit_price(&item.flavor), item.quantity as u64))
+        .collect::<Vec<_>>();
+    let bundles = pieces.iter().map(|(_, count)| count).sum::<u64>() / u64::from(quantity);
+    if bundles == 0 {
+        return None;
+    }
+
+    pieces.sort_unstable_by_key(|&(unit_price, _)| Reverse(unit_price));
+    let mut unbundled = bundles * u64::from(quantity);
+    let mut regular_price = 0;
+    for (unit_price, count) in pieces {
+        let bundled = count.min(unbundled);
+        regular_price += u64::from(unit_price) * bundled;
+        unbundled -= bundled;
+    }
+    Some(regular_price.saturating_sub(u64::from(price) * bundles))
+}
+
+fn yen(amount: u64) -> Result<u32, PricingError> {
+    u32::try_from(amount).map_err(|_| PricingError::TotalTooLarge)
+}
+
+pub fn add_pricing_rule(
+    snapshot: &mut OrderSystemSnapshot,
+    rule: PricingRule,
+) -> Result<PricingRule, AddPricingRuleError> {
+    if !is_valid_code(&rule.code) {
+        return Err(AddPricingRuleError::InvalidCode);
+    }
+    let is_valid_rule = match rule.kind {
+        PricingRuleKind::Bundle { quantity, .. } => quantity >= 2,
+        PricingRuleKind::PercentageDiscount { percent } => (1..=100).contains(&percent),
+        PricingRuleKind::FixedDiscount { amount } => amount > 0,
+    };
+    if !is_valid_rule {
+        return Err(AddPricingRuleError::InvalidRule);
+    }
+    if snapshot.pricing_rule(&rule.code).is_some() {
+        return Err(AddPricingRuleError::AlreadyExists);
+    }
+
+    snapshot.pricing_rules.push(rule.clone());
+    Ok(rule)
+}
+
+pub fn set_pricing_rule_active(
+    snapshot: &mut OrderSystemSnapshot,
+    code: &str,
+    is_active: bool,
+) -> Option<PricingRule> {
+    let rule = snapshot
+        .pricing_rules
+        .iter_mut()
+        .find(|rule| rule.code.eq_ignore_ascii_case(code))?;
+    rule.is_active = is_active;
+    Some(rule.clone())
+}
+
+// Codes are typed by staff on Discord, so keep them short and case-insensitive.
+fn is_valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= 32
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::snapshot::{
+        Flavor, Item, MenuFlavor, OrderSystemSnapshot, PricingRule, PricingRuleKind,
+    };
+    use crate::test_fixtures::{flavor_config, menu_flavor};
+
+    use super::{PricingError, price_order};
+
+    fn snapshot_with_rules() -> OrderSystemSnapshot {
+        let mut snapshot = OrderSystemSnapshot::default();
+        for (id, sort_order, unit_price) in [("tsubuan", 1, 200), ("kurikinton", 2, 250)] {
+            snapshot.add_flavor(
+                MenuFlavor {
+                    unit_price,
+                    ..menu_flavor(id, sort_order)
+                },
+                flavor_config(),
+            );
+        }
+        snapshot.pricing_rules = vec![
+            PricingRule {
+                code: "set3".to_owned(),
+                name: "よりどり3個セット".to_owned(),
+                kind: PricingRuleKind::Bundle {
+                    quantity: 3,
+                    price: 550,
+                },
+                is_active: true,
+            },
+            PricingRule {
+                code: "STAFF".to_owned(),
+                name: "まかない".to_owned(),
+                kind: PricingRuleKind::PercentageDiscount { percent: 50 },
+                is_active: true,
+            },
+        ];
+        snapshot
+    }
+
+    fn items(tsubuan: usize, kurikinton: usize) -> Vec<Item> {
+        vec![
+            Item {
+                flavor: Flavor::new("tsubuan"),
+                quantity: tsubuan,
+            },
+            Item {
+                flavor: Flavor::new("kurikinton"),
+                quantity: kurikinton,
+            },
+        ]
+    }
+
+    #[test]
+    fn bundle_takes_the_most_expensive_pieces() {
+        let snapshot = snapshot_with_rules();
+
+        // 250 + 250 + 200 go into the bundle, the last 200 is charged normally.
+        let price = price_order(&snapshot, &items(2, 2), Some("set3")).expect("bundle applies");
+
+        assert_eq!(price.discount_amount, 150);
+        assert_eq!(price.total_price, 750);
+    }
+
+    #[test]
+    fn coupon_code_is_case_insensitive() {
+        let snapshot = snapshot_with_rules();
+
+        let price = price_order(&snapshot, &items(1, 0), Some("staff")).expect("coupon applies");
+
+        assert_eq!(price.total_price, 100);
+        assert_eq!(price.pricing_rule.as_deref(), Some("STAFF"));
+    }
+
+    #[test]
+    fn bundle_needs_enough_pieces() {
+        let mut snapshot = snapshot_with_rules();
+
+        assert_eq!(
+            price_order(&snapshot, &items(1, 1), Some("set3")),
+            Err(PricingError::NotApplicable("set3".to_owned()))
+        );
+
+        snapshot.pricing_rules[0].is_active = false;
+        assert_eq!(
+            price_order(&snapshot, &items(3, 0), Some("set3")),
+            Err(PricingError::UnknownRule("set3".to_owned()))
+        );
+    }
+
+    #[test]
+    fn large_orders_are_priced_without_overflow() {
+        let mut snapshot = snapshot_with_rules();
+
+        // Bundles are counted, not laid out piece by piece.
+        let price = price_order(&snapshot, &items(3_000_000, 1), Some("set3")).expect("bundle");
+        assert_eq!(price.discount_amount, 50_000_050);
+        assert_eq!(price.total_price, 550_000_200);
+
+        snapshot.menu[0].unit_price = u32::MAX;
+        assert_eq!(
+            price_order(&snapshot, &items(2, 0), None),
+            Err(PricingError::TotalTooLarge)
+        );
+    }
+}
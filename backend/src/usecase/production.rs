@@ -1,13 +1,126 @@
+use chrono::Utc;
+
 use crate::domain::order_status::{self, StatusUpdate};
-use crate::domain::snapshot::{Item, OrderSystemSnapshot};
+use crate::domain::snapshot::{Batch, Flavor, Item, OrderSystemSnapshot};
+use crate::domain::stock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    UnknownFlavor,
+    ZeroQuantity,
+    NotFound,
+    AlreadyFinished,
+}
 
 pub fn register_completed_production(
     snapshot: &mut OrderSystemSnapshot,
     production: Vec<Item>,
-) -> StatusUpdate {
+) -> Option<StatusUpdate> {
+    if !production
+        .iter()
+        .all(|item| snapshot.menu_flavor(&item.flavor).is_some())
+    {
+        return None;
+    }
+
+    let now = Utc::now();
     for item in production {
-        snapshot.unallocated_stock[item.flavor] += item.quantity;
+        stock::add_stock(snapshot, &item.flavor, item.quantity, now);
+    }
+
+    Some(order_status::update_order_statuses(snapshot))
+}
+
+// Without a quantity the batch is a full plate of the flavor.
+pub fn start_batch(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: Flavor,
+    quantity: Option<usize>,
+) -> Result<(Batch, StatusUpdate), BatchError> {
+    let config = snapshot
+        .flavor_configs
+        .get(&flavor)
+        .filter(|_| snapshot.menu_flavor(&flavor).is_some())
+        .ok_or(BatchError::UnknownFlavor)?;
+    let quantity = quantity.unwrap_or(config.quantity_per_batch as usize);
+    if quantity == 0 {
+        return Err(BatchError::ZeroQuantity);
+    }
+
+    let id = snapshot
+        .batches
+        .iter()
+        .map(|batch| batch.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let batch = Batch {
+        id,
+        flavor,
+        quantity,
+        started_at: Utc::now(),
+        finished_at: None,
+    };
+    snapshot.batches.push(batch.clone());
+
+    Ok((batch, order_status::update_order_statuses(snapshot)))
+}
+
+pub fn finish_batch(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+) -> Result<(Batch, StatusUpdate), BatchError> {
+    let batch = snapshot
+        .batches
+        .iter_mut()
+        .find(|batch| batch.id == id)
+        .ok_or(BatchError::NotFound)?;
+    if !batch.is_active() {
+        return Err(BatchError::AlreadyFinished);
+    }
+    let finished_at = Utc::now();
+    batch.finished_at = Some(finished_at);
+    let batch = batch.clone();
+
+    stock::add_stock(snapshot, &batch.flavor, batch.quantity, finished_at);
+
+    Ok((batch, order_status::update_order_statuses(snapshot)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::snapshot::{OrderStatus, OrderSystemSnapshot};
+    use crate::test_fixtures::{snapshot_with_orders, tsubuan, waiting_order};
+
+    use super::{BatchError, finish_batch, start_batch};
+
+    fn snapshot_with_waiting_order(quantity: usize) -> OrderSystemSnapshot {
+        snapshot_with_orders(vec![waiting_order(1, quantity)])
     }
 
-    order_status::update_order_statuses(snapshot)
+    #[test]
+    fn batch_moves_covered_orders_to_cooking_then_ready() {
+        let mut snapshot = snapshot_with_waiting_order(3);
+
+        let (batch, _) = start_batch(&mut snapshot, tsubuan(), None).expect("flavor exists");
+        assert_eq!(batch.quantity, 9);
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Cooking);
+
+        let (_, status_update) = finish_batch(&mut snapshot, batch.id).expect("batch is active");
+        assert_eq!(status_update.newly_ready_orders, vec![1]);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 6);
+        assert_eq!(
+            finish_batch(&mut snapshot, batch.id).err(),
+            Some(BatchError::AlreadyFinished)
+        );
+    }
+
+    #[test]
+    fn small_batch_leaves_larger_order_waiting() {
+        let mut snapshot = snapshot_with_waiting_order(3);
+
+        start_batch(&mut snapshot, tsubuan(), Some(2)).expect("flavor exists");
+
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Waiting);
+    }
 }
@@ -0,0 +1,35 @@
+use crate::domain::order_status::{self, StatusUpdate};
+use crate::domain::snapshot::{OrderSystemSnapshot, Station};
+
+pub fn add_station(snapshot: &mut OrderSystemSnapshot, name: String, plate_count: u32) -> Station {
+    let id = snapshot
+        .stations
+        .iter()
+        .map(|station| station.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let station = Station {
+        id,
+        name,
+        plate_count,
+    };
+    snapshot.stations.push(station.clone());
+    station
+}
+
+// Plates decide which orders are cooking, so allocation runs again.
+pub fn set_station_plate_count(
+    snapshot: &mut OrderSystemSnapshot,
+    id: u32,
+    plate_count: u32,
+) -> Option<(Station, StatusUpdate)> {
+    let station = snapshot
+        .stations
+        .iter_mut()
+        .find(|station| station.id == id)?;
+    station.plate_count = plate_count;
+    let result = station.clone();
+
+    Some((result, order_status::update_order_statuses(snapshot)))
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::order_status::{self, StatusUpdate};
+use crate::domain::snapshot::{Flavor, OrderSystemSnapshot, StockAdjustment, StockReason};
+use crate::domain::stock::{self, StockSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockError {
+    UnknownFlavor,
+    ZeroQuantity,
+    /// Recounts are recorded through `adjust_stock`, not as waste.
+    NotWasteReason,
+    InsufficientStock {
+        available: usize,
+    },
+    /// The recount matches the recorded stock.
+    Unchanged,
+}
+
+pub fn discard_stock(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: Flavor,
+    quantity: usize,
+    reason: StockReason,
+    note: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(StockAdjustment, StatusUpdate), StockError> {
+    if snapshot.menu_flavor(&flavor).is_none() {
+        return Err(StockError::UnknownFlavor);
+    }
+    if quantity == 0 {
+        return Err(StockError::ZeroQuantity);
+    }
+    if !reason.is_waste() {
+        return Err(StockError::NotWasteReason);
+    }
+    let available = snapshot.stock_of(&flavor);
+    if quantity > available {
+        return Err(StockError::InsufficientStock { available });
+    }
+
+    snapshot
+        .unallocated_stock
+        .insert(flavor.clone(), available - quantity);
+    let adjustment = record(snapshot, flavor, -(quantity as i64), reason, note, now);
+    Ok((adjustment, order_status::update_order_statuses(snapshot)))
+}
+
+/// Sets the stock of a flavor to what staff actually counted.
+pub fn adjust_stock(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: Flavor,
+    counted: usize,
+    note: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(StockAdjustment, StatusUpdate), StockError> {
+    if snapshot.menu_flavor(&flavor).is_none() {
+        return Err(StockError::UnknownFlavor);
+    }
+    let recorded = snapshot.stock_of(&flavor);
+    if counted == recorded {
+        return Err(StockError::Unchanged);
+    }
+
+    if counted > recorded {
+        stock::add_stock(snapshot, &flavor, counted - recorded, now);
+    } else {
+        snapshot.unallocated_stock.insert(flavor.clone(), counted);
+    }
+    let delta = counted as i64 - recorded as i64;
+    let adjustment = record(snapshot, flavor, delta, StockReason::Miscount, note, now);
+    Ok((adjustment, order_status::update_order_statuses(snapshot)))
+}
+
+/// Discards every piece past the freshness limit, one adjustment per flavor.
+pub fn discard_expired_stock(
+    snapshot: &mut OrderSystemSnapshot,
+    now: DateTime<Utc>,
+    settings: StockSettings,
+) -> Vec<StockAdjustment> {
+    stock::expired_quantities(snapshot, now, settings)
+        .into_iter()
+        .filter_map(|(flavor, quantity)| {
+            discard_stock(snapshot, flavor, quantity, StockReason::Stale, None, now)
+                .ok()
+                .map(|(adjustment, _)| adjustment)
+        })
+        .collect()
+}
+
+fn record(
+    snapshot: &mut OrderSystemSnapshot,
+    flavor: Flavor,
+    delta: i64,
+    reason: StockReason,
+    note: Option<String>,
+    now: DateTime<Utc>,
+) -> StockAdjustment {
+    let id = snapshot
+        .stock_adjustments
+        .iter()
+        .map(|adjustment| adjustment.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let adjustment = StockAdjustment {
+        id,
+        flavor,
+        delta,
+        reason,
+        note: note.filter(|note| !note.trim().is_empty()),
+        recorded_at: now,
+    };
+    snapshot.stock_adjustments.push(adjustment.clone());
+    adjustment
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::domain::snapshot::{OrderSystemSnapshot, StockReason};
+    use crate::domain::stock::{self, StockSettings};
+    use crate::test_fixtures::{snapshot_with_orders, tsubuan};
+
+    use super::{StockError, adjust_stock, discard_expired_stock, discard_stock};
+
+    fn snapshot_with_stock(quantity: usize) -> OrderSystemSnapshot {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        let produced_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        stock::add_stock(&mut snapshot, &tsubuan(), quantity, produced_at);
+        snapshot
+    }
+
+    #[test]
+    fn discarding_more_than_stock_is_rejected() {
+        let mut snapshot = snapshot_with_stock(2);
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 2, 0, 0).unwrap();
+
+        assert_eq!(
+            discard_stock(&mut snapshot, tsubuan(), 3, StockReason::Burnt, None, now).err(),
+            Some(StockError::InsufficientStock { available: 2 })
+        );
+        assert_eq!(
+            discard_stock(
+                &mut snapshot,
+                tsubuan(),
+                1,
+                StockReason::Miscount,
+                None,
+                now
+            )
+            .err(),
+            Some(StockError::NotWasteReason)
+        );
+
+        let (adjustment, _) =
+            discard_stock(&mut snapshot, tsubuan(), 2, StockReason::Dropped, None, now)
+                .expect("discard");
+        assert_eq!(adjustment.delta, -2);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+        assert!(snapshot.stock_lots.is_empty());
+    }
+
+    #[test]
+    fn recount_records_the_difference() {
+        let mut snapshot = snapshot_with_stock(5);
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 2, 0, 0).unwrap();
+
+        let (adjustment, _) = adjust_stock(
+            &mut snapshot,
+            tsubuan(),
+            3,
+            Some("数え直し".to_owned()),
+            now,
+        )
+        .expect("recount");
+
+        assert_eq!(adjustment.delta, -2);
+        assert_eq!(adjustment.reason, StockReason::Miscount);
+        assert_eq!(
+            adjust_stock(&mut snapshot, tsubuan(), 3, None, now).err(),
+            Some(StockError::Unchanged)
+        );
+    }
+
+    #[test]
+    fn stale_pieces_are_discarded_after_the_freshness_limit() {
+        let mut snapshot = snapshot_with_stock(4);
+        let produced_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        stock::add_stock(
+            &mut snapshot,
+            &tsubuan(),
+            3,
+            produced_at + Duration::minutes(50),
+        );
+        let settings = StockSettings {
+            freshness_minutes: 60,
+            auto_discard: true,
+        };
+
+        let discarded =
+            discard_expired_stock(&mut snapshot, produced_at + Duration::minutes(70), settings);
+
+        assert_eq!(discarded.len(), 1);
+        assert_eq!(discarded[0].delta, -4);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 3);
+    }
+}
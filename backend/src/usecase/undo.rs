@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::domain::order_number::DisplayNumberSettings;
+use crate::domain::order_status::{self, StatusUpdate};
+use crate::domain::snapshot::{Flavor, FlavorConfig, Item, OrderStatus, OrderSystemSnapshot};
+use crate::domain::stock;
+use crate::domain::undo::UndoAction;
+use crate::usecase::order::{self, OrderError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoError {
+    NothingToUndo,
+    Order(OrderError),
+    /// Pieces of the production report have been handed over since.
+    PiecesHandedOver(Flavor),
+    /// The flavor or its station has been removed since.
+    FlavorGone(Flavor),
+    /// The action is kept on the stack only so that undoing stops at it.
+    NotUndoable,
+}
+
+impl From<OrderError> for UndoError {
+    fn from(error: OrderError) -> Self {
+        UndoError::Order(error)
+    }
+}
+
+impl fmt::Display for UndoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UndoError::NothingToUndo => write!(f, "there is no such action to undo"),
+            UndoError::Order(error) => error.fmt(f),
+            UndoError::PiecesHandedOver(flavor) => {
+                write!(f, "pieces of {flavor} have already been handed over")
+            }
+            UndoError::FlavorGone(flavor) => {
+                write!(f, "{flavor} or its station no longer exists")
+            }
+            UndoError::NotUndoable => write!(f, "this action cannot be undone"),
+        }
+    }
+}
+
+// Reverts a staff action and allocates again. Customers who were told their order is ready are
+// not told again, and are told it was a mistake if the order is back in the queue.
+pub fn undo(
+    snapshot: &mut OrderSystemSnapshot,
+    action: &UndoAction,
+    display_numbers: &DisplayNumberSettings,
+) -> Result<StatusUpdate, UndoError> {
+    let told_ready: HashSet<u32> = snapshot
+        .orders
+        .iter()
+        .filter(|order| {
+            matches!(order.status, OrderStatus::Ready | OrderStatus::Completed)
+                && order.ready_at.is_some()
+        })
+        .map(|order| order.id)
+        .collect();
+
+    let mut status_update = match action {
+        UndoAction::CreateOrder { order_id } => {
+            order::cancel_order(snapshot, *order_id)?.status_update
+        }
+        UndoAction::CompleteOrder { order_id } => {
+            order::reopen_order(snapshot, *order_id, display_numbers)?.status_update
+        }
+        UndoAction::CancelOrder { order_id } => {
+            order::restore_order(snapshot, *order_id, display_numbers)?.status_update
+        }
+        UndoAction::UpdatePriority { order_id, previous } => {
+            order::update_order_priority(snapshot, *order_id, *previous)?.status_update
+        }
+        UndoAction::ReportProduction { items } => take_back_production(snapshot, items)?,
+        UndoAction::SetFlavorConfig {
+            flavor,
+            previous,
+            applied,
+        } => {
+            let current = snapshot
+                .flavor_configs
+                .get(flavor)
+                .copied()
+                .ok_or_else(|| UndoError::FlavorGone(flavor.clone()))?;
+            let reverted = revert_changed_fields(current, *previous, *applied);
+            order::set_flavor_config(snapshot, flavor.clone(), reverted)
+                .ok_or_else(|| UndoError::FlavorGone(flavor.clone()))?
+        }
+        UndoAction::FinishBatch { batch_id } => reopen_batch(snapshot, *batch_id)?,
+        UndoAction::HandOverItems { .. } => return Err(UndoError::NotUndoable),
+    };
+
+    status_update
+        .newly_ready_orders
+        .retain(|id| !told_ready.contains(id));
+    status_update
+        .notifications
+        .retain(|notification| !told_ready.contains(&notification.order_id));
+    status_update
+        .notifications
+        .extend(order_status::correction_notifications(
+            snapshot,
+            &told_ready,
+        ));
+    Ok(status_update)
+}
+
+// Fields changed since keep their new values.
+fn revert_changed_fields(
+    current: FlavorConfig,
+    previous: FlavorConfig,
+    applied: FlavorConfig,
+) -> FlavorConfig {
+    fn revert<T: PartialEq>(current: T, previous: T, applied: T) -> T {
+        if previous == applied {
+            current
+        } else {
+            previous
+        }
+    }
+    FlavorConfig {
+        cooking_time_minutes: revert(
+            current.cooking_time_minutes,
+            previous.cooking_time_minutes,
+            applied.cooking_time_minutes,
+        ),
+        quantity_per_batch: revert(
+            current.quantity_per_batch,
+            previous.quantity_per_batch,
+            applied.quantity_per_batch,
+        ),
+        daily_limit: revert(
+            current.daily_limit,
+            previous.daily_limit,
+            applied.daily_limit,
+        ),
+        is_sold_out: revert(
+            current.is_sold_out,
+            previous.is_sold_out,
+            applied.is_sold_out,
+        ),
+        station_id: revert(current.station_id, previous.station_id, applied.station_id),
+    }
+}
+
+// The batch is grilling again and its pieces are taken back like a production report's.
+fn reopen_batch(snapshot: &mut OrderSystemSnapshot, id: u32) -> Result<StatusUpdate, UndoError> {
+    let batch = snapshot
+        .batches
+        .iter_mut()
+        .find(|batch| batch.id == id && !batch.is_active())
+        .ok_or(UndoError::NothingToUndo)?;
+    let finished_at = batch.finished_at.take();
+    let item = Item {
+        flavor: batch.flavor.clone(),
+        quantity: batch.quantity,
+    };
+    take_back_production(snapshot, &[item]).inspect_err(|_| {
+        if let Some(batch) = snapshot.batches.iter_mut().find(|batch| batch.id == id) {
+            batch.finished_at = finished_at;
+        }
+    })
+}
+
+// Pieces that went to ready orders are taken back from the orders that became ready last, which
+// queue again.
+fn take_back_production(
+    snapshot: &mut OrderSystemSnapshot,
+    items: &[Item],
+) -> Result<StatusUpdate, UndoError> {
+    let mut reported = HashMap::<Flavor, usize>::new();
+    for item in items {
+        *reported.entry(item.flavor.clone()).or_insert(0) += item.quantity;
+    }
+    // Checked up front so that nothing is touched when the undo is refused.
+    for (flavor, &quantity) in &reported {
+        let held: usize = snapshot
+            .orders
+            .iter()
+            .filter(|order| order.status == OrderStatus::Ready)
+            .flat_map(|order| order.outstanding())
+            .filter(|(held_flavor, _)| *held_flavor == flavor)
+            .map(|(_, quantity)| quantity)
+            .sum();
+        if snapshot.stock_of(flavor) + held < quantity {
+            return Err(UndoError::PiecesHandedOver(flavor.clone()));
+        }
+    }
+
+    for (flavor, quantity) in reported {
+        while snapshot.stock_of(&flavor) < quantity {
+            let order = snapshot
+                .orders
+                .iter_mut()
+                .filter(|order| {
+                    order.status == OrderStatus::Ready
+                        && order.outstanding().any(|(held, _)| held == &flavor)
+                })
+                .max_by_key(|order| (order.ready_at, order.id))
+                .expect("checked that ready orders hold the pieces");
+            let ready_at = order.ready_at.take().unwrap_or(order.ordered_at);
+            order.status = OrderStatus::Waiting;
+            order.no_show_at = None;
+            let outstanding: Vec<_> = order
+                .outstanding()
+                .map(|(flavor, quantity)| (flavor.clone(), quantity))
+                .collect();
+            for (held, quantity) in outstanding {
+                stock::add_stock(snapshot, &held, quantity, ready_at);
+            }
+        }
+        let left = snapshot.stock_of(&flavor) - quantity;
+        snapshot.unallocated_stock.insert(flavor, left);
+    }
+    Ok(order_status::update_order_statuses(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use crate::domain::order_number::DisplayNumberSettings;
+    use crate::domain::order_status::update_order_statuses;
+    use crate::domain::snapshot::{FlavorConfig, Item, Notify, Order, OrderStatus};
+    use crate::domain::undo::UndoAction;
+    use crate::test_fixtures::{flavor_config, snapshot_with_orders, tsubuan, waiting_order};
+    use crate::usecase::production::{finish_batch, start_batch};
+
+    use super::{UndoError, undo};
+
+    fn order(id: u32, quantity: usize) -> Order {
+        Order {
+            ordered_at: Utc::now() - Duration::minutes(10 - i64::from(id)),
+            notify: [Notify::Line {
+                user_id: format!("user-{id}"),
+            }]
+            .into(),
+            ..waiting_order(id, quantity)
+        }
+    }
+
+    #[test]
+    fn production_undo_requeues_orders_and_corrects_their_customers() {
+        let mut snapshot = snapshot_with_orders(vec![order(1, 2), order(2, 2)]);
+        snapshot.unallocated_stock.insert(tsubuan(), 5);
+        update_order_statuses(&mut snapshot);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 1);
+
+        let action = UndoAction::ReportProduction {
+            items: vec![Item {
+                flavor: tsubuan(),
+                quantity: 3,
+            }],
+        };
+        let status_update = undo(&mut snapshot, &action, &DisplayNumberSettings::default())
+            .expect("pieces are still on the counter");
+
+        let statuses: Vec<_> = snapshot.orders.iter().map(|order| order.status).collect();
+        assert_eq!(statuses, vec![OrderStatus::Ready, OrderStatus::Waiting]);
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+        assert!(status_update.newly_ready_orders.is_empty());
+        let corrected: Vec<_> = status_update
+            .notifications
+            .iter()
+            .map(|notification| notification.order_id)
+            .collect();
+        assert_eq!(corrected, vec![2]);
+
+        snapshot.orders[0].status = OrderStatus::Completed;
+        assert_eq!(
+            undo(&mut snapshot, &action, &DisplayNumberSettings::default()),
+            Err(UndoError::PiecesHandedOver(tsubuan()))
+        );
+    }
+
+    #[test]
+    fn flavor_config_undo_restores_only_the_fields_it_changed() {
+        let mut snapshot = snapshot_with_orders(Vec::new());
+        let previous = flavor_config();
+        let applied = FlavorConfig {
+            daily_limit: Some(10),
+            ..previous
+        };
+        // Switched to sold out after the limit was set.
+        snapshot.flavor_configs.insert(
+            tsubuan(),
+            FlavorConfig {
+                is_sold_out: true,
+                ..applied
+            },
+        );
+
+        let action = UndoAction::SetFlavorConfig {
+            flavor: tsubuan(),
+            previous,
+            applied,
+        };
+        undo(&mut snapshot, &action, &DisplayNumberSettings::default()).expect("flavor exists");
+
+        assert_eq!(
+            snapshot.flavor_configs[&tsubuan()],
+            FlavorConfig {
+                is_sold_out: true,
+                ..previous
+            }
+        );
+    }
+
+    #[test]
+    fn batch_undo_takes_its_pieces_back_and_grills_it_again() {
+        let mut snapshot = snapshot_with_orders(vec![order(1, 2)]);
+        let (batch, _) = start_batch(&mut snapshot, tsubuan(), Some(3)).expect("flavor exists");
+        finish_batch(&mut snapshot, batch.id).expect("batch is active");
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Ready);
+
+        let action = UndoAction::FinishBatch { batch_id: batch.id };
+        undo(&mut snapshot, &action, &DisplayNumberSettings::default())
+            .expect("pieces are still on the counter");
+
+        assert!(snapshot.batches[0].is_active());
+        assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+        assert_eq!(snapshot.orders[0].status, OrderStatus::Cooking);
+        assert_eq!(
+            undo(
+                &mut snapshot,
+                &UndoAction::HandOverItems { order_id: 1 },
+                &DisplayNumberSettings::default()
+            ),
+            Err(UndoError::NotUndoable)
+        );
+    }
+}
@@ -5,10 +5,21 @@ use axum::{
     body::{self, Body},
     http::Request,
 };
-use taiyaq_backend::domain::snapshot::{Flavor, Item, OrderStatus};
+use chrono::{Duration, TimeZone, Utc};
+use taiyaq_backend::api::model::StaffEvent;
+use taiyaq_backend::domain::availability::store_date;
+use taiyaq_backend::domain::business_day::BusinessDay;
+use taiyaq_backend::domain::no_show::{NoShowSettings, NoShowStep};
+use taiyaq_backend::domain::order_number::DisplayOrderNumber;
+use taiyaq_backend::domain::pre_order::PreOrderSettings;
+use taiyaq_backend::domain::snapshot::{Batch, Flavor, Item, Order, OrderStatus, PaymentMethod};
+use taiyaq_backend::domain::wait_history::{WaitTimeEstimator, WaitTimeSettings};
 use tower::ServiceExt;
 
-use crate::helper::{TestRequestExt, deserialize_json, make_router, registry_with_snapshot};
+use crate::helper::{
+    TestRequestExt, deserialize_json, make_router, order, registry_with_failing_storage,
+    registry_with_snapshot, tsubuan, waiting_order_with_items,
+};
 
 #[tokio::test]
 async fn get_openapi_json_200_includes_paths() -> anyhow::Result<()> {
@@ -106,13 +117,14 @@ async fn create_staff_order_401_without_bearer_token() -> anyhow::Result<()> {
 #[tokio::test]
 async fn create_staff_order_201_creates_order() -> anyhow::Result<()> {
     let registry = registry_with_snapshot(|snapshot| {
-        snapshot.unallocated_stock[Flavor::Tsubuan] = 1;
+        snapshot.unallocated_stock.insert(tsubuan(), 1);
     });
     let app = make_router(registry.clone());
 
     let request = serde_json::json!({
         "items": [{ "flavor": "tsubuan", "quantity": 1 }],
-        "isPriority": true
+        "isPriority": true,
+        "paymentMethod": "qrCode"
     });
     let response = app
         .oneshot(
@@ -128,20 +140,323 @@ async fn create_staff_order_201_creates_order() -> anyhow::Result<()> {
     assert_eq!(body["id"], 1);
     assert_eq!(body["status"], "ready");
     assert_eq!(body["isPriority"], true);
+    assert_eq!(body["totalPrice"], 200);
+    assert_eq!(body["paymentMethod"], "qrCode");
 
     let snapshot = registry.snapshot().await?;
     assert_eq!(snapshot.orders.len(), 1);
-    assert_eq!(snapshot.unallocated_stock[Flavor::Tsubuan], 0);
+    assert_eq!(snapshot.stock_of(&tsubuan()), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_staff_order_500_when_storage_fails_keeps_state_unchanged() -> anyhow::Result<()> {
+    let app = make_router(registry_with_failing_storage(|snapshot| {
+        snapshot.orders = vec![helper::order(1, OrderStatus::Waiting)];
+        snapshot.unallocated_stock.insert(tsubuan(), 5);
+    }));
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 2 }]
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+    assert_eq!(
+        response.status(),
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    let response = app
+        .oneshot(Request::get("/api/orders/display").body(Body::empty())?)
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["ready"].as_array().map(Vec::len), Some(0));
+    assert_eq!(body["waiting"].as_array().map(Vec::len), Some(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_order_priority_200_moves_order_to_another_tier() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 1 }],
+        "priority": "accessibility"
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["priority"], "accessibility");
+    assert_eq!(body["isPriority"], true);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::put("/api/staff/orders/1/priority")
+                .bearer()
+                .application_json()
+                .body(Body::from("{}"))?,
+        )
+        .await?;
+    assert_eq!(
+        response.status(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let request = serde_json::json!({ "priority": "staff" });
+    let response = app
+        .oneshot(
+            Request::put("/api/staff/orders/1/priority")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["priority"], "staff");
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_order_history_200_lists_changes_by_staff() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.orders = vec![waiting_order_with_items(
+            1,
+            vec![Item {
+                flavor: tsubuan(),
+                quantity: 1,
+            }],
+        )];
+    }));
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/1/cancel")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/staff/orders/1/history")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["kind"], "statusChanged");
+    assert_eq!(body[0]["actor"], serde_json::json!({ "type": "staff" }));
+    assert_eq!(body[0]["before"], "waiting");
+    assert_eq!(body[0]["after"], "cancelled");
+
+    let response = app
+        .oneshot(
+            Request::get("/api/staff/orders/2/history")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_audit_trail_200_lists_production_reports() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+    let order = serde_json::json!({ "items": [{ "flavor": "tsubuan", "quantity": 1 }] });
+    let production = serde_json::json!({ "items": [{ "flavor": "tsubuan", "quantity": 3 }] });
+    for (uri, request) in [
+        ("/api/staff/orders", order),
+        ("/api/staff/production", production),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post(uri)
+                    .bearer()
+                    .application_json()
+                    .body(Body::from(request.to_string()))?,
+            )
+            .await?;
+        assert!(response.status().is_success());
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/staff/audit-trail?kind=productionReported")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["orderId"], serde_json::Value::Null);
+    assert_eq!(body[0]["flavor"], "tsubuan");
+    assert_eq!(body[0]["before"], "0");
+    assert_eq!(body[0]["after"], "3");
+
+    let uri = format!(
+        "/api/staff/audit-trail?from={}",
+        (Utc::now() + Duration::minutes(1)).format("%Y-%m-%dT%H:%M:%SZ")
+    );
+    let response = app
+        .oneshot(Request::get(uri).bearer().body(Body::empty())?)
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body.as_array().map(Vec::len), Some(0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn undo_200_takes_back_the_latest_staff_action() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+    let order = serde_json::json!({ "items": [{ "flavor": "tsubuan", "quantity": 1 }] });
+    let production = serde_json::json!({ "items": [{ "flavor": "tsubuan", "quantity": 1 }] });
+    for (uri, request) in [
+        ("/api/staff/orders", order),
+        ("/api/staff/production", production),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post(uri)
+                    .bearer()
+                    .application_json()
+                    .body(Body::from(request.to_string()))?,
+            )
+            .await?;
+        assert!(response.status().is_success());
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/undo")
+                .bearer()
+                .application_json()
+                .body(Body::from("{}"))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["action"]["type"], "reportProduction");
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/orders/1").body(Body::empty())?)
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "waiting");
+
+    let response = app
+        .oneshot(
+            Request::get("/api/staff/undo")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["action"]["type"], "createOrder");
+    Ok(())
+}
+
+#[tokio::test]
+async fn undo_takes_back_sold_out_but_refuses_hand_over() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.orders = vec![Order {
+            status: OrderStatus::Ready,
+            ready_at: Some(Utc::now()),
+            ..waiting_order_with_items(
+                1,
+                vec![Item {
+                    flavor: tsubuan(),
+                    quantity: 1,
+                }],
+            )
+        }];
+    }));
+    let sold_out = serde_json::json!({ "isSoldOut": true });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::put("/api/staff/flavors/tsubuan/sold-out")
+                .bearer()
+                .application_json()
+                .body(Body::from(sold_out.to_string()))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let undo = || {
+        Request::post("/api/staff/undo")
+            .bearer()
+            .application_json()
+            .body(Body::from("{}"))
+    };
+    let response = app.clone().oneshot(undo()?).await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["action"]["type"], "setFlavorConfig");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/staff/flavors/config")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["tsubuan"]["isSoldOut"], false);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/1/handover")
+                .bearer()
+                .application_json()
+                .body(Body::from("{}"))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let response = app.oneshot(undo()?).await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "not-undoable");
     Ok(())
 }
 
 #[tokio::test]
 async fn update_production_200_returns_newly_ready_orders() -> anyhow::Result<()> {
     let app = make_router(registry_with_snapshot(|snapshot| {
-        snapshot.orders = vec![helper::waiting_order_with_items(
+        snapshot.orders = vec![waiting_order_with_items(
             1,
             vec![Item {
-                flavor: Flavor::Tsubuan,
+                flavor: tsubuan(),
                 quantity: 1,
             }],
         )];
@@ -165,3 +480,985 @@ async fn update_production_200_returns_newly_ready_orders() -> anyhow::Result<()
     assert_eq!(body["unallocatedItems"], serde_json::json!([]));
     Ok(())
 }
+
+#[tokio::test]
+async fn create_staff_order_400_for_retired_flavor() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.menu[0].is_active = false;
+    }));
+
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 1 }]
+    });
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_staff_order_422_for_duplicate_flavor() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+
+    let request = serde_json::json!({
+        "items": [
+            { "flavor": "tsubuan", "quantity": 1 },
+            { "flavor": "tsubuan", "quantity": 2 }
+        ]
+    });
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(
+        response.status(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+    assert_eq!(
+        response.headers()[axum::http::header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "duplicate-flavor");
+    assert_eq!(body["status"], 422);
+    Ok(())
+}
+
+#[tokio::test]
+async fn complete_order_404_problem_for_unknown_order() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/orders/42/complete")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "order-not-found");
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_flavor_201_adds_flavor_to_menu() -> anyhow::Result<()> {
+    let registry = registry_with_snapshot(|_| {});
+    let app = make_router(registry.clone());
+
+    let request = serde_json::json!({
+        "id": "matcha",
+        "displayName": "抹茶",
+        "sortOrder": 4,
+        "cookingTimeMinutes": 12,
+        "quantityPerBatch": 6,
+        "unitPrice": 250
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/flavors")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["id"], "matcha");
+    assert_eq!(body["config"]["quantityPerBatch"], 6);
+
+    let response = app
+        .oneshot(Request::get("/api/wait-times").body(Body::empty())?)
+        .await?;
+
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["flavors"][3]["flavor"], "matcha");
+    assert_eq!(body["flavors"][3]["displayName"], "抹茶");
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_flavor_409_for_existing_flavor() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+
+    let request = serde_json::json!({
+        "id": "tsubuan",
+        "displayName": "つぶあん",
+        "sortOrder": 1,
+        "cookingTimeMinutes": 15,
+        "quantityPerBatch": 9,
+        "unitPrice": 200
+    });
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/flavors")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_flavor_active_200_removes_flavor_from_menu() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+
+    let request = serde_json::json!({ "isActive": false });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::put("/api/staff/flavors/kurikinton/active")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let response = app
+        .oneshot(Request::get("/api/menu").body(Body::empty())?)
+        .await?;
+
+    let body = deserialize_json(response).await?;
+    let ids = body
+        .as_array()
+        .expect("menu is an array")
+        .iter()
+        .map(|flavor| flavor["id"].as_str().unwrap_or_default())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["tsubuan", "custard"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_staff_order_409_over_daily_limit() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+
+    let request = serde_json::json!({ "dailyLimit": 2 });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::put("/api/staff/flavors/tsubuan/daily-limit")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 3 }]
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+
+    let response = app
+        .oneshot(Request::get("/api/wait-times").body(Body::empty())?)
+        .await?;
+
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["flavors"][0]["remainingQuantity"], 2);
+    assert_eq!(body["flavors"][0]["isSoldOut"], false);
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_revenue_200_nets_out_cancellations_and_refunds() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        for (id, status) in [
+            (1, OrderStatus::Completed),
+            (2, OrderStatus::Completed),
+            (3, OrderStatus::Cancelled),
+        ] {
+            snapshot.orders.push(Order {
+                total_price: 400,
+                payment_method: Some(PaymentMethod::Cash),
+                ..order(id, status)
+            });
+        }
+    }));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/2/refund")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert!(body["refundedAt"].is_string());
+
+    let response = app
+        .oneshot(
+            Request::get("/api/staff/revenue?date=2026-01-01")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["grossSales"], 800);
+    assert_eq!(body["cancelledAmount"], 400);
+    assert_eq!(body["refundedAmount"], 400);
+    assert_eq!(body["netSales"], 400);
+    assert_eq!(body["byPaymentMethod"][0]["paymentMethod"], "cash");
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_staff_order_201_applies_bundle() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|_| {}));
+
+    let request = serde_json::json!({
+        "code": "set3",
+        "name": "よりどり3個セット",
+        "kind": { "type": "bundle", "quantity": 3, "price": 500 }
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/pricing-rules")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+
+    let request = serde_json::json!({
+        "items": [
+            { "flavor": "tsubuan", "quantity": 2 },
+            { "flavor": "custard", "quantity": 1 }
+        ],
+        "pricingRule": "SET3"
+    });
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["totalPrice"], 500);
+    assert_eq!(body["discountAmount"], 100);
+    assert_eq!(body["pricingRule"], "set3");
+    Ok(())
+}
+
+#[tokio::test]
+async fn complete_order_409_for_cancelled_order_until_restored() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(order(1, OrderStatus::Cancelled));
+    }));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/1/complete")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "invalid-transition");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/1/restore")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/orders/1/complete")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "completed");
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_station_201_shortens_wait_time_of_assigned_flavor() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(waiting_order_with_items(
+            1,
+            vec![Item {
+                flavor: tsubuan(),
+                quantity: 9,
+            }],
+        ));
+    }));
+
+    let request = serde_json::json!({ "name": "鉄板A", "plateCount": 2 });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/stations")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["id"], 1);
+
+    let request = serde_json::json!({
+        "cookingTimeMinutes": 15,
+        "quantityPerBatch": 9,
+        "stationId": 1
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::put("/api/staff/flavors/tsubuan")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+    let response = app
+        .oneshot(Request::get("/api/wait-times").body(Body::empty())?)
+        .await?;
+
+    // The order takes one plate and the next piece goes on the second one.
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["flavors"][0]["waitMinutes"], 15);
+    assert_eq!(body["waitTimes"]["tsubuan"], 15);
+    Ok(())
+}
+
+#[tokio::test]
+async fn finish_batch_200_makes_cooking_order_ready() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(waiting_order_with_items(
+            1,
+            vec![Item {
+                flavor: tsubuan(),
+                quantity: 3,
+            }],
+        ));
+    }));
+
+    let request = serde_json::json!({ "flavor": "tsubuan" });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/batches")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["quantity"], 9);
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/orders/1").body(Body::empty())?)
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "cooking");
+    assert_eq!(body["estimatedWaitMinutes"], 15);
+    assert!(body["estimate"]["expectedReadyAt"].is_string());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/batches/1/finish")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["newlyReadyOrders"], serde_json::json!([1]));
+
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/batches/1/finish")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_timer_alerts_once_per_escalation_level() -> anyhow::Result<()> {
+    let started_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.batches.push(Batch {
+            id: 1,
+            flavor: tsubuan(),
+            quantity: 9,
+            started_at,
+            finished_at: None,
+        });
+    });
+    let mut events = registry.subscribe_staff_events();
+
+    let due = started_at + Duration::minutes(15);
+    assert_eq!(registry.check_batch_timers(due, 3).await?.len(), 1);
+    assert!(registry.check_batch_timers(due, 3).await?.is_empty());
+    let escalated = registry
+        .check_batch_timers(due + Duration::minutes(3), 3)
+        .await?;
+    assert_eq!(escalated[0].escalation_level, 1);
+
+    let StaffEvent::BatchDue { alert, .. } = events.recv().await? else {
+        panic!("expected a batch alert");
+    };
+    assert_eq!((alert.batch.id, alert.escalation_level), (1, 0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn no_show_policy_expires_uncollected_order_and_returns_its_stock() -> anyhow::Result<()> {
+    let now = Utc::now();
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(Order {
+            status: OrderStatus::Ready,
+            ready_at: Some(now - Duration::minutes(20)),
+            ..waiting_order_with_items(
+                1,
+                vec![Item {
+                    flavor: tsubuan(),
+                    quantity: 2,
+                }],
+            )
+        });
+    })
+    .with_no_show_settings(NoShowSettings {
+        reminder_minutes: 10,
+        expire_minutes: Some(30),
+    });
+    let mut events = registry.subscribe_staff_events();
+    let app = make_router(registry.clone());
+
+    assert_eq!(registry.chase_no_shows(now).await?.len(), 1);
+    assert!(registry.chase_no_shows(now).await?.is_empty());
+    let StaffEvent::OrderNoShow { notice, .. } = events.recv().await? else {
+        panic!("expected a no-show notice");
+    };
+    assert_eq!((notice.order_id, notice.step), (1, NoShowStep::Reminded));
+
+    let expired = registry.chase_no_shows(now + Duration::minutes(10)).await?;
+    assert_eq!(expired[0].step, NoShowStep::Expired);
+    assert_eq!(registry.snapshot().await?.stock_of(&tsubuan()), 2);
+
+    let response = app
+        .oneshot(
+            Request::get("/api/staff/orders?status=expired")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body[0]["id"], 1);
+    assert_eq!(body[0]["status"], "expired");
+    assert!(body[0]["noShowAt"].is_string());
+    assert!(body[0]["expiredAt"].is_string());
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_production_plan_200_recommends_batches_for_waiting_orders() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(waiting_order_with_items(
+            1,
+            vec![Item {
+                flavor: tsubuan(),
+                quantity: 10,
+            }],
+        ));
+    }));
+
+    let response = app
+        .oneshot(
+            Request::get("/api/staff/production/plan")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    let batches = body["batches"].as_array().expect("batches array");
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[1]["startInMinutes"], 15);
+    assert_eq!(body["totalWaitMinutes"], 30);
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_wait_times_200_blends_recent_throughput_when_configured() -> anyhow::Result<()> {
+    let now = Utc::now();
+    let registry = registry_with_snapshot(|snapshot| {
+        for (id, ready_minutes_ago) in [(1, 20), (2, 10), (3, 5)] {
+            snapshot.orders.push(Order {
+                ordered_at: now - Duration::minutes(30),
+                ready_at: Some(now - Duration::minutes(ready_minutes_ago)),
+                ..waiting_order_with_items(
+                    id,
+                    vec![Item {
+                        flavor: tsubuan(),
+                        quantity: 9,
+                    }],
+                )
+            });
+            snapshot.orders.last_mut().expect("pushed").status = OrderStatus::Completed;
+        }
+    })
+    .with_wait_time_settings(WaitTimeSettings {
+        estimator: WaitTimeEstimator::Blended,
+        ..Default::default()
+    });
+    let app = make_router(registry);
+
+    let response = app
+        .oneshot(Request::get("/api/wait-times").body(Body::empty())?)
+        .await?;
+
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["estimator"], "blended");
+    let tsubuan = &body["flavors"][0];
+    // 27 pieces in 30 minutes: the next piece takes 2 minutes instead of a 15-minute batch.
+    assert_eq!(tsubuan["batchModelWaitMinutes"], 15);
+    assert_eq!(tsubuan["historyWaitMinutes"], 2);
+    assert_eq!(tsubuan["waitMinutes"], 9);
+    Ok(())
+}
+
+#[tokio::test]
+async fn discard_stock_201_shows_up_in_waste_report() -> anyhow::Result<()> {
+    let app = make_router(registry_with_snapshot(|snapshot| {
+        snapshot.unallocated_stock.insert(tsubuan(), 5);
+    }));
+    let request = serde_json::json!({
+        "flavor": "tsubuan",
+        "quantity": 2,
+        "reason": "dropped",
+        "note": "床に落とした"
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/stock/discard")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["delta"], -2);
+
+    let too_many = serde_json::json!({ "flavor": "tsubuan", "quantity": 4, "reason": "burnt" });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/stock/discard")
+                .bearer()
+                .application_json()
+                .body(Body::from(too_many.to_string()))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+
+    let response = app
+        .oneshot(
+            Request::get("/api/staff/stock/waste")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["totalQuantity"], 2);
+    assert_eq!(body["byFlavor"][0]["flavor"], "tsubuan");
+    assert_eq!(body["byFlavor"][0]["byReason"][0]["reason"], "dropped");
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_order_items_200_returns_stock_of_ready_order() -> anyhow::Result<()> {
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(Order {
+            status: OrderStatus::Ready,
+            ready_at: Some(Utc::now()),
+            ..waiting_order_with_items(
+                1,
+                vec![Item {
+                    flavor: tsubuan(),
+                    quantity: 1,
+                }],
+            )
+        });
+    });
+    let app = make_router(registry.clone());
+    let request = serde_json::json!({ "items": [{ "flavor": "custard", "quantity": 2 }] });
+
+    let response = app
+        .oneshot(
+            Request::patch("/api/staff/orders/1/items")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "waiting");
+    assert_eq!(body["items"][0]["flavor"], "custard");
+    let snapshot = registry.snapshot().await?;
+    assert_eq!(snapshot.stock_of(&tsubuan()), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn hand_over_items_200_keeps_outstanding_part_queued() -> anyhow::Result<()> {
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.unallocated_stock.insert(tsubuan(), 4);
+        snapshot.orders.push(waiting_order_with_items(
+            1,
+            vec![
+                Item {
+                    flavor: tsubuan(),
+                    quantity: 4,
+                },
+                Item {
+                    flavor: Flavor::new("custard"),
+                    quantity: 6,
+                },
+            ],
+        ));
+    });
+    let app = make_router(registry);
+    let request = serde_json::json!({});
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/1/handover")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "waiting");
+    assert_eq!(body["handedOver"][0]["quantity"], 4);
+
+    let response = app
+        .oneshot(Request::get("/api/orders/1").body(Body::empty())?)
+        .await?;
+
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["isPartiallyHandedOver"], true);
+    assert_eq!(body["items"][0]["handedOverQuantity"], 4);
+    assert_eq!(body["items"][1]["handedOverQuantity"], 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_staff_order_201_skips_display_numbers_in_use() -> anyhow::Result<()> {
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.orders.push(order(1, OrderStatus::Waiting));
+        snapshot.orders.push(Order {
+            display_number: DisplayOrderNumber::new("99"),
+            ..order(2, OrderStatus::Completed)
+        });
+    });
+    let app = make_router(registry);
+
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 1 }]
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["displayNumber"], "02");
+
+    let response = app
+        .oneshot(Request::get("/api/orders/by-number/%2302").body(Body::empty())?)
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["id"], 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn close_business_day_200_locks_the_day() -> anyhow::Result<()> {
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.unallocated_stock.insert(tsubuan(), 2);
+        snapshot.orders.push(Order {
+            business_day_id: Some(1),
+            ..order(1, OrderStatus::Completed)
+        });
+        snapshot.orders.push(Order {
+            business_day_id: Some(1),
+            ..order(2, OrderStatus::Waiting)
+        });
+    });
+    let app = make_router(registry);
+    let request = serde_json::json!({ "stockDisposition": "writeOff" });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/business-days/close")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["completedCount"], 1);
+    assert_eq!(body["revenue"]["cancelledCount"], 1);
+    assert_eq!(body["waste"]["totalQuantity"], 2);
+    assert_eq!(body["stockDisposition"], "writeOff");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/staff/orders/2/history")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["after"], "cancelled");
+    assert_eq!(body[0]["reason"], "businessDayClosed");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/1/reopen")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "business-day-closed");
+
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 1 }]
+    });
+    let response = app
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "business-day-not-open");
+    Ok(())
+}
+
+#[tokio::test]
+async fn orders_of_earlier_days_stay_out_of_mutations_but_keep_their_ids() -> anyhow::Result<()> {
+    let registry = registry_with_snapshot(|snapshot| {
+        let opened_at = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        snapshot.business_days.insert(
+            0,
+            BusinessDay {
+                id: 2,
+                date: store_date(opened_at),
+                opened_at,
+                closed_at: Some(opened_at + Duration::hours(8)),
+                stock_disposition: None,
+            },
+        );
+        snapshot.orders.push(Order {
+            business_day_id: Some(2),
+            ordered_at: opened_at,
+            ..order(5, OrderStatus::Completed)
+        });
+    });
+    let app = make_router(registry);
+
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 1 }]
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["id"], 6);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders/5/reopen")
+                .bearer()
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["type"], "business-day-closed");
+
+    let response = app
+        .oneshot(Request::get("/api/orders/5").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "completed");
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_staff_order_201_holds_pre_order_in_its_pickup_slot() -> anyhow::Result<()> {
+    let settings = PreOrderSettings::default();
+    let pickup_at = settings.slot_start(Utc::now() + Duration::hours(2));
+    let registry = registry_with_snapshot(|snapshot| {
+        snapshot.business_days[0].date = store_date(pickup_at);
+        snapshot.unallocated_stock.insert(tsubuan(), 9);
+    });
+    let app = make_router(registry.clone());
+
+    let request = serde_json::json!({
+        "items": [{ "flavor": "tsubuan", "quantity": 2 }],
+        "pickupAt": pickup_at
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/api/staff/orders")
+                .bearer()
+                .application_json()
+                .body(Body::from(request.to_string()))?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["status"], "waiting");
+    assert_eq!(
+        body["pickupAt"]
+            .as_str()
+            .map(str::parse::<chrono::DateTime<Utc>>),
+        Some(Ok(pickup_at))
+    );
+    // The stock stays with walk-ins until the lead time before pickup.
+    assert_eq!(registry.snapshot().await?.stock_of(&tsubuan()), 9);
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/orders/display").body(Body::empty())?)
+        .await?;
+    let body = deserialize_json(response).await?;
+    assert_eq!(body["waiting"].as_array().map(Vec::len), Some(0));
+
+    let response = app
+        .oneshot(Request::get("/api/pickup-slots").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+    let body = deserialize_json(response).await?;
+    let slot = body
+        .as_array()
+        .and_then(|slots| {
+            slots.iter().find(|slot| {
+                slot["startsAt"]
+                    .as_str()
+                    .map(str::parse::<chrono::DateTime<Utc>>)
+                    == Some(Ok(pickup_at))
+            })
+        })
+        .cloned()
+        .unwrap_or_default();
+    assert_eq!(slot["reserved"], 2);
+    assert_eq!(slot["remaining"], settings.slot_capacity - 2);
+    Ok(())
+}
@@ -3,36 +3,107 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use axum::{Router, body, http::request::Builder};
 use bot_sdk_line::messaging_api_line::models::Message;
-use chrono::{TimeZone, Utc};
+use chrono::{Duration, TimeZone, Utc};
 use tokio::sync::Mutex;
 
 use taiyaq_backend::api::route::routes;
 use taiyaq_backend::app::AppRegistry;
+use taiyaq_backend::domain::availability::store_date;
+use taiyaq_backend::domain::business_day::BusinessDay;
 use taiyaq_backend::domain::notification::NotificationDeliveryLog;
-use taiyaq_backend::domain::snapshot::{Item, Notify, Order, OrderStatus, OrderSystemSnapshot};
+use taiyaq_backend::domain::order_event::{EventFilter, OrderEvent};
+use taiyaq_backend::domain::order_number::DisplayOrderNumber;
+use taiyaq_backend::domain::snapshot::{
+    Flavor, FlavorConfig, Item, MenuFlavor, Notify, Order, OrderStatus, OrderSystemSnapshot,
+};
+use taiyaq_backend::domain::snapshot_change::{self, SnapshotChange};
 use taiyaq_backend::port::line_reply::LineReplySender;
 use taiyaq_backend::port::notification_log::NotificationLog;
 use taiyaq_backend::port::notifier::Notifier;
 use taiyaq_backend::port::order_repository::OrderRepository;
+use taiyaq_backend::port::staff_alert::StaffAlertSender;
 
 pub fn make_router(registry: AppRegistry) -> Router {
     routes(registry)
 }
 
 pub fn registry_with_snapshot(arrange: impl FnOnce(&mut OrderSystemSnapshot)) -> AppRegistry {
-    let mut snapshot = OrderSystemSnapshot::default();
+    registry_with_repository(FakeRepository::new(arranged_snapshot(arrange)))
+}
+
+// Storage that can still be read at startup but refuses every write.
+pub fn registry_with_failing_storage(
+    arrange: impl FnOnce(&mut OrderSystemSnapshot),
+) -> AppRegistry {
+    registry_with_repository(FakeRepository {
+        fail_writes: true,
+        ..FakeRepository::new(arranged_snapshot(arrange))
+    })
+}
+
+fn arranged_snapshot(arrange: impl FnOnce(&mut OrderSystemSnapshot)) -> OrderSystemSnapshot {
+    let mut snapshot = snapshot_with_seeded_menu();
+    // Tests run during business hours unless they close the day themselves.
+    let opened_at = Utc::now() - Duration::hours(1);
+    snapshot.business_days.push(BusinessDay {
+        id: 1,
+        date: store_date(opened_at),
+        opened_at,
+        closed_at: None,
+        stock_disposition: None,
+    });
     arrange(&mut snapshot);
-    let repository = Arc::new(FakeRepository::new(snapshot));
+    snapshot
+}
+
+fn registry_with_repository(repository: FakeRepository) -> AppRegistry {
+    let repository = Arc::new(repository);
     let notifier = Arc::new(FakeNotifier);
     AppRegistry::new_with_ports(
         repository,
         notifier.clone(),
+        notifier.clone(),
         notifier,
         "test-token".to_owned(),
         "test-line-secret".to_owned(),
     )
 }
 
+pub fn tsubuan() -> Flavor {
+    Flavor::new("tsubuan")
+}
+
+// Mirrors the catalog seeded by the migrations, priced as staff would through the menu API.
+fn snapshot_with_seeded_menu() -> OrderSystemSnapshot {
+    let mut snapshot = OrderSystemSnapshot::default();
+    for (sort_order, (id, display_name, quantity_per_batch)) in [
+        ("tsubuan", "つぶあん", 9),
+        ("custard", "カスタード", 9),
+        ("kurikinton", "栗きんとん", 2),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        snapshot.add_flavor(
+            MenuFlavor {
+                id: Flavor::new(id),
+                display_name: display_name.to_owned(),
+                sort_order: sort_order as i64 + 1,
+                is_active: true,
+                unit_price: 200,
+            },
+            FlavorConfig {
+                cooking_time_minutes: 15,
+                quantity_per_batch,
+                daily_limit: None,
+                is_sold_out: false,
+                station_id: None,
+            },
+        );
+    }
+    snapshot
+}
+
 pub fn order(id: u32, status: OrderStatus) -> Order {
     Order {
         id,
@@ -42,7 +113,19 @@ pub fn order(id: u32, status: OrderStatus) -> Order {
         ready_at: None,
         completed_at: None,
         notify: Default::default(),
-        is_priority: false,
+        priority: Default::default(),
+        total_price: 0,
+        pricing_rule: None,
+        discount_amount: 0,
+        payment_method: None,
+        refunded_at: None,
+        handed_over: Vec::new(),
+        display_number: DisplayOrderNumber::new(format!("{id:02}")),
+        business_day_id: None,
+        pickup_at: None,
+        release_at: None,
+        no_show_at: None,
+        expired_at: None,
     }
 }
 
@@ -77,14 +160,18 @@ impl TestRequestExt for Builder {
 
 struct FakeRepository {
     snapshot: Mutex<OrderSystemSnapshot>,
+    events: Mutex<Vec<OrderEvent>>,
     notification_logs: Mutex<Vec<NotificationDeliveryLog>>,
+    fail_writes: bool,
 }
 
 impl FakeRepository {
     fn new(snapshot: OrderSystemSnapshot) -> Self {
         Self {
             snapshot: Mutex::new(snapshot),
+            events: Mutex::new(Vec::new()),
             notification_logs: Mutex::new(Vec::new()),
+            fail_writes: false,
         }
     }
 }
@@ -95,10 +182,40 @@ impl OrderRepository for FakeRepository {
         Ok(self.snapshot.lock().await.clone())
     }
 
-    async fn replace_snapshot(&self, snapshot: &OrderSystemSnapshot) -> anyhow::Result<()> {
-        *self.snapshot.lock().await = snapshot.clone();
+    async fn apply_changes(
+        &self,
+        changes: &[SnapshotChange],
+        events: &[OrderEvent],
+    ) -> anyhow::Result<()> {
+        if self.fail_writes {
+            anyhow::bail!("storage is unavailable");
+        }
+        snapshot_change::apply_changes(&mut *self.snapshot.lock().await, changes);
+        self.events.lock().await.extend_from_slice(events);
         Ok(())
     }
+
+    async fn order_history(&self, order_id: u32) -> anyhow::Result<Vec<OrderEvent>> {
+        Ok(self
+            .events
+            .lock()
+            .await
+            .iter()
+            .filter(|event| event.order_id == Some(order_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn events(&self, filter: EventFilter) -> anyhow::Result<Vec<OrderEvent>> {
+        Ok(self
+            .events
+            .lock()
+            .await
+            .iter()
+            .filter(|event| filter.matches(event))
+            .cloned()
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -127,3 +244,10 @@ impl LineReplySender for FakeNotifier {
         Ok(())
     }
 }
+
+#[async_trait]
+impl StaffAlertSender for FakeNotifier {
+    async fn alert_staff(&self, _message: String) -> anyhow::Result<()> {
+        Ok(())
+    }
+}